@@ -62,14 +62,17 @@ fn main() {
         NewCategory {
             name: "Electronics".to_string(),
             description: Some("Electronic devices and components".to_string()),
+            parent_id: None,
         },
         NewCategory {
             name: "Office Supplies".to_string(),
             description: Some("Supplies for office use".to_string()),
+            parent_id: None,
         },
         NewCategory {
             name: "Furniture".to_string(),
             description: Some("Office and home furniture".to_string()),
+            parent_id: None,
         },
     ];
     
@@ -88,5 +91,35 @@ fn main() {
         }
     }
     
+    // Create some common units of measure
+    use inventory_manager::models::unit::{NewUnitOfMeasure, UnitOfMeasure};
+    
+    let units = vec![
+        ("ea", "Each", false),
+        ("m", "Metre", true),
+        ("kg", "Kilogram", true),
+    ];
+    
+    for (code, name, fractional) in units {
+        let new_unit = NewUnitOfMeasure {
+            code: code.to_string(),
+            name: name.to_string(),
+            fractional,
+        };
+        
+        match UnitOfMeasure::create(&pool, new_unit) {
+            Ok(unit_id) => {
+                println!("Unit '{}' created with ID: {}", code, unit_id);
+            },
+            Err(e) => {
+                if e.to_string().contains("UNIQUE constraint failed") {
+                    println!("Unit '{}' already exists", code);
+                } else {
+                    eprintln!("Failed to create unit '{}': {}", code, e);
+                }
+            }
+        }
+    }
+    
     println!("Database initialization completed");
 }
\ No newline at end of file
@@ -1,11 +1,8 @@
-use clap::{App, AppSettings, Arg, SubCommand};
 use reqwest::{Client, header};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 use std::io::{self, Write};
 use std::error::Error;
 use std::fmt;
-use tokio;
 use colored::*;
 use prettytable::{Table, Row, Cell, format};
 use dialoguer::{Input, Password, Select, Confirm};
@@ -39,7 +36,7 @@ struct InventorySummary {
 #[derive(Debug, Serialize, Deserialize)]
 struct CategorySummary {
     id: i64,
-    name: String,
+    path: String,
     items_count: i64,
     total_quantity: i64,
     total_value: f64,
@@ -85,6 +82,8 @@ struct Transaction {
     transaction_type: String,
     notes: Option<String>,
     transaction_date: String,
+    quantity_before: Option<i32>,
+    quantity_after: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -95,6 +94,48 @@ struct NewTransaction {
     notes: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct LedgerEntry {
+    transaction_date: String,
+    transaction_type: String,
+    change: i32,
+    balance: i32,
+    username: Option<String>,
+    reference: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ItemLedger {
+    item_name: String,
+    start_date: String,
+    end_date: String,
+    opening_balance: i32,
+    total_in: i64,
+    total_out: i64,
+    closing_balance: i32,
+    entries: Vec<LedgerEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UserActivity {
+    username: String,
+    transactions_count: i64,
+    items_count: i64,
+    added: i64,
+    removed: i64,
+    adjusted: i64,
+    value_added: f64,
+    value_removed: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StreamEvent {
+    id: i64,
+    event_type: String,
+    payload: serde_json::Value,
+    created_at: String,
+}
+
 struct CliError(String);
 
 impl fmt::Display for CliError {
@@ -142,7 +183,7 @@ impl InventoryCli {
 
         // Add a timeout to the request to prevent hanging
         let response = match self.client
-            .post(&format!("{}/api/auth/login", self.base_url))
+            .post(format!("{}/api/auth/login", self.base_url))
             .json(&login_request)
             .timeout(std::time::Duration::from_secs(5))
             .send()
@@ -198,7 +239,7 @@ impl InventoryCli {
     async fn get_inventory_summary(&self) -> CliResult<InventorySummary> {
         let headers = self.get_auth_header()?;
         let response = self.client
-            .get(&format!("{}/api/reports/inventory-summary", self.base_url))
+            .get(format!("{}/api/reports/inventory-summary", self.base_url))
             .headers(headers)
             .send()
             .await?;
@@ -215,7 +256,7 @@ impl InventoryCli {
     async fn get_category_summary(&self) -> CliResult<Vec<CategorySummary>> {
         let headers = self.get_auth_header()?;
         let response = self.client
-            .get(&format!("{}/api/reports/category-summary", self.base_url))
+            .get(format!("{}/api/reports/category-summary", self.base_url))
             .headers(headers)
             .send()
             .await?;
@@ -232,7 +273,7 @@ impl InventoryCli {
     async fn list_categories(&self) -> CliResult<Vec<Category>> {
         let headers = self.get_auth_header()?;
         let response = self.client
-            .get(&format!("{}/api/categories", self.base_url))
+            .get(format!("{}/api/categories", self.base_url))
             .headers(headers)
             .send()
             .await?;
@@ -255,7 +296,7 @@ impl InventoryCli {
         };
 
         let response = self.client
-            .post(&format!("{}/api/categories", self.base_url))
+            .post(format!("{}/api/categories", self.base_url))
             .headers(headers)
             .json(&category)
             .send()
@@ -273,7 +314,7 @@ impl InventoryCli {
     async fn list_inventory(&self) -> CliResult<Vec<InventoryItem>> {
         let headers = self.get_auth_header()?;
         let response = self.client
-            .get(&format!("{}/api/inventory", self.base_url))
+            .get(format!("{}/api/inventory", self.base_url))
             .headers(headers)
             .send()
             .await?;
@@ -287,10 +328,11 @@ impl InventoryCli {
         }
     }
 
+    #[allow(dead_code)]
     async fn get_inventory_item(&self, id: i64) -> CliResult<InventoryItem> {
         let headers = self.get_auth_header()?;
         let response = self.client
-            .get(&format!("{}/api/inventory/{}", self.base_url, id))
+            .get(format!("{}/api/inventory/{}", self.base_url, id))
             .headers(headers)
             .send()
             .await?;
@@ -307,7 +349,7 @@ impl InventoryCli {
     async fn create_inventory_item(&self, item: NewInventoryItem) -> CliResult<InventoryItem> {
         let headers = self.get_auth_header()?;
         let response = self.client
-            .post(&format!("{}/api/inventory", self.base_url))
+            .post(format!("{}/api/inventory", self.base_url))
             .headers(headers)
             .json(&item)
             .send()
@@ -325,7 +367,7 @@ impl InventoryCli {
     async fn create_transaction(&self, transaction: NewTransaction) -> CliResult<Transaction> {
         let headers = self.get_auth_header()?;
         let response = self.client
-            .post(&format!("{}/api/transactions", self.base_url))
+            .post(format!("{}/api/transactions", self.base_url))
             .headers(headers)
             .json(&transaction)
             .send()
@@ -343,7 +385,7 @@ impl InventoryCli {
     async fn list_recent_transactions(&self) -> CliResult<Vec<Transaction>> {
         let headers = self.get_auth_header()?;
         let response = self.client
-            .get(&format!("{}/api/transactions/recent", self.base_url))
+            .get(format!("{}/api/transactions/recent", self.base_url))
             .headers(headers)
             .send()
             .await?;
@@ -356,6 +398,58 @@ impl InventoryCli {
             Err(Box::new(CliError(error.error)))
         }
     }
+
+    async fn get_item_ledger(&self, item_id: i64, range: &[(&str, String)]) -> CliResult<ItemLedger> {
+        let headers = self.get_auth_header()?;
+        let response = self.client
+            .get(format!("{}/api/reports/ledger/{}", self.base_url, item_id))
+            .headers(headers)
+            .query(range)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            let ledger: ItemLedger = response.json().await?;
+            Ok(ledger)
+        } else {
+            let error: ErrorResponse = response.json().await?;
+            Err(Box::new(CliError(error.error)))
+        }
+    }
+
+    async fn get_user_activity(&self, range: &[(&str, String)]) -> CliResult<Vec<UserActivity>> {
+        let headers = self.get_auth_header()?;
+        let response = self.client
+            .get(format!("{}/api/reports/user-activity", self.base_url))
+            .headers(headers)
+            .query(range)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            let activity: Vec<UserActivity> = response.json().await?;
+            Ok(activity)
+        } else {
+            let error: ErrorResponse = response.json().await?;
+            Err(Box::new(CliError(error.error)))
+        }
+    }
+
+    async fn open_event_stream(&self) -> CliResult<reqwest::Response> {
+        let headers = self.get_auth_header()?;
+        let response = self.client
+            .get(format!("{}/api/events", self.base_url))
+            .headers(headers)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            let error: ErrorResponse = response.json().await?;
+            Err(Box::new(CliError(error.error)))
+        }
+    }
 }
 
 async fn interactive_login(cli: &mut InventoryCli) -> CliResult<()> {
@@ -703,6 +797,7 @@ async fn list_transactions(cli: &InventoryCli) -> CliResult<()> {
         Cell::new("Item ID").style_spec("Fb"),
         Cell::new("Type").style_spec("Fb"),
         Cell::new("Quantity").style_spec("Fb"),
+        Cell::new("Stock").style_spec("Fb"),
         Cell::new("Notes").style_spec("Fb"),
     ]));
     
@@ -720,6 +815,10 @@ async fn list_transactions(cli: &InventoryCli) -> CliResult<()> {
             Cell::new(&transaction.item_id.to_string()),
             type_cell,
             Cell::new(&transaction.quantity.to_string()),
+            Cell::new(&match (transaction.quantity_before, transaction.quantity_after) {
+                (Some(before), Some(after)) => format!("{} -> {}", before, after),
+                _ => "-".to_string(),
+            }),
             Cell::new(transaction.notes.as_deref().unwrap_or("-")),
         ]));
     }
@@ -729,6 +828,221 @@ async fn list_transactions(cli: &InventoryCli) -> CliResult<()> {
     Ok(())
 }
 
+/// Asks for an optional date range; blank answers leave the server's defaults.
+fn prompt_date_range() -> CliResult<Vec<(&'static str, String)>> {
+    let mut range = Vec::new();
+    
+    for (name, prompt) in [("start_date", "Start date YYYY-MM-DD (optional)"), ("end_date", "End date YYYY-MM-DD (optional)")] {
+        let value = Input::<String>::new()
+            .with_prompt(prompt)
+            .allow_empty(true)
+            .interact()?;
+        
+        if !value.is_empty() {
+            range.push((name, value));
+        }
+    }
+    
+    Ok(range)
+}
+
+async fn show_item_ledger(cli: &InventoryCli) -> CliResult<()> {
+    println!("\n{}", "=== Item Ledger ===".green().bold());
+    
+    let items = cli.list_inventory().await?;
+    
+    if items.is_empty() {
+        println!("No inventory items available.");
+        return Ok(());
+    }
+    
+    let item_names: Vec<String> = items.iter()
+        .map(|i| format!("{} (Qty: {})", i.name, i.quantity))
+        .collect();
+    
+    let item_index = Select::new()
+        .with_prompt("Select item")
+        .items(&item_names)
+        .default(0)
+        .interact()?;
+    
+    let range = prompt_date_range()?;
+    let ledger = cli.get_item_ledger(items[item_index].id.unwrap(), &range).await?;
+    
+    println!(
+        "{} from {} to {}",
+        ledger.item_name.bold(),
+        ledger.start_date,
+        ledger.end_date
+    );
+    
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("Date").style_spec("Fb"),
+        Cell::new("Type").style_spec("Fb"),
+        Cell::new("Change").style_spec("Fb"),
+        Cell::new("Balance").style_spec("Fb"),
+        Cell::new("User").style_spec("Fb"),
+        Cell::new("Reference").style_spec("Fb"),
+    ]));
+    
+    table.add_row(Row::new(vec![
+        Cell::new(""),
+        Cell::new("Opening balance").style_spec("Fb"),
+        Cell::new(""),
+        Cell::new(&ledger.opening_balance.to_string()).style_spec("Fb"),
+        Cell::new(""),
+        Cell::new(""),
+    ]));
+    
+    for entry in &ledger.entries {
+        let change_cell = if entry.change < 0 {
+            Cell::new(&entry.change.to_string()).style_spec("Fr")
+        } else {
+            Cell::new(&format!("+{}", entry.change)).style_spec("Fg")
+        };
+        
+        table.add_row(Row::new(vec![
+            Cell::new(&entry.transaction_date),
+            Cell::new(&entry.transaction_type),
+            change_cell,
+            Cell::new(&entry.balance.to_string()),
+            Cell::new(entry.username.as_deref().unwrap_or("-")),
+            Cell::new(entry.reference.as_deref().unwrap_or("-")),
+        ]));
+    }
+    
+    table.add_row(Row::new(vec![
+        Cell::new(""),
+        Cell::new("Closing balance").style_spec("Fb"),
+        Cell::new(&format!("+{} / -{}", ledger.total_in, ledger.total_out)),
+        Cell::new(&ledger.closing_balance.to_string()).style_spec("Fb"),
+        Cell::new(""),
+        Cell::new(""),
+    ]));
+    
+    table.printstd();
+    
+    Ok(())
+}
+
+async fn show_user_activity(cli: &InventoryCli) -> CliResult<()> {
+    println!("\n{}", "=== User Activity ===".green().bold());
+    
+    let range = prompt_date_range()?;
+    let activity = cli.get_user_activity(&range).await?;
+    
+    if activity.is_empty() {
+        println!("No activity in this period.");
+        return Ok(());
+    }
+    
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    
+    table.add_row(Row::new(vec![
+        Cell::new("User").style_spec("Fb"),
+        Cell::new("Transactions").style_spec("Fb"),
+        Cell::new("Items").style_spec("Fb"),
+        Cell::new("Added").style_spec("Fb"),
+        Cell::new("Removed").style_spec("Fb"),
+        Cell::new("Adjusted").style_spec("Fb"),
+        Cell::new("Value In").style_spec("Fb"),
+        Cell::new("Value Out").style_spec("Fb"),
+    ]));
+    
+    for user in activity {
+        table.add_row(Row::new(vec![
+            Cell::new(&user.username),
+            Cell::new(&user.transactions_count.to_string()),
+            Cell::new(&user.items_count.to_string()),
+            Cell::new(&user.added.to_string()),
+            Cell::new(&user.removed.to_string()),
+            Cell::new(&user.adjusted.to_string()),
+            Cell::new(&format!("${:.2}", user.value_added)),
+            Cell::new(&format!("${:.2}", user.value_removed)),
+        ]));
+    }
+    
+    table.printstd();
+    
+    Ok(())
+}
+
+fn describe_event(event: &StreamEvent) -> ColoredString {
+    let payload = &event.payload;
+    let name = payload["name"].as_str().unwrap_or("-");
+    
+    match event.event_type.as_str() {
+        "transaction.created" => format!(
+            "{} of {} for item {}",
+            payload["transaction_type"].as_str().unwrap_or("-"),
+            payload["quantity"],
+            payload["item_id"]
+        )
+        .normal(),
+        "item.low_stock" => format!(
+            "{} is low on stock: {} left (reorder point {})",
+            name, payload["quantity"], payload["reorder_point"]
+        )
+        .red()
+        .bold(),
+        "item.deleted" => format!("Item {} deleted", payload["id"]).yellow(),
+        _ => format!("{} (quantity {})", name, payload["quantity"]).normal(),
+    }
+}
+
+/// Prints events as the server commits them, until Ctrl+C.
+async fn watch_events(cli: &InventoryCli) -> CliResult<()> {
+    println!("\n{}", "=== Live Activity (Ctrl+C to stop) ===".green().bold());
+    
+    let mut response = cli.open_event_stream().await?;
+    let mut buffer = String::new();
+    
+    loop {
+        let chunk = tokio::select! {
+            chunk = response.chunk() => chunk?,
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        };
+        
+        let chunk = match chunk {
+            Some(chunk) => chunk,
+            None => {
+                println!("{}", "Event stream closed by the server".yellow());
+                return Ok(());
+            }
+        };
+        
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        
+        // Events are separated by a blank line; keep any partial event for the next chunk
+        while let Some(end) = buffer.find("\n\n") {
+            let message: String = buffer.drain(..end + 2).collect();
+            
+            let data = message
+                .lines()
+                .filter_map(|line| line.strip_prefix("data: "))
+                .collect::<Vec<_>>()
+                .join("\n");
+            
+            if data.is_empty() {
+                continue;
+            }
+            
+            if let Ok(event) = serde_json::from_str::<StreamEvent>(&data) {
+                println!(
+                    "{} {:<20} {}",
+                    event.created_at.dimmed(),
+                    event.event_type.cyan(),
+                    describe_event(&event)
+                );
+            }
+        }
+    }
+}
+
 async fn show_category_summary(cli: &InventoryCli) -> CliResult<()> {
     println!("\n{}", "=== Category Summary ===".green().bold());
     
@@ -751,7 +1065,7 @@ async fn show_category_summary(cli: &InventoryCli) -> CliResult<()> {
     
     for summary in summaries {
         table.add_row(Row::new(vec![
-            Cell::new(&summary.name),
+            Cell::new(&summary.path),
             Cell::new(&summary.items_count.to_string()),
             Cell::new(&summary.total_quantity.to_string()),
             Cell::new(&format!("${:.2}", summary.total_value)),
@@ -773,6 +1087,9 @@ async fn main_menu(cli: &mut InventoryCli) -> CliResult<bool> {
         "Add Transaction",
         "List Recent Transactions",
         "Category Summary",
+        "Item Ledger",
+        "User Activity",
+        "Live Activity",
         "Exit",
     ];
     
@@ -808,6 +1125,15 @@ async fn main_menu(cli: &mut InventoryCli) -> CliResult<bool> {
             show_category_summary(cli).await?;
         },
         8 => {
+            show_item_ledger(cli).await?;
+        },
+        9 => {
+            show_user_activity(cli).await?;
+        },
+        10 => {
+            watch_events(cli).await?;
+        },
+        11 => {
             println!("Exiting...");
             return Ok(false);
         },
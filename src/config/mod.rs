@@ -8,6 +8,14 @@ pub struct Config {
     pub jwt_expiration: i64,
     pub server_host: String,
     pub server_port: u16,
+    /// SMTP relay for outgoing mail; mail is disabled when unset.
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_from: String,
+    /// Channels low-stock alerts are delivered through: `log`, `webhook`, `email`.
+    pub alert_channels: Vec<String>,
+    /// Directory scheduled reports are written to, one subdirectory per schedule.
+    pub report_archive_dir: String,
 }
 
 impl Config {
@@ -33,12 +41,37 @@ impl Config {
             .parse::<u16>()
             .unwrap_or(8080);
         
+        let smtp_host = env::var("SMTP_HOST").ok().filter(|host| !host.is_empty());
+        
+        let smtp_port = env::var("SMTP_PORT")
+            .unwrap_or_else(|_| "25".to_string())
+            .parse::<u16>()
+            .unwrap_or(25);
+        
+        let smtp_from = env::var("SMTP_FROM")
+            .unwrap_or_else(|_| "inventory@localhost".to_string());
+        
+        let alert_channels = env::var("ALERT_CHANNELS")
+            .unwrap_or_else(|_| "log,webhook".to_string())
+            .split(',')
+            .map(|channel| channel.trim().to_lowercase())
+            .filter(|channel| !channel.is_empty())
+            .collect();
+        
+        let report_archive_dir = env::var("REPORT_ARCHIVE_DIR")
+            .unwrap_or_else(|_| "reports".to_string());
+        
         Self {
             database_url,
             jwt_secret,
             jwt_expiration,
             server_host,
             server_port,
+            smtp_host,
+            smtp_port,
+            smtp_from,
+            alert_channels,
+            report_archive_dir,
         }
     }
 }
\ No newline at end of file
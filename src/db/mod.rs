@@ -1,3 +1,4 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::Error as SqliteError;
@@ -16,6 +17,10 @@ pub enum DbError {
     NoRowsAffected,
     #[error("Entity not found")]
     NotFound,
+    #[error("Validation error: {0}")]
+    Validation(String),
+    #[error("Background task failed: {0}")]
+    Task(String),
 }
 
 pub type DbResult<T> = Result<T, DbError>;
@@ -31,4 +36,77 @@ pub fn init_pool(db_path: &str) -> DbResult<DbPool> {
     schema::initialize_database(&conn)?;
     
     Ok(pool)
+}
+
+/// Parses a stored timestamp, either RFC 3339 or SQLite's `CURRENT_TIMESTAMP`
+/// format (UTC, no offset).
+pub fn parse_timestamp(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|dt| dt.and_utc()))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// Runs `work` against the pool on the blocking thread pool, so background
+/// tasks that poll the database don't hold up the async runtime. A panic in
+/// `work` comes back as `DbError::Task` rather than ending the caller's loop.
+pub async fn run_blocking<T, F>(pool: &DbPool, work: F) -> DbResult<T>
+where
+    F: FnOnce(&DbPool) -> DbResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let pool = pool.clone();
+    match tokio::task::spawn_blocking(move || work(&pool)).await {
+        Ok(result) => result,
+        Err(e) => Err(DbError::Task(e.to_string())),
+    }
+}
+
+/// A fresh database in a temporary directory, with one admin user (id 1) for
+/// records that need an author. The directory is removed when dropped.
+#[cfg(test)]
+pub fn test_pool() -> (tempfile::TempDir, DbPool) {
+    let dir = tempfile::tempdir().expect("temporary directory");
+    let pool = init_pool(dir.path().join("test.db").to_str().expect("utf-8 path")).expect("test database");
+    pool.get()
+        .expect("connection")
+        .execute(
+            "INSERT INTO users (username, password_hash, email, role) VALUES ('admin', '', 'admin@example.com', 'admin')",
+            [],
+        )
+        .expect("test user");
+    (dir, pool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    #[test]
+    fn parse_timestamp_reads_rfc3339_in_any_offset() {
+        assert_eq!(
+            parse_timestamp("2026-03-01T10:30:00+02:00"),
+            Utc.with_ymd_and_hms(2026, 3, 1, 8, 30, 0).unwrap()
+        );
+        assert_eq!(
+            parse_timestamp("2026-03-01T10:30:00Z"),
+            Utc.with_ymd_and_hms(2026, 3, 1, 10, 30, 0).unwrap()
+        );
+    }
+    
+    #[test]
+    fn parse_timestamp_reads_sqlite_timestamps_as_utc() {
+        assert_eq!(
+            parse_timestamp("2026-03-01 10:30:00"),
+            Utc.with_ymd_and_hms(2026, 3, 1, 10, 30, 0).unwrap()
+        );
+    }
+    
+    #[test]
+    fn parse_timestamp_falls_back_to_now() {
+        let before = Utc::now();
+        let parsed = parse_timestamp("yesterday");
+        assert!(parsed >= before && parsed <= Utc::now());
+    }
 }
\ No newline at end of file
@@ -22,7 +22,7 @@ pub fn initialize_database(conn: &Connection) -> SqliteResult<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS categories (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
             description TEXT,
             created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
             updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
@@ -30,6 +30,17 @@ pub fn initialize_database(conn: &Connection) -> SqliteResult<()> {
         [],
     )?;
     
+    add_column_if_missing(conn, "categories", "parent_id", "INTEGER REFERENCES categories (id)")?;
+    drop_category_name_unique(conn)?;
+    
+    // Category names are unique among siblings; top-level categories share
+    // parent 0 so they are compared with each other too
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS categories_parent_name
+         ON categories (COALESCE(parent_id, 0), name)",
+        [],
+    )?;
+    
     // Create inventory items table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS inventory_items (
@@ -64,6 +75,603 @@ pub fn initialize_database(conn: &Connection) -> SqliteResult<()> {
         [],
     )?;
     
+    // Columns added after the initial schema
+    add_column_if_missing(conn, "inventory_items", "serialized", "BOOLEAN NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "inventory_transactions", "quantity_before", "INTEGER")?;
+    let added_quantity_after = add_column_if_missing(conn, "inventory_transactions", "quantity_after", "INTEGER")?;
+    add_column_if_missing(conn, "inventory_transactions", "reference", "TEXT")?;
+    add_column_if_missing(
+        conn,
+        "inventory_transactions",
+        "reversal_of",
+        "INTEGER REFERENCES inventory_transactions (id)",
+    )?;
+    add_column_if_missing(
+        conn,
+        "inventory_transactions",
+        "reversed_by",
+        "INTEGER REFERENCES inventory_transactions (id)",
+    )?;
+    
+    // Fill in quantity_after for transactions recorded before it was kept,
+    // wherever it can be worked out; adjustments set it outright
+    if added_quantity_after {
+        conn.execute(
+            "UPDATE inventory_transactions SET quantity_after = CASE transaction_type
+                WHEN 'addition' THEN quantity_before + quantity
+                WHEN 'removal' THEN quantity_before - quantity
+                ELSE quantity
+             END
+             WHERE quantity_before IS NOT NULL OR transaction_type = 'adjustment'",
+            [],
+        )?;
+    }
+    
+    // Create item lots table for batch and expiry tracking
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_lots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL,
+            lot_number TEXT NOT NULL,
+            manufacture_date DATE,
+            expiry_date DATE,
+            quantity INTEGER NOT NULL DEFAULT 0,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (item_id, lot_number),
+            FOREIGN KEY (item_id) REFERENCES inventory_items (id)
+        )",
+        [],
+    )?;
+    
+    // Create transaction lots table linking movements to the lots they touched
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transaction_lots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            transaction_id INTEGER NOT NULL,
+            lot_id INTEGER NOT NULL,
+            quantity INTEGER NOT NULL,
+            FOREIGN KEY (transaction_id) REFERENCES inventory_transactions (id),
+            FOREIGN KEY (lot_id) REFERENCES item_lots (id)
+        )",
+        [],
+    )?;
+    
+    // Create serial numbers table for individually tracked units
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS serial_numbers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL,
+            serial_number TEXT NOT NULL UNIQUE,
+            status TEXT NOT NULL,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (item_id) REFERENCES inventory_items (id)
+        )",
+        [],
+    )?;
+    
+    // Create transaction serials table linking movements to the units they moved
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS transaction_serials (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            transaction_id INTEGER NOT NULL,
+            serial_id INTEGER NOT NULL,
+            status_before TEXT,
+            FOREIGN KEY (transaction_id) REFERENCES inventory_transactions (id),
+            FOREIGN KEY (serial_id) REFERENCES serial_numbers (id)
+        )",
+        [],
+    )?;
+    
+    // Create reservations table for stock promised to orders
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reservations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL,
+            holder TEXT NOT NULL,
+            quantity INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            expires_at TIMESTAMP,
+            created_by INTEGER NOT NULL,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (item_id) REFERENCES inventory_items (id),
+            FOREIGN KEY (created_by) REFERENCES users (id)
+        )",
+        [],
+    )?;
+    
+    // Reservation a removal drew down, so reversing it can give the units back
+    add_column_if_missing(
+        conn,
+        "inventory_transactions",
+        "reservation_id",
+        "INTEGER REFERENCES reservations (id)",
+    )?;
+    
+    // Create count sessions table for cycle counting
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS count_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            category_id INTEGER,
+            location TEXT,
+            status TEXT NOT NULL,
+            created_by INTEGER NOT NULL,
+            approved_by INTEGER,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            approved_at TIMESTAMP,
+            FOREIGN KEY (category_id) REFERENCES categories (id),
+            FOREIGN KEY (created_by) REFERENCES users (id),
+            FOREIGN KEY (approved_by) REFERENCES users (id)
+        )",
+        [],
+    )?;
+    
+    // Create count lines table holding each session's count sheet
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS count_lines (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER NOT NULL,
+            item_id INTEGER NOT NULL,
+            expected_quantity INTEGER NOT NULL,
+            UNIQUE (session_id, item_id),
+            FOREIGN KEY (session_id) REFERENCES count_sessions (id),
+            FOREIGN KEY (item_id) REFERENCES inventory_items (id)
+        )",
+        [],
+    )?;
+    
+    // Create count entries table with one figure per counter per line
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS count_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            line_id INTEGER NOT NULL,
+            counter_id INTEGER NOT NULL,
+            counted_quantity INTEGER NOT NULL,
+            counted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (line_id, counter_id),
+            FOREIGN KEY (line_id) REFERENCES count_lines (id),
+            FOREIGN KEY (counter_id) REFERENCES users (id)
+        )",
+        [],
+    )?;
+    
+    // Create category attributes table describing the fields items in a
+    // category (and its subcategories) carry
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS category_attributes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            category_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            attribute_type TEXT NOT NULL,
+            required BOOLEAN NOT NULL DEFAULT 0,
+            allowed_values TEXT,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (category_id, name),
+            FOREIGN KEY (category_id) REFERENCES categories (id)
+        )",
+        [],
+    )?;
+    
+    // Create item attributes table holding each value as JSON
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_attributes (
+            item_id INTEGER NOT NULL,
+            attribute_id INTEGER NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (item_id, attribute_id),
+            FOREIGN KEY (item_id) REFERENCES inventory_items (id),
+            FOREIGN KEY (attribute_id) REFERENCES category_attributes (id)
+        )",
+        [],
+    )?;
+    
+    // Create units of measure table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS units (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            code TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            fractional BOOLEAN NOT NULL DEFAULT 0,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    
+    // Create item units table with each item's alternate units and how many
+    // base units one of them holds
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_units (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL,
+            unit_id INTEGER NOT NULL,
+            factor REAL NOT NULL,
+            usage TEXT NOT NULL,
+            UNIQUE (item_id, unit_id),
+            FOREIGN KEY (item_id) REFERENCES inventory_items (id),
+            FOREIGN KEY (unit_id) REFERENCES units (id)
+        )",
+        [],
+    )?;
+    
+    add_column_if_missing(conn, "inventory_items", "base_unit_id", "INTEGER REFERENCES units (id)")?;
+    add_column_if_missing(conn, "inventory_transactions", "unit_id", "INTEGER REFERENCES units (id)")?;
+    add_column_if_missing(conn, "inventory_transactions", "unit_quantity", "REAL")?;
+    
+    // Create bill of materials table linking kits to their component items
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS bom_components (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kit_id INTEGER NOT NULL,
+            component_id INTEGER NOT NULL,
+            quantity INTEGER NOT NULL,
+            UNIQUE (kit_id, component_id),
+            FOREIGN KEY (kit_id) REFERENCES inventory_items (id),
+            FOREIGN KEY (component_id) REFERENCES inventory_items (id)
+        )",
+        [],
+    )?;
+    
+    // Create item barcodes table with the alternate codes (GTIN/UPC, supplier
+    // part numbers) that identify an item besides its SKU
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_barcodes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL,
+            code TEXT NOT NULL UNIQUE,
+            kind TEXT NOT NULL,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (item_id) REFERENCES inventory_items (id)
+        )",
+        [],
+    )?;
+    
+    // Quantity at or below which an item counts as low on stock
+    add_column_if_missing(conn, "inventory_items", "reorder_point", "INTEGER")?;
+    add_column_if_missing(conn, "inventory_items", "supplier", "TEXT")?;
+    
+    // Create events table, an append-only log of stock and item changes
+    // written in the same transaction as the change itself
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            event_type TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    
+    // Create webhooks table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            events TEXT NOT NULL DEFAULT '[]',
+            active BOOLEAN NOT NULL DEFAULT 1,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )?;
+    
+    // Create webhook outbox table with one row per event still to deliver to
+    // a webhook
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhook_outbox (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            webhook_id INTEGER NOT NULL,
+            event_id INTEGER NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            last_error TEXT,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            delivered_at TIMESTAMP,
+            FOREIGN KEY (webhook_id) REFERENCES webhooks (id),
+            FOREIGN KEY (event_id) REFERENCES events (id)
+        )",
+        [],
+    )?;
+    
+    // Create webhook deliveries table logging every delivery attempt
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            outbox_id INTEGER NOT NULL,
+            webhook_id INTEGER NOT NULL,
+            attempt INTEGER NOT NULL,
+            status_code INTEGER,
+            error TEXT,
+            duration_ms INTEGER NOT NULL,
+            attempted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (outbox_id) REFERENCES webhook_outbox (id),
+            FOREIGN KEY (webhook_id) REFERENCES webhooks (id)
+        )",
+        [],
+    )?;
+    
+    // Create alerts table; an item has at most one unresolved alert of each kind
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS alerts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'open',
+            quantity INTEGER NOT NULL,
+            threshold INTEGER,
+            message TEXT NOT NULL,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            acknowledged_by INTEGER,
+            acknowledged_at TIMESTAMP,
+            resolved_by INTEGER,
+            resolved_at TIMESTAMP,
+            FOREIGN KEY (item_id) REFERENCES inventory_items (id) ON DELETE CASCADE,
+            FOREIGN KEY (acknowledged_by) REFERENCES users (id),
+            FOREIGN KEY (resolved_by) REFERENCES users (id)
+        )",
+        [],
+    )?;
+    
+    add_alert_item_cascade(conn)?;
+    
+    // Create alert deliveries table tracking each alert through each channel
+    // so failed deliveries are retried
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS alert_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            alert_id INTEGER NOT NULL,
+            channel TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            last_error TEXT,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            delivered_at TIMESTAMP,
+            UNIQUE (alert_id, channel),
+            FOREIGN KEY (alert_id) REFERENCES alerts (id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    
+    // Create alert subscriptions table; a subscription without a category
+    // covers every category
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS alert_subscriptions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            category_id INTEGER,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (user_id) REFERENCES users (id),
+            FOREIGN KEY (category_id) REFERENCES categories (id)
+        )",
+        [],
+    )?;
+    
+    // Create job cursors table recording how far background jobs have
+    // read the events table
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS job_cursors (
+            name TEXT PRIMARY KEY,
+            last_event_id INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    
+    // Create report schedules table; parameters and recipients are JSON
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS report_schedules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            cron TEXT NOT NULL,
+            report_type TEXT NOT NULL,
+            parameters TEXT NOT NULL DEFAULT '{}',
+            format TEXT NOT NULL DEFAULT 'pdf',
+            recipients TEXT NOT NULL DEFAULT '[]',
+            active BOOLEAN NOT NULL DEFAULT 1,
+            created_by INTEGER NOT NULL,
+            last_run_at TIMESTAMP,
+            next_run_at TIMESTAMP,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (created_by) REFERENCES users (id)
+        )",
+        [],
+    )?;
+    
+    // Create report runs table logging every scheduled report generated
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS report_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            schedule_id INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            file_path TEXT,
+            file_size INTEGER,
+            recipients TEXT NOT NULL DEFAULT '[]',
+            error TEXT,
+            started_at TIMESTAMP NOT NULL,
+            finished_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (schedule_id) REFERENCES report_schedules (id)
+        )",
+        [],
+    )?;
+    
+    create_search_index(conn, "items_fts", "inventory_items", "name, description, sku, location")?;
+    create_search_index(conn, "categories_fts", "categories", "name, description")?;
+    
     info!("Database schema initialized successfully");
+    Ok(())
+}
+
+/// Adds a column to an existing table, so databases created by older versions
+/// pick up new columns without being recreated. Returns whether the column
+/// was added.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> SqliteResult<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>("name"))?;
+    
+    for existing in columns {
+        if existing? == column {
+            return Ok(false);
+        }
+    }
+    
+    conn.execute(
+        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+        [],
+    )?;
+    
+    Ok(true)
+}
+
+/// Rebuilds the categories table of databases created before categories were
+/// nested, which made names unique across the whole tree.
+fn drop_category_name_unique(conn: &Connection) -> SqliteResult<()> {
+    let table_sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'categories'",
+        [],
+        |row| row.get(0),
+    )?;
+    
+    if !table_sql.contains("UNIQUE") {
+        return Ok(());
+    }
+    
+    info!("Replacing the global unique constraint on category names...");
+    
+    // The search triggers go with the old table and are recreated with the index
+    rebuild_table(
+        conn,
+        "categories",
+        "id INTEGER PRIMARY KEY AUTOINCREMENT,
+         name TEXT NOT NULL,
+         description TEXT,
+         created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+         updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+         parent_id INTEGER REFERENCES categories (id)",
+        "id, name, description, created_at, updated_at, parent_id",
+    )
+}
+
+/// Alerts tables created before alerts referenced their item kept alerts for
+/// deleted items around; rebuild them so alerts go with the item.
+fn add_alert_item_cascade(conn: &Connection) -> SqliteResult<()> {
+    let has_item_key: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_foreign_key_list('alerts') WHERE \"from\" = 'item_id')",
+        [],
+        |row| row.get(0),
+    )?;
+    
+    if has_item_key {
+        return Ok(());
+    }
+    
+    info!("Linking alerts to their items...");
+    conn.execute("DELETE FROM alerts WHERE item_id NOT IN (SELECT id FROM inventory_items)", [])?;
+    rebuild_table(
+        conn,
+        "alerts",
+        "id INTEGER PRIMARY KEY AUTOINCREMENT,
+         item_id INTEGER NOT NULL,
+         kind TEXT NOT NULL,
+         status TEXT NOT NULL DEFAULT 'open',
+         quantity INTEGER NOT NULL,
+         threshold INTEGER,
+         message TEXT NOT NULL,
+         created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+         acknowledged_by INTEGER,
+         acknowledged_at TIMESTAMP,
+         resolved_by INTEGER,
+         resolved_at TIMESTAMP,
+         FOREIGN KEY (item_id) REFERENCES inventory_items (id) ON DELETE CASCADE,
+         FOREIGN KEY (acknowledged_by) REFERENCES users (id),
+         FOREIGN KEY (resolved_by) REFERENCES users (id)",
+        "id, item_id, kind, status, quantity, threshold, message, created_at,
+         acknowledged_by, acknowledged_at, resolved_by, resolved_at",
+    )
+}
+
+/// Recreates `table` with a new definition and copies `columns` across, for
+/// constraint changes SQLite cannot make with `ALTER TABLE`. Foreign keys are
+/// off while the old table is dropped so rows pointing at it survive.
+fn rebuild_table(conn: &Connection, table: &str, definition: &str, columns: &str) -> SqliteResult<()> {
+    conn.execute_batch("PRAGMA foreign_keys = OFF")?;
+    
+    let result = conn.unchecked_transaction().and_then(|tx| {
+        tx.execute_batch(&format!(
+            "CREATE TABLE {table}_new ({definition});
+             INSERT INTO {table}_new ({columns}) SELECT {columns} FROM {table};
+             DROP TABLE {table};
+             ALTER TABLE {table}_new RENAME TO {table};"
+        ))?;
+        tx.commit()
+    });
+    
+    conn.execute_batch("PRAGMA foreign_keys = ON")?;
+    result
+}
+
+/// Creates an FTS5 index over `columns` of `table`, with triggers keeping it
+/// in step with the table. Rows that already exist are indexed the first time.
+fn create_search_index(
+    conn: &Connection,
+    index: &str,
+    table: &str,
+    columns: &str,
+) -> SqliteResult<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)",
+        [index],
+        |row| row.get(0),
+    )?;
+    
+    let new_values = columns
+        .split(", ")
+        .map(|column| format!("new.{}", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let old_values = columns
+        .split(", ")
+        .map(|column| format!("old.{}", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    
+    if !exists {
+        conn.execute_batch(&format!(
+            "CREATE VIRTUAL TABLE {index} USING fts5(
+                {columns},
+                content = '{table}',
+                content_rowid = 'id',
+                tokenize = 'porter unicode61'
+             );
+             
+             INSERT INTO {index} ({index}) VALUES ('rebuild');"
+        ))?;
+    }
+    
+    // Triggers are dropped with their table, so they are (re)created even
+    // when the index already exists. The update trigger is replaced so older
+    // databases stop reindexing rows on changes to unindexed columns.
+    conn.execute_batch(&format!(
+        "CREATE TRIGGER IF NOT EXISTS {index}_insert AFTER INSERT ON {table} BEGIN
+            INSERT INTO {index} (rowid, {columns}) VALUES (new.id, {new_values});
+         END;
+         
+         CREATE TRIGGER IF NOT EXISTS {index}_delete AFTER DELETE ON {table} BEGIN
+            INSERT INTO {index} ({index}, rowid, {columns}) VALUES ('delete', old.id, {old_values});
+         END;
+         
+         DROP TRIGGER IF EXISTS {index}_update;
+         CREATE TRIGGER {index}_update AFTER UPDATE OF {columns} ON {table} BEGIN
+            INSERT INTO {index} ({index}, rowid, {columns}) VALUES ('delete', old.id, {old_values});
+            INSERT INTO {index} (rowid, {columns}) VALUES (new.id, {new_values});
+         END;"
+    ))?;
+    
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,194 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use crate::db::{DbError, DbPool};
+use crate::models::alert::{Alert, AlertStatus, AlertSubscription, NewAlertSubscription};
+use crate::utils::middleware::authenticated_user_id;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlertListQuery {
+    /// `open`, `acknowledged` or `resolved`
+    pub status: Option<String>,
+    pub item_id: Option<i64>,
+}
+
+fn unauthorized() -> HttpResponse {
+    HttpResponse::Unauthorized().json(ErrorResponse {
+        error: "Authentication required".to_string(),
+    })
+}
+
+pub async fn list_alerts(
+    pool: web::Data<DbPool>,
+    query: web::Query<AlertListQuery>,
+) -> impl Responder {
+    let status = match query.status.as_deref() {
+        None => None,
+        Some(status) => match AlertStatus::from_str(status) {
+            Some(status) => Some(status),
+            None => {
+                return HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!("Unknown alert status '{}'", status),
+                });
+            }
+        },
+    };
+    
+    match Alert::list(&pool, status, query.item_id) {
+        Ok(alerts) => HttpResponse::Ok().json(alerts),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing alerts: {}", e),
+        }),
+    }
+}
+
+pub async fn get_alert(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let alert_id = path.into_inner();
+    
+    match Alert::find_by_id(&pool, alert_id) {
+        Ok(alert) => HttpResponse::Ok().json(alert),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving alert: {}", e),
+            })
+        }
+    }
+}
+
+fn change_status(
+    pool: &DbPool,
+    alert_id: i64,
+    result: Result<(), DbError>,
+    action: &str,
+) -> HttpResponse {
+    match result {
+        Ok(_) => match Alert::find_by_id(pool, alert_id) {
+            Ok(alert) => HttpResponse::Ok().json(alert),
+            Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Alert {} but failed to retrieve", action),
+            }),
+        },
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::Conflict(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error updating alert: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn acknowledge_alert(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let alert_id = path.into_inner();
+    
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => return unauthorized(),
+    };
+    
+    change_status(&pool, alert_id, Alert::acknowledge(&pool, alert_id, user_id), "acknowledged")
+}
+
+pub async fn resolve_alert(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let alert_id = path.into_inner();
+    
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => return unauthorized(),
+    };
+    
+    change_status(&pool, alert_id, Alert::resolve(&pool, alert_id, user_id), "resolved")
+}
+
+pub async fn list_subscriptions(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+) -> impl Responder {
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => return unauthorized(),
+    };
+    
+    match AlertSubscription::list_by_user(&pool, user_id) {
+        Ok(subscriptions) => HttpResponse::Ok().json(subscriptions),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing alert subscriptions: {}", e),
+        }),
+    }
+}
+
+pub async fn create_subscription(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    new_subscription: web::Json<NewAlertSubscription>,
+) -> impl Responder {
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => return unauthorized(),
+    };
+    
+    match AlertSubscription::create(&pool, user_id, new_subscription.into_inner()) {
+        Ok(subscription) => HttpResponse::Created().json(subscription),
+        Err(e) => {
+            let mut status = match e {
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error creating alert subscription: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn delete_subscription(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let subscription_id = path.into_inner();
+    
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => return unauthorized(),
+    };
+    
+    match AlertSubscription::delete(&pool, subscription_id, user_id) {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error deleting alert subscription: {}", e),
+            })
+        }
+    }
+}
\ No newline at end of file
@@ -1,7 +1,8 @@
 use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use crate::db::{DbError, DbPool};
-use crate::models::category::{Category, NewCategory, UpdateCategory};
+use crate::models::attribute::{CategoryAttribute, NewCategoryAttribute};
+use crate::models::category::{Category, NewCategory, UpdateCategory, PATH_SEPARATOR};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
@@ -13,6 +14,17 @@ pub struct SearchQuery {
     pub query: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PathQuery {
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryPath {
+    pub path: String,
+    pub categories: Vec<Category>,
+}
+
 pub async fn create_category(
     pool: web::Data<DbPool>,
     new_category: web::Json<NewCategory>,
@@ -30,7 +42,7 @@ pub async fn create_category(
             let error_message = match e {
                 DbError::Sqlite(e) => {
                     if e.to_string().contains("UNIQUE constraint failed") {
-                        "Category name already exists".to_string()
+                        "A category with this name already exists under the same parent".to_string()
                     } else {
                         format!("Database error: {}", e)
                     }
@@ -82,9 +94,15 @@ pub async fn update_category(
                 }),
             }
         }
+        Err(DbError::Sqlite(e)) if e.to_string().contains("UNIQUE constraint failed") => {
+            HttpResponse::BadRequest().json(ErrorResponse {
+                error: "A category with this name already exists under the same parent".to_string(),
+            })
+        }
         Err(e) => {
             let mut status = match e {
                 DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::BadRequest(),
                 _ => HttpResponse::InternalServerError(),
             };
             
@@ -106,6 +124,7 @@ pub async fn delete_category(
         Err(e) => {
             let mut status = match e {
                 DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::Conflict(),
                 _ => HttpResponse::InternalServerError(),
             };
             
@@ -137,4 +156,154 @@ pub async fn search_categories(
             error: format!("Error searching categories: {}", e),
         }),
     }
+}
+
+pub async fn list_subcategories(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let category_id = path.into_inner();
+    
+    if let Err(e) = Category::find_by_id(&pool, category_id) {
+        let mut status = match e {
+            DbError::NotFound => HttpResponse::NotFound(),
+            _ => HttpResponse::InternalServerError(),
+        };
+        
+        return status.json(ErrorResponse {
+            error: format!("Error retrieving category: {}", e),
+        });
+    }
+    
+    match Category::list_children(&pool, category_id) {
+        Ok(categories) => HttpResponse::Ok().json(categories),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing subcategories: {}", e),
+        }),
+    }
+}
+
+pub async fn get_category_path(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let category_id = path.into_inner();
+    
+    match Category::ancestors(&pool, category_id) {
+        Ok(categories) => {
+            let path = categories
+                .iter()
+                .map(|category| category.name.as_str())
+                .collect::<Vec<_>>()
+                .join(PATH_SEPARATOR);
+            
+            HttpResponse::Ok().json(CategoryPath { path, categories })
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving category path: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn find_category_by_path(
+    pool: web::Data<DbPool>,
+    query: web::Query<PathQuery>,
+) -> impl Responder {
+    match Category::find_by_path(&pool, &query.path) {
+        Ok(category) => HttpResponse::Ok().json(category),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving category: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn create_category_attribute(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    new_attribute: web::Json<NewCategoryAttribute>,
+) -> impl Responder {
+    let category_id = path.into_inner();
+    
+    match CategoryAttribute::create(&pool, category_id, new_attribute.into_inner()) {
+        Ok(attribute_id) => {
+            match CategoryAttribute::find_by_id(&pool, attribute_id) {
+                Ok(attribute) => HttpResponse::Created().json(attribute),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Attribute created but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error creating category attribute: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn list_category_attributes(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let category_id = path.into_inner();
+    
+    if let Err(e) = Category::find_by_id(&pool, category_id) {
+        let mut status = match e {
+            DbError::NotFound => HttpResponse::NotFound(),
+            _ => HttpResponse::InternalServerError(),
+        };
+        
+        return status.json(ErrorResponse {
+            error: format!("Error retrieving category: {}", e),
+        });
+    }
+    
+    match CategoryAttribute::list_for_category(&pool, category_id) {
+        Ok(attributes) => HttpResponse::Ok().json(attributes),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing category attributes: {}", e),
+        }),
+    }
+}
+
+pub async fn delete_category_attribute(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i64, i64)>,
+) -> impl Responder {
+    let (category_id, attribute_id) = path.into_inner();
+    
+    match CategoryAttribute::delete(&pool, category_id, attribute_id) {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error deleting category attribute: {}", e),
+            })
+        }
+    }
 }
\ No newline at end of file
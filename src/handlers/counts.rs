@@ -0,0 +1,188 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use crate::db::{DbError, DbPool};
+use crate::models::cycle_count::{CountSession, NewCountEntry, NewCountSession};
+use crate::utils::middleware::authenticated_user_id;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApprovalResponse {
+    pub session: CountSession,
+    pub adjustment_ids: Vec<i64>,
+}
+
+pub async fn create_count_session(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    new_session: web::Json<NewCountSession>,
+) -> impl Responder {
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Authentication required".to_string(),
+            });
+        }
+    };
+    
+    match CountSession::create(&pool, new_session.into_inner(), user_id) {
+        Ok(session_id) => {
+            match CountSession::find_by_id(&pool, session_id, true) {
+                Ok(session) => HttpResponse::Created().json(session),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Count session created but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error creating count session: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn list_count_sessions(
+    pool: web::Data<DbPool>,
+) -> impl Responder {
+    match CountSession::list(&pool) {
+        Ok(sessions) => HttpResponse::Ok().json(sessions),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing count sessions: {}", e),
+        }),
+    }
+}
+
+pub async fn get_count_session(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    
+    match CountSession::find_by_id(&pool, session_id, true) {
+        Ok(session) => HttpResponse::Ok().json(session),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving count session: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn record_count(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    entry: web::Json<NewCountEntry>,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Authentication required".to_string(),
+            });
+        }
+    };
+    
+    match CountSession::record_count(&pool, session_id, entry.into_inner(), user_id) {
+        Ok(_) => {
+            match CountSession::find_by_id(&pool, session_id, true) {
+                Ok(session) => HttpResponse::Ok().json(session),
+                Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: format!("Count recorded but failed to retrieve session: {}", e),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error recording count: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn approve_count_session(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Authentication required".to_string(),
+            });
+        }
+    };
+    
+    match CountSession::approve(&pool, session_id, user_id) {
+        Ok(adjustment_ids) => {
+            match CountSession::find_by_id(&pool, session_id, true) {
+                Ok(session) => HttpResponse::Ok().json(ApprovalResponse {
+                    session,
+                    adjustment_ids,
+                }),
+                Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: format!("Count session approved but failed to retrieve: {}", e),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::Conflict(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error approving count session: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn cancel_count_session(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let session_id = path.into_inner();
+    
+    match CountSession::cancel(&pool, session_id) {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::Conflict(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error cancelling count session: {}", e),
+            })
+        }
+    }
+}
\ No newline at end of file
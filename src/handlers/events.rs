@@ -0,0 +1,126 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use futures::stream;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::db::{run_blocking, DbPool};
+use crate::models::event::{Event, EventType};
+
+/// How often the stream checks for newly committed events.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Polls without events before a keep-alive comment is sent.
+const KEEP_ALIVE_POLLS: u32 = 15;
+const BATCH_SIZE: i64 = 100;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventStreamQuery {
+    /// Comma-separated event types to receive, e.g. `transaction.created,item.low_stock`.
+    pub types: Option<String>,
+    /// Replay events after this id; defaults to only events from now on.
+    pub after: Option<i64>,
+}
+
+struct StreamState {
+    pool: web::Data<DbPool>,
+    types: Vec<EventType>,
+    last_id: i64,
+    idle_polls: u32,
+    interval: tokio::time::Interval,
+}
+
+fn format_event(event: &Event) -> String {
+    let data = serde_json::to_string(event).unwrap_or_else(|_| "null".to_string());
+    format!("id: {}\nevent: {}\ndata: {}\n\n", event.id, event.event_type, data)
+}
+
+/// Server-sent event stream of committed events. A reconnecting client's
+/// `Last-Event-ID` header resumes the stream where it left off.
+pub async fn stream_events(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    query: web::Query<EventStreamQuery>,
+) -> impl Responder {
+    let mut types = Vec::new();
+    
+    for name in query.types.as_deref().unwrap_or("").split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        match EventType::from_str(name) {
+            Some(event_type) => types.push(event_type),
+            None => {
+                return HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!("Unknown event type '{}'", name),
+                });
+            }
+        }
+    }
+    
+    let last_event_id = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i64>().ok());
+    
+    let last_id = match last_event_id.or(query.after) {
+        Some(id) => id,
+        None => match Event::latest_id(&pool) {
+            Ok(id) => id,
+            Err(e) => {
+                return HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: format!("Error opening event stream: {}", e),
+                });
+            }
+        },
+    };
+    
+    let state = StreamState {
+        pool,
+        types,
+        last_id,
+        idle_polls: 0,
+        interval: tokio::time::interval(POLL_INTERVAL),
+    };
+    
+    let events = stream::unfold(state, |mut state| async move {
+        loop {
+            state.interval.tick().await;
+            
+            let last_id = state.last_id;
+            let events = match run_blocking(&state.pool, move |pool| Event::list_after(pool, last_id, BATCH_SIZE)).await {
+                Ok(events) => events,
+                Err(e) => {
+                    error!("Event stream stopped: {}", e);
+                    return None;
+                }
+            };
+            
+            let mut chunk = String::new();
+            
+            for event in &events {
+                state.last_id = event.id;
+                if state.types.is_empty() || state.types.contains(&event.event_type) {
+                    chunk.push_str(&format_event(event));
+                }
+            }
+            
+            if chunk.is_empty() {
+                state.idle_polls += 1;
+                if state.idle_polls < KEEP_ALIVE_POLLS {
+                    continue;
+                }
+                chunk.push_str(": keep-alive\n\n");
+            }
+            
+            state.idle_polls = 0;
+            return Some((Ok::<_, actix_web::Error>(web::Bytes::from(chunk)), state));
+        }
+    });
+    
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
\ No newline at end of file
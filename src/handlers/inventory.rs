@@ -1,7 +1,12 @@
 use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use crate::db::{DbError, DbPool};
+use crate::models::attribute::AttributeFilter;
+use crate::models::barcode::{ItemBarcode, NewItemBarcode};
 use crate::models::inventory_item::{InventoryItem, InventoryItemFilter, NewInventoryItem, UpdateInventoryItem};
+use crate::models::lot::Lot;
+use crate::models::serial_number::SerialNumber;
+use crate::models::unit::{ItemUnit, NewItemUnit};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
@@ -17,6 +22,8 @@ pub struct SearchQuery {
     pub max_price: Option<f64>,
     pub location: Option<String>,
     pub query: Option<String>,
+    /// Comma-separated attribute conditions, e.g. `voltage>=12,colour=red`
+    pub attributes: Option<String>,
 }
 
 pub async fn create_item(
@@ -74,6 +81,52 @@ pub async fn get_item(
     }
 }
 
+pub async fn get_item_by_sku(
+    pool: web::Data<DbPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let sku = path.into_inner();
+    
+    match InventoryItem::find_by_sku(&pool, &sku, true) {
+        Ok(item) => HttpResponse::Ok().json(item),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving inventory item: {}", e),
+            })
+        }
+    }
+}
+
+/// Looks an item up by whatever a scanner read: its SKU or an alternate barcode.
+pub async fn get_item_by_barcode(
+    pool: web::Data<DbPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let code = path.into_inner();
+    
+    let item = ItemBarcode::find_item(&pool, &code)
+        .and_then(|item_id| InventoryItem::find_by_id(&pool, item_id, true));
+    
+    match item {
+        Ok(item) => HttpResponse::Ok().json(item),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving inventory item: {}", e),
+            })
+        }
+    }
+}
+
 pub async fn update_item(
     pool: web::Data<DbPool>,
     path: web::Path<i64>,
@@ -93,6 +146,7 @@ pub async fn update_item(
         Err(e) => {
             let mut status = match e {
                 DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::BadRequest(),
                 _ => HttpResponse::InternalServerError(),
             };
             
@@ -139,6 +193,16 @@ pub async fn search_items(
     pool: web::Data<DbPool>,
     query: web::Query<SearchQuery>,
 ) -> impl Responder {
+    let attributes = match query.attributes.as_deref().map(AttributeFilter::parse_list) {
+        Some(Some(attributes)) => attributes,
+        Some(None) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: "Invalid attribute filter; use name=value or name>=number".to_string(),
+            });
+        }
+        None => Vec::new(),
+    };
+    
     let filter = InventoryItemFilter {
         category_id: query.category_id,
         min_quantity: query.min_quantity,
@@ -147,6 +211,7 @@ pub async fn search_items(
         max_price: query.max_price,
         location: query.location.clone(),
         search_query: query.query.clone(),
+        attributes,
     };
     
     match InventoryItem::search(&pool, filter, true) {
@@ -157,6 +222,100 @@ pub async fn search_items(
     }
 }
 
+pub async fn list_item_lots(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let item_id = path.into_inner();
+    
+    match Lot::list_by_item(&pool, item_id) {
+        Ok(lots) => HttpResponse::Ok().json(lots),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing item lots: {}", e),
+        }),
+    }
+}
+
+pub async fn list_item_serials(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let item_id = path.into_inner();
+    
+    match SerialNumber::list_by_item(&pool, item_id) {
+        Ok(serials) => HttpResponse::Ok().json(serials),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing item serial numbers: {}", e),
+        }),
+    }
+}
+
+pub async fn list_item_units(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let item_id = path.into_inner();
+    
+    match ItemUnit::list_by_item(&pool, item_id) {
+        Ok(units) => HttpResponse::Ok().json(units),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing item units: {}", e),
+        }),
+    }
+}
+
+pub async fn add_item_unit(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    new_unit: web::Json<NewItemUnit>,
+) -> impl Responder {
+    let item_id = path.into_inner();
+    
+    match ItemUnit::create(&pool, item_id, new_unit.into_inner()) {
+        Ok(_) => match ItemUnit::list_by_item(&pool, item_id) {
+            Ok(units) => HttpResponse::Created().json(units),
+            Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Unit added but failed to retrieve: {}", e),
+            }),
+        },
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                DbError::Sqlite(ref sqlite_error) if sqlite_error.to_string().contains("UNIQUE constraint failed") => {
+                    HttpResponse::Conflict()
+                }
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error adding item unit: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn remove_item_unit(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i64, i64)>,
+) -> impl Responder {
+    let (item_id, unit_id) = path.into_inner();
+    
+    match ItemUnit::delete(&pool, item_id, unit_id) {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error removing item unit: {}", e),
+            })
+        }
+    }
+}
+
 pub async fn get_low_stock_items(
     pool: web::Data<DbPool>,
     query: web::Query<LowStockQuery>,
@@ -174,4 +333,70 @@ pub async fn get_low_stock_items(
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LowStockQuery {
     pub threshold: Option<i32>,
+}
+
+pub async fn list_item_barcodes(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let item_id = path.into_inner();
+    
+    match ItemBarcode::list_by_item(&pool, item_id) {
+        Ok(barcodes) => HttpResponse::Ok().json(barcodes),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing item barcodes: {}", e),
+        }),
+    }
+}
+
+pub async fn add_item_barcode(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    new_barcode: web::Json<NewItemBarcode>,
+) -> impl Responder {
+    let item_id = path.into_inner();
+    
+    match ItemBarcode::create(&pool, item_id, new_barcode.into_inner()) {
+        Ok(_) => match ItemBarcode::list_by_item(&pool, item_id) {
+            Ok(barcodes) => HttpResponse::Created().json(barcodes),
+            Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Barcode added but failed to retrieve: {}", e),
+            }),
+        },
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::Conflict(),
+                DbError::Sqlite(ref sqlite_error) if sqlite_error.to_string().contains("UNIQUE constraint failed") => {
+                    HttpResponse::Conflict()
+                }
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error adding item barcode: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn remove_item_barcode(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i64, i64)>,
+) -> impl Responder {
+    let (item_id, barcode_id) = path.into_inner();
+    
+    match ItemBarcode::delete(&pool, item_id, barcode_id) {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error removing item barcode: {}", e),
+            })
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,150 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use crate::db::{DbError, DbPool};
+use crate::models::kit::{Kit, KitOperation, NewBomComponent};
+use crate::utils::middleware::authenticated_user_id;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KitOperationResponse {
+    pub kit: Kit,
+    pub transaction_ids: Vec<i64>,
+}
+
+pub async fn get_kit(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let kit_id = path.into_inner();
+    
+    match Kit::find(&pool, kit_id) {
+        Ok(kit) => HttpResponse::Ok().json(kit),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving kit: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn add_component(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    new_component: web::Json<NewBomComponent>,
+) -> impl Responder {
+    let kit_id = path.into_inner();
+    
+    match Kit::add_component(&pool, kit_id, new_component.into_inner()) {
+        Ok(_) => match Kit::find(&pool, kit_id) {
+            Ok(kit) => HttpResponse::Ok().json(kit),
+            Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Component added but failed to retrieve kit: {}", e),
+            }),
+        },
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error adding kit component: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn remove_component(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i64, i64)>,
+) -> impl Responder {
+    let (kit_id, component_id) = path.into_inner();
+    
+    match Kit::remove_component(&pool, kit_id, component_id) {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error removing kit component: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn assemble_kit(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    operation: web::Json<KitOperation>,
+) -> impl Responder {
+    run_kit_operation(req, pool, path.into_inner(), operation.into_inner(), true)
+}
+
+pub async fn disassemble_kit(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    operation: web::Json<KitOperation>,
+) -> impl Responder {
+    run_kit_operation(req, pool, path.into_inner(), operation.into_inner(), false)
+}
+
+fn run_kit_operation(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    kit_id: i64,
+    operation: KitOperation,
+    assemble: bool,
+) -> HttpResponse {
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Authentication required".to_string(),
+            });
+        }
+    };
+    
+    let (result, action) = if assemble {
+        (Kit::assemble(&pool, kit_id, operation, user_id), "assembling")
+    } else {
+        (Kit::disassemble(&pool, kit_id, operation, user_id), "disassembling")
+    };
+    
+    match result {
+        Ok(transaction_ids) => match Kit::find(&pool, kit_id) {
+            Ok(kit) => HttpResponse::Created().json(KitOperationResponse {
+                kit,
+                transaction_ids,
+            }),
+            Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Kit updated but failed to retrieve: {}", e),
+            }),
+        },
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::Conflict(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error {} kit: {}", action, e),
+            })
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,163 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use crate::db::{DbError, DbPool};
+use crate::models::inventory_item::{InventoryItem, InventoryItemFilter};
+use crate::utils::labels::{sheet_svg, Label, Symbol, Symbology};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelQuery {
+    /// `png` or `svg` (the default)
+    pub format: Option<String>,
+    /// `code128` (the default) or `qr`
+    pub symbology: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelSheetQuery {
+    pub category_id: Option<i64>,
+    pub location: Option<String>,
+    pub query: Option<String>,
+    pub symbology: Option<String>,
+}
+
+fn parse_symbology(symbology: Option<&str>) -> Result<Symbology, HttpResponse> {
+    match symbology {
+        None => Ok(Symbology::Code128),
+        Some(symbology) => Symbology::from_str(symbology).ok_or_else(|| {
+            HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Unknown symbology '{}'; use code128 or qr", symbology),
+            })
+        }),
+    }
+}
+
+fn render(data: &str, query: &LabelQuery) -> HttpResponse {
+    let symbology = match parse_symbology(query.symbology.as_deref()) {
+        Ok(symbology) => symbology,
+        Err(response) => return response,
+    };
+    
+    let symbol = match Symbol::encode(&symbology, data) {
+        Ok(symbol) => symbol,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: e.to_string(),
+            });
+        }
+    };
+    
+    match query.format.as_deref().unwrap_or("svg") {
+        "svg" => HttpResponse::Ok()
+            .content_type("image/svg+xml")
+            .body(symbol.to_svg()),
+        "png" => match symbol.to_png() {
+            Ok(png) => HttpResponse::Ok().content_type("image/png").body(png),
+            Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Error rendering label: {}", e),
+            }),
+        },
+        other => HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Unknown label format '{}'; use png or svg", other),
+        }),
+    }
+}
+
+pub async fn get_item_label(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    query: web::Query<LabelQuery>,
+) -> impl Responder {
+    let item_id = path.into_inner();
+    
+    let item = match InventoryItem::find_by_id(&pool, item_id, false) {
+        Ok(item) => item,
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound | DbError::Sqlite(rusqlite::Error::QueryReturnedNoRows) => {
+                    HttpResponse::NotFound()
+                }
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            return status.json(ErrorResponse {
+                error: format!("Error retrieving inventory item: {}", e),
+            });
+        }
+    };
+    
+    match item.sku.as_deref() {
+        Some(sku) if !sku.is_empty() => render(sku, &query),
+        _ => HttpResponse::BadRequest().json(ErrorResponse {
+            error: "Item has no SKU to encode".to_string(),
+        }),
+    }
+}
+
+pub async fn get_location_label(
+    path: web::Path<String>,
+    query: web::Query<LabelQuery>,
+) -> impl Responder {
+    render(&path.into_inner(), &query)
+}
+
+/// Printable SVG sheet with one label per matching item that has a SKU.
+pub async fn get_label_sheet(
+    pool: web::Data<DbPool>,
+    query: web::Query<LabelSheetQuery>,
+) -> impl Responder {
+    let symbology = match parse_symbology(query.symbology.as_deref()) {
+        Ok(symbology) => symbology,
+        Err(response) => return response,
+    };
+    
+    let filter = InventoryItemFilter {
+        category_id: query.category_id,
+        min_quantity: None,
+        max_quantity: None,
+        min_price: None,
+        max_price: None,
+        location: query.location.clone(),
+        search_query: query.query.clone(),
+        attributes: Vec::new(),
+    };
+    
+    let items = match InventoryItem::search(&pool, filter, false) {
+        Ok(items) => items,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Error searching inventory items: {}", e),
+            });
+        }
+    };
+    
+    let mut labels = Vec::new();
+    
+    for item in items {
+        let sku = match item.sku {
+            Some(sku) if !sku.is_empty() => sku,
+            _ => continue,
+        };
+        
+        match Symbol::encode(&symbology, &sku) {
+            Ok(symbol) => labels.push(Label {
+                symbol,
+                title: item.name,
+                caption: sku,
+            }),
+            Err(e) => {
+                return HttpResponse::BadRequest().json(ErrorResponse {
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    
+    HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .body(sheet_svg(&labels))
+}
\ No newline at end of file
@@ -3,4 +3,15 @@ pub mod users;
 pub mod categories;
 pub mod inventory;
 pub mod transactions;
-pub mod reports;
\ No newline at end of file
+pub mod reports;
+pub mod serials;
+pub mod reservations;
+pub mod counts;
+pub mod search;
+pub mod units;
+pub mod kits;
+pub mod labels;
+pub mod webhooks;
+pub mod events;
+pub mod alerts;
+pub mod report_schedules;
\ No newline at end of file
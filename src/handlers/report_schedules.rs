@@ -0,0 +1,291 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use crate::db::{DbError, DbPool};
+use crate::models::report_request::ReportRequest;
+use crate::models::report_schedule::{NewReportSchedule, ReportRun, ReportSchedule, UpdateReportSchedule};
+use crate::utils::export::ExportFormat;
+use crate::utils::middleware::authenticated_user_id;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunHistoryQuery {
+    pub limit: Option<i64>,
+}
+
+/// Checks that a schedule's report type and parameters describe a report
+/// that can be built, before the schedule is stored.
+fn check_report(report_type: &str, parameters: &Value) -> Result<(), HttpResponse> {
+    ReportRequest::parse(report_type, parameters)
+        .map(|_| ())
+        .map_err(|e| {
+            HttpResponse::BadRequest().json(ErrorResponse {
+                error: e.to_string(),
+            })
+        })
+}
+
+pub async fn create_schedule(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    new_schedule: web::Json<NewReportSchedule>,
+) -> impl Responder {
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Authentication required".to_string(),
+            });
+        }
+    };
+    
+    let new_schedule = new_schedule.into_inner();
+    if let Err(response) = check_report(&new_schedule.report_type, &new_schedule.parameters) {
+        return response;
+    }
+    
+    match ReportSchedule::create(&pool, new_schedule, user_id) {
+        Ok(schedule_id) => {
+            match ReportSchedule::find_by_id(&pool, schedule_id) {
+                Ok(schedule) => HttpResponse::Created().json(schedule),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Report schedule created but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error creating report schedule: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn list_schedules(
+    pool: web::Data<DbPool>,
+) -> impl Responder {
+    match ReportSchedule::list(&pool) {
+        Ok(schedules) => HttpResponse::Ok().json(schedules),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing report schedules: {}", e),
+        }),
+    }
+}
+
+pub async fn get_schedule(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let schedule_id = path.into_inner();
+    
+    match ReportSchedule::find_by_id(&pool, schedule_id) {
+        Ok(schedule) => HttpResponse::Ok().json(schedule),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving report schedule: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn update_schedule(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    update: web::Json<UpdateReportSchedule>,
+) -> impl Responder {
+    let schedule_id = path.into_inner();
+    let update = update.into_inner();
+    
+    if update.report_type.is_some() || update.parameters.is_some() {
+        let schedule = match ReportSchedule::find_by_id(&pool, schedule_id) {
+            Ok(schedule) => schedule,
+            Err(e) => {
+                let mut status = match e {
+                    DbError::NotFound => HttpResponse::NotFound(),
+                    _ => HttpResponse::InternalServerError(),
+                };
+                
+                return status.json(ErrorResponse {
+                    error: format!("Error updating report schedule: {}", e),
+                });
+            }
+        };
+        
+        let report_type = update.report_type.as_ref().unwrap_or(&schedule.report_type);
+        let parameters = update.parameters.as_ref().unwrap_or(&schedule.parameters);
+        if let Err(response) = check_report(report_type, parameters) {
+            return response;
+        }
+    }
+    
+    match ReportSchedule::update(&pool, schedule_id, update) {
+        Ok(_) => {
+            match ReportSchedule::find_by_id(&pool, schedule_id) {
+                Ok(schedule) => HttpResponse::Ok().json(schedule),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Report schedule updated but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error updating report schedule: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn delete_schedule(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let schedule_id = path.into_inner();
+    
+    match ReportSchedule::delete(&pool, schedule_id) {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error deleting report schedule: {}", e),
+            })
+        }
+    }
+}
+
+/// Queues a schedule to run on the scheduler's next poll.
+pub async fn run_schedule(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let schedule_id = path.into_inner();
+    
+    match ReportSchedule::run_now(&pool, schedule_id) {
+        Ok(_) => {
+            match ReportSchedule::find_by_id(&pool, schedule_id) {
+                Ok(schedule) => HttpResponse::Accepted().json(schedule),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Report schedule queued but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error queueing report schedule: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn list_schedule_runs(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    query: web::Query<RunHistoryQuery>,
+) -> impl Responder {
+    let schedule_id = path.into_inner();
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    
+    if let Err(e) = ReportSchedule::find_by_id(&pool, schedule_id) {
+        let mut status = match e {
+            DbError::NotFound => HttpResponse::NotFound(),
+            _ => HttpResponse::InternalServerError(),
+        };
+        
+        return status.json(ErrorResponse {
+            error: format!("Error retrieving report schedule: {}", e),
+        });
+    }
+    
+    match ReportRun::list_by_schedule(&pool, schedule_id, limit) {
+        Ok(runs) => HttpResponse::Ok().json(runs),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing report runs: {}", e),
+        }),
+    }
+}
+
+/// Downloads the archived file a run produced.
+pub async fn download_run_file(
+    pool: web::Data<DbPool>,
+    path: web::Path<(i64, i64)>,
+) -> impl Responder {
+    let (schedule_id, run_id) = path.into_inner();
+    
+    let run = match ReportRun::find(&pool, schedule_id, run_id) {
+        Ok(run) => run,
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            return status.json(ErrorResponse {
+                error: format!("Error retrieving report run: {}", e),
+            });
+        }
+    };
+    
+    let file_path = match run.file_path {
+        Some(file_path) => file_path,
+        None => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: format!("Report run {} did not produce a file", run_id),
+            });
+        }
+    };
+    
+    let data = match fs::read(&file_path) {
+        Ok(data) => data,
+        Err(e) => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: format!("Archived report {} is not available: {}", file_path, e),
+            });
+        }
+    };
+    
+    let path = Path::new(&file_path);
+    let content_type = path
+        .extension()
+        .and_then(|extension| ExportFormat::from_str(&extension.to_string_lossy()))
+        .map(|format| format.content_type())
+        .unwrap_or("application/octet-stream");
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+        .body(data)
+}
\ No newline at end of file
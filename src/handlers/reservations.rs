@@ -0,0 +1,187 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use crate::db::{DbError, DbPool};
+use crate::models::reservation::{NewReservation, Reservation};
+use crate::models::transaction::{NewTransaction, Transaction, TransactionType};
+use crate::utils::middleware::authenticated_user_id;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReservationListQuery {
+    pub item_id: Option<i64>,
+    pub active: Option<bool>,
+}
+
+pub async fn create_reservation(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    new_reservation: web::Json<NewReservation>,
+) -> impl Responder {
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Authentication required".to_string(),
+            });
+        }
+    };
+    
+    match Reservation::create(&pool, new_reservation.into_inner(), user_id) {
+        Ok(reservation_id) => {
+            match Reservation::find_by_id(&pool, reservation_id) {
+                Ok(reservation) => HttpResponse::Created().json(reservation),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Reservation created but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::Conflict(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error creating reservation: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn get_reservation(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let reservation_id = path.into_inner();
+    
+    match Reservation::find_by_id(&pool, reservation_id) {
+        Ok(reservation) => HttpResponse::Ok().json(reservation),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving reservation: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn list_reservations(
+    pool: web::Data<DbPool>,
+    query: web::Query<ReservationListQuery>,
+) -> impl Responder {
+    let active_only = query.active.unwrap_or(true);
+    
+    match Reservation::list(&pool, query.item_id, active_only) {
+        Ok(reservations) => HttpResponse::Ok().json(reservations),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing reservations: {}", e),
+        }),
+    }
+}
+
+pub async fn release_reservation(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let reservation_id = path.into_inner();
+    
+    match Reservation::release(&pool, reservation_id) {
+        Ok(_) => {
+            match Reservation::find_by_id(&pool, reservation_id) {
+                Ok(reservation) => HttpResponse::Ok().json(reservation),
+                Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: format!("Reservation released but failed to retrieve: {}", e),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::Conflict(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error releasing reservation: {}", e),
+            })
+        }
+    }
+}
+
+/// Fulfils the whole remaining reservation with a removal transaction.
+/// Partial fulfilment goes through `POST /api/transactions` with a
+/// `reservation_id`.
+pub async fn fulfil_reservation(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let reservation_id = path.into_inner();
+    
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Authentication required".to_string(),
+            });
+        }
+    };
+    
+    let reservation = match Reservation::find_by_id(&pool, reservation_id) {
+        Ok(reservation) => reservation,
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            return status.json(ErrorResponse {
+                error: format!("Error retrieving reservation: {}", e),
+            });
+        }
+    };
+    
+    let removal = NewTransaction {
+        item_id: reservation.item_id,
+        transaction_type: TransactionType::Removal,
+        quantity: reservation.quantity,
+        user_id,
+        notes: Some(format!("Fulfils reservation {} for {}", reservation_id, reservation.holder)),
+        reference: None,
+        lot: None,
+        serial_numbers: Vec::new(),
+        reservation_id: Some(reservation_id),
+        unit: None,
+        unit_quantity: None,
+    };
+    
+    match Transaction::create(&pool, removal) {
+        Ok(transaction_id) => {
+            match Transaction::find_by_id(&pool, transaction_id, true) {
+                Ok(transaction) => HttpResponse::Created().json(transaction),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Reservation fulfilled but failed to retrieve transaction".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::Validation(_) => HttpResponse::Conflict(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error fulfilling reservation: {}", e),
+            })
+        }
+    }
+}
\ No newline at end of file
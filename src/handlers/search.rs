@@ -0,0 +1,29 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use crate::db::DbPool;
+use crate::models::search;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<i64>,
+}
+
+pub async fn search_all(
+    pool: web::Data<DbPool>,
+    query: web::Query<SearchQuery>,
+) -> impl Responder {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    
+    match search::search(&pool, &query.q, limit) {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error searching: {}", e),
+        }),
+    }
+}
\ No newline at end of file
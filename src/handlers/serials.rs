@@ -0,0 +1,59 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use crate::db::{DbError, DbPool};
+use crate::models::serial_number::SerialNumber;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+pub async fn get_serial_history(
+    pool: web::Data<DbPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let serial_number = path.into_inner();
+    
+    match SerialNumber::history(&pool, &serial_number) {
+        Ok(history) => HttpResponse::Ok().json(history),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving serial number: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn retire_serial(
+    pool: web::Data<DbPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let serial_number = path.into_inner();
+    
+    match SerialNumber::retire(&pool, &serial_number) {
+        Ok(_) => {
+            match SerialNumber::find_by_serial(&pool, &serial_number) {
+                Ok(serial) => HttpResponse::Ok().json(serial),
+                Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: format!("Serial number retired but failed to retrieve: {}", e),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::Conflict(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retiring serial number: {}", e),
+            })
+        }
+    }
+}
\ No newline at end of file
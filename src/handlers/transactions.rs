@@ -1,7 +1,10 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use crate::db::{DbError, DbPool};
-use crate::models::transaction::{NewTransaction, Transaction};
+use crate::models::barcode::ItemBarcode;
+use crate::models::lot::NewLot;
+use crate::models::transaction::{NewTransaction, NewTransactionBatch, Transaction, TransactionType};
+use crate::utils::middleware::authenticated_user_id;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
@@ -40,6 +43,168 @@ pub async fn create_transaction(
     }
 }
 
+pub async fn create_batch_transactions(
+    pool: web::Data<DbPool>,
+    batch: web::Json<NewTransactionBatch>,
+) -> impl Responder {
+    let batch = batch.into_inner();
+    
+    if batch.reference.trim().is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "A reference number is required for a batch".to_string(),
+        });
+    }
+    
+    if batch.lines.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "A batch must contain at least one line".to_string(),
+        });
+    }
+    
+    match Transaction::create_batch(&pool, batch) {
+        Ok(result) if result.committed => HttpResponse::Created().json(result),
+        Ok(result) => HttpResponse::UnprocessableEntity().json(result),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error creating transaction batch: {}", e),
+        }),
+    }
+}
+
+/// A transaction keyed by a scanned SKU or alternate barcode instead of an item id.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScanTransactionRequest {
+    pub code: String,
+    pub transaction_type: TransactionType,
+    /// Defaults to one unit, or one per scanned serial number.
+    pub quantity: Option<i32>,
+    pub notes: Option<String>,
+    pub reference: Option<String>,
+    pub lot: Option<NewLot>,
+    #[serde(default)]
+    pub serial_numbers: Vec<String>,
+    pub unit: Option<String>,
+    pub unit_quantity: Option<f64>,
+}
+
+pub async fn scan_transaction(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    scan: web::Json<ScanTransactionRequest>,
+) -> impl Responder {
+    let scan = scan.into_inner();
+    
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Authentication required".to_string(),
+            });
+        }
+    };
+    
+    let item_id = match ItemBarcode::find_item(&pool, &scan.code) {
+        Ok(item_id) => item_id,
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            return status.json(ErrorResponse {
+                error: format!("No item matches code '{}'", scan.code),
+            });
+        }
+    };
+    
+    let quantity = scan.quantity.unwrap_or(if scan.serial_numbers.is_empty() {
+        1
+    } else {
+        scan.serial_numbers.len() as i32
+    });
+    
+    let new_transaction = NewTransaction {
+        item_id,
+        transaction_type: scan.transaction_type,
+        quantity,
+        user_id,
+        notes: scan.notes,
+        reference: scan.reference,
+        lot: scan.lot,
+        serial_numbers: scan.serial_numbers,
+        reservation_id: None,
+        unit: scan.unit,
+        unit_quantity: scan.unit_quantity,
+    };
+    
+    match Transaction::create(&pool, new_transaction) {
+        Ok(transaction_id) => {
+            match Transaction::find_by_id(&pool, transaction_id, true) {
+                Ok(transaction) => HttpResponse::Created().json(transaction),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Transaction created but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error creating transaction: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn reverse_transaction(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    body: web::Json<ReverseTransactionRequest>,
+) -> impl Responder {
+    let transaction_id = path.into_inner();
+    
+    let reason = body.reason.trim();
+    if reason.is_empty() {
+        return HttpResponse::BadRequest().json(ErrorResponse {
+            error: "A reason is required to reverse a transaction".to_string(),
+        });
+    }
+    
+    let user_id = match authenticated_user_id(&req) {
+        Some(user_id) => user_id,
+        None => {
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "Authentication required".to_string(),
+            });
+        }
+    };
+    
+    match Transaction::reverse(&pool, transaction_id, user_id, reason) {
+        Ok(reversal_id) => {
+            match Transaction::find_by_id(&pool, reversal_id, true) {
+                Ok(transaction) => HttpResponse::Created().json(transaction),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Transaction reversed but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::Conflict(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error reversing transaction: {}", e),
+            })
+        }
+    }
+}
+
 pub async fn get_transaction(
     pool: web::Data<DbPool>,
     path: web::Path<i64>,
@@ -106,4 +271,9 @@ pub async fn list_recent_transactions(
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RecentTransactionsQuery {
     pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReverseTransactionRequest {
+    pub reason: String,
 }
\ No newline at end of file
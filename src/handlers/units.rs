@@ -0,0 +1,52 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use crate::db::{DbError, DbPool};
+use crate::models::unit::{NewUnitOfMeasure, UnitOfMeasure};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+pub async fn create_unit(
+    pool: web::Data<DbPool>,
+    new_unit: web::Json<NewUnitOfMeasure>,
+) -> impl Responder {
+    match UnitOfMeasure::create(&pool, new_unit.into_inner()) {
+        Ok(unit_id) => {
+            match UnitOfMeasure::find_by_id(&pool, unit_id) {
+                Ok(unit) => HttpResponse::Created().json(unit),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Unit created but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let error_message = match e {
+                DbError::Sqlite(e) => {
+                    if e.to_string().contains("UNIQUE constraint failed") {
+                        "Unit code already exists".to_string()
+                    } else {
+                        format!("Database error: {}", e)
+                    }
+                }
+                _ => format!("Error creating unit: {}", e),
+            };
+            
+            HttpResponse::BadRequest().json(ErrorResponse {
+                error: error_message,
+            })
+        }
+    }
+}
+
+pub async fn list_units(
+    pool: web::Data<DbPool>,
+) -> impl Responder {
+    match UnitOfMeasure::list(&pool) {
+        Ok(units) => HttpResponse::Ok().json(units),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing units: {}", e),
+        }),
+    }
+}
\ No newline at end of file
@@ -2,7 +2,7 @@ use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use crate::auth::password;
 use crate::db::{DbError, DbPool};
-use crate::models::user::{NewUser, UpdateUser, User, UserRole};
+use crate::models::user::{NewUser, UpdateUser, User};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
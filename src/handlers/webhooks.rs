@@ -0,0 +1,165 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use crate::db::{DbError, DbPool};
+use crate::models::webhook::{NewWebhook, UpdateWebhook, Webhook, WebhookDelivery};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeliveryLogQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RetryResponse {
+    pub requeued: usize,
+}
+
+pub async fn create_webhook(
+    pool: web::Data<DbPool>,
+    new_webhook: web::Json<NewWebhook>,
+) -> impl Responder {
+    match Webhook::create(&pool, new_webhook.into_inner()) {
+        Ok(webhook_id) => {
+            match Webhook::find_by_id(&pool, webhook_id) {
+                Ok(webhook) => HttpResponse::Created().json(webhook),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Webhook created but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error creating webhook: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn list_webhooks(
+    pool: web::Data<DbPool>,
+) -> impl Responder {
+    match Webhook::list(&pool) {
+        Ok(webhooks) => HttpResponse::Ok().json(webhooks),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing webhooks: {}", e),
+        }),
+    }
+}
+
+pub async fn get_webhook(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let webhook_id = path.into_inner();
+    
+    match Webhook::find_by_id(&pool, webhook_id) {
+        Ok(webhook) => HttpResponse::Ok().json(webhook),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrieving webhook: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn update_webhook(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    update: web::Json<UpdateWebhook>,
+) -> impl Responder {
+    let webhook_id = path.into_inner();
+    
+    match Webhook::update(&pool, webhook_id, update.into_inner()) {
+        Ok(_) => {
+            match Webhook::find_by_id(&pool, webhook_id) {
+                Ok(webhook) => HttpResponse::Ok().json(webhook),
+                Err(_) => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: "Webhook updated but failed to retrieve".to_string(),
+                }),
+            }
+        }
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                DbError::Validation(_) => HttpResponse::BadRequest(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error updating webhook: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn delete_webhook(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let webhook_id = path.into_inner();
+    
+    match Webhook::delete(&pool, webhook_id) {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error deleting webhook: {}", e),
+            })
+        }
+    }
+}
+
+pub async fn list_webhook_deliveries(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+    query: web::Query<DeliveryLogQuery>,
+) -> impl Responder {
+    let webhook_id = path.into_inner();
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    
+    match WebhookDelivery::list_by_webhook(&pool, webhook_id, limit) {
+        Ok(deliveries) => HttpResponse::Ok().json(deliveries),
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Error listing webhook deliveries: {}", e),
+        }),
+    }
+}
+
+pub async fn retry_webhook_deliveries(
+    pool: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let webhook_id = path.into_inner();
+    
+    match Webhook::retry_failed(&pool, webhook_id) {
+        Ok(requeued) => HttpResponse::Ok().json(RetryResponse { requeued }),
+        Err(e) => {
+            let mut status = match e {
+                DbError::NotFound => HttpResponse::NotFound(),
+                _ => HttpResponse::InternalServerError(),
+            };
+            
+            status.json(ErrorResponse {
+                error: format!("Error retrying webhook deliveries: {}", e),
+            })
+        }
+    }
+}
\ No newline at end of file
@@ -2,29 +2,37 @@ use actix_web::{web, App, HttpServer, middleware::Logger};
 use actix_session::{storage::CookieSessionStore, SessionMiddleware};
 use actix_web::cookie::Key;
 use dotenv::dotenv;
-use log::info;
+use log::{info, warn};
 use std::io;
 use actix_web::{error, HttpResponse};
 
-mod auth;
-mod config;
-mod db;
-mod handlers;
-mod models;
-mod utils;
-
-use crate::config::Config;
-use crate::db::init_pool;
-use crate::handlers::{
+use inventory_manager::config::Config;
+use inventory_manager::db::init_pool;
+use inventory_manager::handlers::{
     auth as auth_handlers,
     users as user_handlers,
     categories as category_handlers,
     inventory as inventory_handlers,
     transactions as transaction_handlers,
     reports as report_handlers,
+    serials as serial_handlers,
+    reservations as reservation_handlers,
+    counts as count_handlers,
+    search as search_handlers,
+    units as unit_handlers,
+    kits as kit_handlers,
+    labels as label_handlers,
+    webhooks as webhook_handlers,
+    events as event_handlers,
+    alerts as alert_handlers,
+    report_schedules as report_schedule_handlers,
 };
-use crate::models::user::UserRole;
-use crate::utils::middleware::{Authentication, RoleAuthorization};
+use inventory_manager::models::user::UserRole;
+use inventory_manager::utils::middleware::{Authentication, RoleAuthorization};
+use inventory_manager::utils::alerts::{channels_from_config, run_alert_engine};
+use inventory_manager::utils::mail::Mailer;
+use inventory_manager::utils::scheduler::run_scheduler;
+use inventory_manager::utils::webhooks::run_dispatcher;
 
 #[actix_web::main]
 async fn main() -> io::Result<()> {
@@ -45,10 +53,23 @@ async fn main() -> io::Result<()> {
         },
         Err(e) => {
             eprintln!("Failed to initialize database: {}", e);
-            return Err(io::Error::new(io::ErrorKind::Other, "Database initialization failed"));
+            return Err(io::Error::other("Database initialization failed"));
         }
     };
     
+    // Deliver queued webhook events in the background
+    actix_rt::spawn(run_dispatcher(pool.clone()));
+    
+    // Raise and deliver stock alerts after each stock movement
+    actix_rt::spawn(run_alert_engine(pool.clone(), channels_from_config(&config, &pool)));
+    
+    // Generate scheduled reports into the archive and email them out
+    let mailer = Mailer::from_config(&config).unwrap_or_else(|e| {
+        warn!("Scheduled reports will not be emailed: {}", e);
+        None
+    });
+    actix_rt::spawn(run_scheduler(pool.clone(), config.report_archive_dir.clone().into(), mailer));
+    
     // Generate a random key for session encryption
     let secret_key = Key::generate();
     
@@ -65,7 +86,7 @@ async fn main() -> io::Result<()> {
             // Add global JSON extractor config to improve error reporting for 400 errors
             .app_data(
                 web::JsonConfig::default()
-                    .limit(4096) // 4KB limit, adjust as needed
+                    .limit(65536) // 64KB limit so batch transactions fit
                     .error_handler(|err, _req| {
                         let msg = format!("Invalid JSON: {}", err);
                         error::InternalError::from_response(
@@ -102,9 +123,22 @@ async fn main() -> io::Result<()> {
                             .route("", web::post().to(category_handlers::create_category))
                             .route("", web::get().to(category_handlers::list_categories))
                             .route("/search", web::get().to(category_handlers::search_categories))
+                            .route("/by-path", web::get().to(category_handlers::find_category_by_path))
                             .route("/{id}", web::get().to(category_handlers::get_category))
                             .route("/{id}", web::put().to(category_handlers::update_category))
                             .route("/{id}", web::delete().to(category_handlers::delete_category))
+                            .route("/{id}/children", web::get().to(category_handlers::list_subcategories))
+                            .route("/{id}/path", web::get().to(category_handlers::get_category_path))
+                            .route("/{id}/attributes", web::post().to(category_handlers::create_category_attribute))
+                            .route("/{id}/attributes", web::get().to(category_handlers::list_category_attributes))
+                            .route("/{id}/attributes/{attribute_id}", web::delete().to(category_handlers::delete_category_attribute))
+                    )
+                    // Unit of measure routes (authenticated)
+                    .service(
+                        web::scope("/units")
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("", web::post().to(unit_handlers::create_unit))
+                            .route("", web::get().to(unit_handlers::list_units))
                     )
                     // Inventory routes (authenticated)
                     .service(
@@ -114,7 +148,19 @@ async fn main() -> io::Result<()> {
                             .route("", web::get().to(inventory_handlers::list_items))
                             .route("/search", web::get().to(inventory_handlers::search_items))
                             .route("/low-stock", web::get().to(inventory_handlers::get_low_stock_items))
+                            .route("/labels", web::get().to(label_handlers::get_label_sheet))
+                            .route("/by-sku/{sku}", web::get().to(inventory_handlers::get_item_by_sku))
+                            .route("/by-barcode/{code}", web::get().to(inventory_handlers::get_item_by_barcode))
                             .route("/{id}", web::get().to(inventory_handlers::get_item))
+                            .route("/{id}/lots", web::get().to(inventory_handlers::list_item_lots))
+                            .route("/{id}/serials", web::get().to(inventory_handlers::list_item_serials))
+                            .route("/{id}/label", web::get().to(label_handlers::get_item_label))
+                            .route("/{id}/units", web::get().to(inventory_handlers::list_item_units))
+                            .route("/{id}/units", web::post().to(inventory_handlers::add_item_unit))
+                            .route("/{id}/units/{unit_id}", web::delete().to(inventory_handlers::remove_item_unit))
+                            .route("/{id}/barcodes", web::get().to(inventory_handlers::list_item_barcodes))
+                            .route("/{id}/barcodes", web::post().to(inventory_handlers::add_item_barcode))
+                            .route("/{id}/barcodes/{barcode_id}", web::delete().to(inventory_handlers::remove_item_barcode))
                             .route("/{id}", web::put().to(inventory_handlers::update_item))
                             .route("/{id}", web::delete().to(inventory_handlers::delete_item))
                     )
@@ -123,10 +169,118 @@ async fn main() -> io::Result<()> {
                         web::scope("/transactions")
                             .wrap(Authentication::new(config.jwt_secret.clone()))
                             .route("", web::post().to(transaction_handlers::create_transaction))
+                            .route("/batch", web::post().to(transaction_handlers::create_batch_transactions))
+                            .route("/scan", web::post().to(transaction_handlers::scan_transaction))
                             .route("/recent", web::get().to(transaction_handlers::list_recent_transactions))
                             .route("/{id}", web::get().to(transaction_handlers::get_transaction))
                             .route("/item/{id}", web::get().to(transaction_handlers::list_item_transactions))
                             .route("/user/{id}", web::get().to(transaction_handlers::list_user_transactions))
+                            // Reversals (manager or admin)
+                            .service(
+                                web::resource("/{id}/reverse")
+                                    .wrap(RoleAuthorization::new(vec![UserRole::Manager]))
+                                    .route(web::post().to(transaction_handlers::reverse_transaction))
+                            )
+                    )
+                    // Location routes (authenticated)
+                    .service(
+                        web::scope("/locations")
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("/{location}/label", web::get().to(label_handlers::get_location_label))
+                    )
+                    // Kit routes (authenticated)
+                    .service(
+                        web::scope("/kits")
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("/{id}", web::get().to(kit_handlers::get_kit))
+                            .route("/{id}/components", web::post().to(kit_handlers::add_component))
+                            .route("/{id}/components/{component_id}", web::delete().to(kit_handlers::remove_component))
+                            .route("/{id}/assemble", web::post().to(kit_handlers::assemble_kit))
+                            .route("/{id}/disassemble", web::post().to(kit_handlers::disassemble_kit))
+                    )
+                    // Reservation routes (authenticated)
+                    .service(
+                        web::scope("/reservations")
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("", web::post().to(reservation_handlers::create_reservation))
+                            .route("", web::get().to(reservation_handlers::list_reservations))
+                            .route("/{id}", web::get().to(reservation_handlers::get_reservation))
+                            .route("/{id}/release", web::post().to(reservation_handlers::release_reservation))
+                            .route("/{id}/fulfil", web::post().to(reservation_handlers::fulfil_reservation))
+                    )
+                    // Cycle count routes (authenticated)
+                    .service(
+                        web::scope("/counts")
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("", web::post().to(count_handlers::create_count_session))
+                            .route("", web::get().to(count_handlers::list_count_sessions))
+                            .route("/{id}", web::get().to(count_handlers::get_count_session))
+                            .route("/{id}/entries", web::post().to(count_handlers::record_count))
+                            // Approval posts adjustments (manager or admin)
+                            .service(
+                                web::resource("/{id}/approve")
+                                    .wrap(RoleAuthorization::new(vec![UserRole::Manager]))
+                                    .route(web::post().to(count_handlers::approve_count_session))
+                            )
+                            .route("/{id}/cancel", web::post().to(count_handlers::cancel_count_session))
+                    )
+                    // Serial number routes (authenticated)
+                    .service(
+                        web::scope("/serials")
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("/{serial}", web::get().to(serial_handlers::get_serial_history))
+                            .route("/{serial}/retire", web::post().to(serial_handlers::retire_serial))
+                    )
+                    // Search routes (authenticated)
+                    .service(
+                        web::scope("/search")
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("", web::get().to(search_handlers::search_all))
+                    )
+                    // Live event stream (authenticated)
+                    .service(
+                        web::scope("/events")
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("", web::get().to(event_handlers::stream_events))
+                    )
+                    // Alert routes (authenticated)
+                    .service(
+                        web::scope("/alerts")
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("", web::get().to(alert_handlers::list_alerts))
+                            .route("/subscriptions", web::get().to(alert_handlers::list_subscriptions))
+                            .route("/subscriptions", web::post().to(alert_handlers::create_subscription))
+                            .route("/subscriptions/{id}", web::delete().to(alert_handlers::delete_subscription))
+                            .route("/{id}", web::get().to(alert_handlers::get_alert))
+                            .route("/{id}/acknowledge", web::post().to(alert_handlers::acknowledge_alert))
+                            .route("/{id}/resolve", web::post().to(alert_handlers::resolve_alert))
+                    )
+                    // Webhook routes (manager or admin)
+                    .service(
+                        web::scope("/webhooks")
+                            .wrap(RoleAuthorization::new(vec![UserRole::Manager]))
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("", web::post().to(webhook_handlers::create_webhook))
+                            .route("", web::get().to(webhook_handlers::list_webhooks))
+                            .route("/{id}", web::get().to(webhook_handlers::get_webhook))
+                            .route("/{id}", web::put().to(webhook_handlers::update_webhook))
+                            .route("/{id}", web::delete().to(webhook_handlers::delete_webhook))
+                            .route("/{id}/deliveries", web::get().to(webhook_handlers::list_webhook_deliveries))
+                            .route("/{id}/retry", web::post().to(webhook_handlers::retry_webhook_deliveries))
+                    )
+                    // Report schedule routes (manager or admin)
+                    .service(
+                        web::scope("/report-schedules")
+                            .wrap(RoleAuthorization::new(vec![UserRole::Manager]))
+                            .wrap(Authentication::new(config.jwt_secret.clone()))
+                            .route("", web::post().to(report_schedule_handlers::create_schedule))
+                            .route("", web::get().to(report_schedule_handlers::list_schedules))
+                            .route("/{id}", web::get().to(report_schedule_handlers::get_schedule))
+                            .route("/{id}", web::put().to(report_schedule_handlers::update_schedule))
+                            .route("/{id}", web::delete().to(report_schedule_handlers::delete_schedule))
+                            .route("/{id}/run", web::post().to(report_schedule_handlers::run_schedule))
+                            .route("/{id}/runs", web::get().to(report_schedule_handlers::list_schedule_runs))
+                            .route("/{id}/runs/{run_id}/file", web::get().to(report_schedule_handlers::download_run_file))
                     )
                     // Report routes (authenticated)
                     .service(
@@ -135,6 +289,14 @@ async fn main() -> io::Result<()> {
                             .route("/inventory-summary", web::get().to(report_handlers::get_inventory_summary))
                             .route("/category-summary", web::get().to(report_handlers::get_category_summary))
                             .route("/transaction-history", web::get().to(report_handlers::get_transaction_history))
+                            .route("/expiring", web::get().to(report_handlers::get_expiring_lots))
+                            .route("/reorder-suggestions", web::get().to(report_handlers::get_reorder_suggestions))
+                            .route("/forecast/{item_id}", web::get().to(report_handlers::get_demand_forecast))
+                            .route("/abc", web::get().to(report_handlers::get_abc_analysis))
+                            .route("/turnover", web::get().to(report_handlers::get_turnover))
+                            .route("/dead-stock", web::get().to(report_handlers::get_dead_stock))
+                            .route("/ledger/{item_id}", web::get().to(report_handlers::get_item_ledger))
+                            .route("/user-activity", web::get().to(report_handlers::get_user_activity))
                     )
             )
     })
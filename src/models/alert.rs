@@ -0,0 +1,573 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::models::event::{Event, EventType};
+use crate::models::inventory_item::InventoryItem;
+
+/// Cursor name the alert engine uses in `job_cursors`.
+const ENGINE_CURSOR: &str = "alerts";
+/// Deliveries are given up on after this many failed attempts.
+pub const MAX_DELIVERY_ATTEMPTS: i32 = 5;
+/// Delay before the first retry; it doubles with every further failure.
+const RETRY_BASE_SECONDS: i64 = 30;
+const RETRY_MAX_SECONDS: i64 = 60 * 60;
+
+const ALERT_COLUMNS: &str =
+    "id, item_id, kind, status, quantity, threshold, message, created_at,
+     acknowledged_by, acknowledged_at, resolved_by, resolved_at";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum AlertKind {
+    /// At or below the item's reorder point.
+    LowStock,
+    OutOfStock,
+}
+
+impl AlertKind {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(kind: &str) -> Option<Self> {
+        match kind.to_lowercase().as_str() {
+            "low_stock" => Some(AlertKind::LowStock),
+            "out_of_stock" => Some(AlertKind::OutOfStock),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AlertKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            AlertKind::LowStock => "low_stock",
+            AlertKind::OutOfStock => "out_of_stock",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum AlertStatus {
+    Open,
+    Acknowledged,
+    Resolved,
+}
+
+impl AlertStatus {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(status: &str) -> Option<Self> {
+        match status.to_lowercase().as_str() {
+            "open" => Some(AlertStatus::Open),
+            "acknowledged" => Some(AlertStatus::Acknowledged),
+            "resolved" => Some(AlertStatus::Resolved),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AlertStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            AlertStatus::Open => "open",
+            AlertStatus::Acknowledged => "acknowledged",
+            AlertStatus::Resolved => "resolved",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Alert {
+    pub id: i64,
+    pub item_id: i64,
+    pub kind: AlertKind,
+    pub status: AlertStatus,
+    /// Quantity on hand when the alert was raised.
+    pub quantity: i32,
+    pub threshold: Option<i32>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    pub acknowledged_by: Option<i64>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<i64>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlertSubscription {
+    pub id: i64,
+    pub user_id: i64,
+    /// `None` subscribes to every category.
+    pub category_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewAlertSubscription {
+    pub category_id: Option<i64>,
+}
+
+/// A user subscribed to an alert's category.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Recipient {
+    pub user_id: i64,
+    pub username: String,
+    pub email: String,
+}
+
+/// An alert that is due to go out through one channel, with the users to
+/// notify about it.
+#[derive(Debug)]
+pub struct AlertDelivery {
+    pub id: i64,
+    pub channel: String,
+    pub attempts: i32,
+    pub alert: Alert,
+    pub recipients: Vec<Recipient>,
+}
+
+impl Alert {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let kind_str: String = row.get("kind")?;
+        let status_str: String = row.get("status")?;
+        let created_at_str: String = row.get("created_at")?;
+        let acknowledged_at_str: Option<String> = row.get("acknowledged_at")?;
+        let resolved_at_str: Option<String> = row.get("resolved_at")?;
+        
+        Ok(Alert {
+            id: row.get("id")?,
+            item_id: row.get("item_id")?,
+            kind: AlertKind::from_str(&kind_str).unwrap_or(AlertKind::LowStock),
+            status: AlertStatus::from_str(&status_str).unwrap_or(AlertStatus::Open),
+            quantity: row.get("quantity")?,
+            threshold: row.get("threshold")?,
+            message: row.get("message")?,
+            created_at: parse_timestamp(&created_at_str),
+            acknowledged_by: row.get("acknowledged_by")?,
+            acknowledged_at: acknowledged_at_str.as_deref().map(parse_timestamp),
+            resolved_by: row.get("resolved_by")?,
+            resolved_at: resolved_at_str.as_deref().map(parse_timestamp),
+        })
+    }
+    
+    pub fn find_by_id(pool: &DbPool, id: i64) -> DbResult<Self> {
+        let conn = pool.get()?;
+        Self::find_in_tx(&conn, id)
+    }
+    
+    fn find_in_tx(conn: &Connection, id: i64) -> DbResult<Self> {
+        conn.query_row(
+            &format!("SELECT {} FROM alerts WHERE id = ?", ALERT_COLUMNS),
+            params![id],
+            Self::from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::from(e),
+        })
+    }
+    
+    /// Alerts, newest first, optionally narrowed to a status or an item.
+    pub fn list(pool: &DbPool, status: Option<AlertStatus>, item_id: Option<i64>) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM alerts
+             WHERE (?1 IS NULL OR status = ?1) AND (?2 IS NULL OR item_id = ?2)
+             ORDER BY id DESC",
+            ALERT_COLUMNS
+        ))?;
+        
+        let alerts_iter = stmt.query_map(params![status.map(|s| s.to_string()), item_id], Self::from_row)?;
+        let mut alerts = Vec::new();
+        
+        for alert_result in alerts_iter {
+            match alert_result {
+                Ok(alert) => alerts.push(alert),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(alerts)
+    }
+    
+    pub fn acknowledge(pool: &DbPool, id: i64, user_id: i64) -> DbResult<()> {
+        let alert = Self::find_by_id(pool, id)?;
+        
+        if alert.status != AlertStatus::Open {
+            return Err(DbError::Validation(format!("Alert {} is already {}", id, alert.status)));
+        }
+        
+        let conn = pool.get()?;
+        conn.execute(
+            "UPDATE alerts SET status = 'acknowledged', acknowledged_by = ?, acknowledged_at = CURRENT_TIMESTAMP
+             WHERE id = ?",
+            params![user_id, id],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Closes an alert by hand. If the item is still short the engine raises
+    /// a new alert on its next stock movement.
+    pub fn resolve(pool: &DbPool, id: i64, user_id: i64) -> DbResult<()> {
+        let alert = Self::find_by_id(pool, id)?;
+        
+        if alert.status == AlertStatus::Resolved {
+            return Err(DbError::Validation(format!("Alert {} is already resolved", id)));
+        }
+        
+        let conn = pool.get()?;
+        conn.execute(
+            "UPDATE alerts SET status = 'resolved', resolved_by = ?, resolved_at = CURRENT_TIMESTAMP WHERE id = ?",
+            params![user_id, id],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Re-evaluates every item touched by events since the engine last ran
+    /// and queues a delivery on each of `channels` for every alert raised,
+    /// returning how many were raised. Items back above their threshold have
+    /// their alerts resolved automatically.
+    pub fn evaluate_pending(pool: &DbPool, channels: &[&str]) -> DbResult<usize> {
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        
+        let cursor: Option<i64> = tx
+            .query_row(
+                "SELECT last_event_id FROM job_cursors WHERE name = ?",
+                params![ENGINE_CURSOR],
+                |row| row.get(0),
+            )
+            .optional()?;
+        
+        // On first start only events from now on are evaluated
+        let last_event_id = match cursor {
+            Some(last_event_id) => last_event_id,
+            None => tx.query_row("SELECT COALESCE(MAX(id), 0) FROM events", [], |row| row.get(0))?,
+        };
+        
+        let events: Vec<(i64, String, String)> = {
+            let mut stmt = tx.prepare(
+                "SELECT id, event_type, payload FROM events WHERE id > ? ORDER BY id"
+            )?;
+            let rows = stmt.query_map(params![last_event_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        
+        let mut item_ids = BTreeSet::new();
+        let mut new_cursor = last_event_id;
+        
+        for (event_id, event_type, payload) in &events {
+            new_cursor = *event_id;
+            
+            let key = match EventType::from_str(event_type) {
+                Some(EventType::TransactionCreated) => "item_id",
+                Some(EventType::ItemCreated) | Some(EventType::ItemUpdated) | Some(EventType::ItemDeleted) => "id",
+                _ => continue,
+            };
+            
+            let payload: serde_json::Value = serde_json::from_str(payload).unwrap_or_default();
+            if let Some(item_id) = payload[key].as_i64() {
+                item_ids.insert(item_id);
+            }
+        }
+        
+        let mut raised = 0;
+        
+        for item_id in item_ids {
+            if let Some(alert_id) = Self::evaluate_item(&tx, item_id)? {
+                for channel in channels {
+                    tx.execute(
+                        "INSERT INTO alert_deliveries (alert_id, channel) VALUES (?, ?)",
+                        params![alert_id, channel],
+                    )?;
+                }
+                raised += 1;
+            }
+        }
+        
+        tx.execute(
+            "INSERT INTO job_cursors (name, last_event_id) VALUES (?, ?)
+             ON CONFLICT (name) DO UPDATE SET last_event_id = excluded.last_event_id",
+            params![ENGINE_CURSOR, new_cursor],
+        )?;
+        
+        tx.commit()?;
+        Ok(raised)
+    }
+    
+    /// Brings an item's alerts in line with its stock: resolves alerts that
+    /// no longer apply and raises one for the current shortage unless an
+    /// unresolved alert of that kind already exists. Returns the new alert's
+    /// id.
+    fn evaluate_item(conn: &Connection, item_id: i64) -> DbResult<Option<i64>> {
+        let item = match InventoryItem::find_in_tx(conn, item_id) {
+            Ok(item) => Some(item),
+            Err(DbError::NotFound) => None,
+            Err(e) => return Err(e),
+        };
+        
+        let shortage = item.as_ref().and_then(|item| {
+            if item.quantity <= 0 {
+                Some(AlertKind::OutOfStock)
+            } else if item.reorder_point.is_some_and(|reorder_point| item.quantity <= reorder_point) {
+                Some(AlertKind::LowStock)
+            } else {
+                None
+            }
+        });
+        
+        let unresolved: Vec<(i64, String)> = {
+            let mut stmt = conn.prepare(
+                "SELECT id, kind FROM alerts WHERE item_id = ? AND status != 'resolved'"
+            )?;
+            let rows = stmt.query_map(params![item_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        
+        let mut already_raised = false;
+        
+        for (alert_id, kind) in unresolved {
+            if AlertKind::from_str(&kind) == shortage {
+                already_raised = true;
+            } else {
+                conn.execute(
+                    "UPDATE alerts SET status = 'resolved', resolved_at = CURRENT_TIMESTAMP WHERE id = ?",
+                    params![alert_id],
+                )?;
+            }
+        }
+        
+        let (item, kind) = match (item, shortage) {
+            (Some(item), Some(kind)) if !already_raised => (item, kind),
+            _ => return Ok(None),
+        };
+        
+        let name = match &item.sku {
+            Some(sku) => format!("{} ({})", item.name, sku),
+            None => item.name.clone(),
+        };
+        let message = match kind {
+            AlertKind::OutOfStock => format!("{} is out of stock", name),
+            AlertKind::LowStock => format!(
+                "{} is low on stock: {} left, reorder point {}",
+                name,
+                item.quantity,
+                item.reorder_point.unwrap_or_default()
+            ),
+        };
+        
+        conn.execute(
+            "INSERT INTO alerts (item_id, kind, quantity, threshold, message) VALUES (?, ?, ?, ?, ?)",
+            params![item_id, kind.to_string(), item.quantity, item.reorder_point, message],
+        )?;
+        
+        Ok(Some(conn.last_insert_rowid()))
+    }
+    
+    /// Publishes the alert as an `alert.raised` event for webhooks and the
+    /// live event stream.
+    pub fn publish(&self, pool: &DbPool) -> DbResult<()> {
+        let conn = pool.get()?;
+        Event::record(&conn, EventType::AlertRaised, self)?;
+        Ok(())
+    }
+}
+
+impl AlertDelivery {
+    /// Pending deliveries on `channels` whose next attempt is due, oldest
+    /// first. Recipients are looked up now, so subscription changes made
+    /// while a delivery was being retried are honoured.
+    pub fn due(pool: &DbPool, channels: &[&str], limit: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let channels_json = serde_json::to_string(channels).unwrap_or_else(|_| "[]".to_string());
+        
+        let rows: Vec<(i64, String, i32, i64, Alert)> = {
+            let mut stmt = conn.prepare(
+                "SELECT d.id AS delivery_id, d.channel, d.attempts, i.category_id, a.*
+                 FROM alert_deliveries d
+                 JOIN alerts a ON a.id = d.alert_id
+                 JOIN inventory_items i ON i.id = a.item_id
+                 WHERE d.status = 'pending' AND d.next_attempt_at <= CURRENT_TIMESTAMP
+                 AND d.channel IN (SELECT value FROM json_each(?))
+                 ORDER BY d.id
+                 LIMIT ?"
+            )?;
+            let rows = stmt.query_map(params![channels_json, limit], |row| {
+                Ok((
+                    row.get("delivery_id")?,
+                    row.get("channel")?,
+                    row.get("attempts")?,
+                    row.get("category_id")?,
+                    Alert::from_row(row)?,
+                ))
+            })?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        
+        let mut deliveries = Vec::with_capacity(rows.len());
+        
+        for (id, channel, attempts, category_id, alert) in rows {
+            deliveries.push(AlertDelivery {
+                id,
+                channel,
+                attempts,
+                alert,
+                recipients: AlertSubscription::recipients(&conn, category_id)?,
+            });
+        }
+        
+        Ok(deliveries)
+    }
+    
+    /// Marks the delivery done or schedules the next retry with exponential
+    /// backoff, giving up after `MAX_DELIVERY_ATTEMPTS`. `error` is `None`
+    /// when the attempt succeeded.
+    pub fn record_attempt(&self, pool: &DbPool, error: Option<String>) -> DbResult<()> {
+        let attempt = self.attempts + 1;
+        let conn = pool.get()?;
+        
+        match error {
+            None => {
+                conn.execute(
+                    "UPDATE alert_deliveries SET status = 'delivered', attempts = ?, last_error = NULL,
+                     delivered_at = CURRENT_TIMESTAMP WHERE id = ?",
+                    params![attempt, self.id],
+                )?;
+            }
+            Some(error) if attempt >= MAX_DELIVERY_ATTEMPTS => {
+                conn.execute(
+                    "UPDATE alert_deliveries SET status = 'failed', attempts = ?, last_error = ? WHERE id = ?",
+                    params![attempt, error, self.id],
+                )?;
+            }
+            Some(error) => {
+                let delay = (RETRY_BASE_SECONDS << (attempt - 1)).min(RETRY_MAX_SECONDS);
+                conn.execute(
+                    "UPDATE alert_deliveries SET attempts = ?, last_error = ?,
+                     next_attempt_at = datetime('now', ?) WHERE id = ?",
+                    params![attempt, error, format!("+{} seconds", delay), self.id],
+                )?;
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+impl AlertSubscription {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let created_at_str: String = row.get("created_at")?;
+        
+        Ok(AlertSubscription {
+            id: row.get("id")?,
+            user_id: row.get("user_id")?,
+            category_id: row.get("category_id")?,
+            created_at: parse_timestamp(&created_at_str),
+        })
+    }
+    
+    pub fn create(pool: &DbPool, user_id: i64, new_subscription: NewAlertSubscription) -> DbResult<Self> {
+        let conn = pool.get()?;
+        
+        if let Some(category_id) = new_subscription.category_id {
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM categories WHERE id = ?)",
+                params![category_id],
+                |row| row.get(0),
+            )?;
+            
+            if !exists {
+                return Err(DbError::Validation(format!("Category {} not found", category_id)));
+            }
+        }
+        
+        let duplicate: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM alert_subscriptions WHERE user_id = ? AND category_id IS ?)",
+            params![user_id, new_subscription.category_id],
+            |row| row.get(0),
+        )?;
+        
+        if duplicate {
+            return Err(DbError::Validation("Already subscribed".to_string()));
+        }
+        
+        conn.execute(
+            "INSERT INTO alert_subscriptions (user_id, category_id) VALUES (?, ?)",
+            params![user_id, new_subscription.category_id],
+        )?;
+        
+        let id = conn.last_insert_rowid();
+        conn.query_row(
+            "SELECT id, user_id, category_id, created_at FROM alert_subscriptions WHERE id = ?",
+            params![id],
+            Self::from_row,
+        )
+        .map_err(DbError::from)
+    }
+    
+    pub fn list_by_user(pool: &DbPool, user_id: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, user_id, category_id, created_at FROM alert_subscriptions WHERE user_id = ? ORDER BY id"
+        )?;
+        
+        let subscriptions_iter = stmt.query_map(params![user_id], Self::from_row)?;
+        let mut subscriptions = Vec::new();
+        
+        for subscription_result in subscriptions_iter {
+            match subscription_result {
+                Ok(subscription) => subscriptions.push(subscription),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(subscriptions)
+    }
+    
+    /// Deletes one of the user's own subscriptions.
+    pub fn delete(pool: &DbPool, id: i64, user_id: i64) -> DbResult<()> {
+        let conn = pool.get()?;
+        let result = conn.execute(
+            "DELETE FROM alert_subscriptions WHERE id = ? AND user_id = ?",
+            params![id, user_id],
+        )?;
+        
+        if result > 0 {
+            Ok(())
+        } else {
+            Err(DbError::NotFound)
+        }
+    }
+    
+    /// Users subscribed to the category, any of its parent categories, or
+    /// to all categories.
+    pub fn recipients(conn: &Connection, category_id: i64) -> DbResult<Vec<Recipient>> {
+        let mut stmt = conn.prepare(
+            "WITH RECURSIVE chain(id, parent_id) AS (
+                SELECT id, parent_id FROM categories WHERE id = ?
+                UNION ALL
+                SELECT c.id, c.parent_id FROM categories c JOIN chain ON c.id = chain.parent_id
+             )
+             SELECT DISTINCT u.id, u.username, u.email
+             FROM alert_subscriptions s
+             JOIN users u ON u.id = s.user_id
+             WHERE s.category_id IS NULL OR s.category_id IN (SELECT id FROM chain)
+             ORDER BY u.id"
+        )?;
+        
+        let recipients = stmt
+            .query_map(params![category_id], |row| {
+                Ok(Recipient {
+                    user_id: row.get(0)?,
+                    username: row.get(1)?,
+                    email: row.get(2)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        
+        Ok(recipients)
+    }
+}
\ No newline at end of file
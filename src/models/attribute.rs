@@ -0,0 +1,452 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::models::category::Category;
+
+/// Ancestors of a category including itself, for looking up inherited attributes.
+const CATEGORY_CHAIN: &str =
+    "WITH RECURSIVE chain(id, parent_id) AS (
+        SELECT id, parent_id FROM categories WHERE id = ?
+        UNION
+        SELECT c.id, c.parent_id FROM categories c JOIN chain ON c.id = chain.parent_id
+     )";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum AttributeType {
+    Text,
+    Number,
+    Boolean,
+    Enum,
+}
+
+impl AttributeType {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(attribute_type: &str) -> Option<Self> {
+        match attribute_type.to_lowercase().as_str() {
+            "text" => Some(AttributeType::Text),
+            "number" => Some(AttributeType::Number),
+            "boolean" => Some(AttributeType::Boolean),
+            "enum" => Some(AttributeType::Enum),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AttributeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            AttributeType::Text => "text",
+            AttributeType::Number => "number",
+            AttributeType::Boolean => "boolean",
+            AttributeType::Enum => "enum",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryAttribute {
+    pub id: Option<i64>,
+    pub category_id: i64,
+    pub name: String,
+    pub attribute_type: AttributeType,
+    pub required: bool,
+    pub allowed_values: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewCategoryAttribute {
+    pub name: String,
+    pub attribute_type: AttributeType,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub allowed_values: Vec<String>,
+}
+
+/// A condition on an item attribute, written `name=value` or with one of
+/// `>=`, `<=`, `>`, `<` for numeric comparisons.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttributeFilter {
+    pub name: String,
+    pub operator: String,
+    pub value: String,
+}
+
+impl AttributeFilter {
+    pub fn parse(condition: &str) -> Option<Self> {
+        for operator in [">=", "<=", ">", "<", "="] {
+            if let Some((name, value)) = condition.split_once(operator) {
+                let name = name.trim();
+                let value = value.trim();
+                
+                if name.is_empty() || value.is_empty() {
+                    return None;
+                }
+                
+                if operator != "=" && value.parse::<f64>().is_err() {
+                    return None;
+                }
+                
+                return Some(AttributeFilter {
+                    name: name.to_string(),
+                    operator: operator.to_string(),
+                    value: value.to_string(),
+                });
+            }
+        }
+        
+        None
+    }
+    
+    /// Parses a comma-separated list of conditions, e.g. `voltage>=12,colour=red`.
+    pub fn parse_list(conditions: &str) -> Option<Vec<Self>> {
+        conditions
+            .split(',')
+            .filter(|condition| !condition.trim().is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+    
+    /// SQL condition on `inventory_items` matching this filter, with the
+    /// parameters it binds.
+    pub fn to_sql(&self) -> (String, Vec<String>) {
+        let condition = format!(
+            "EXISTS (SELECT 1 FROM item_attributes ia
+                     JOIN category_attributes ca ON ca.id = ia.attribute_id
+                     WHERE ia.item_id = inventory_items.id AND ca.name = ?
+                     AND CASE ca.attribute_type
+                         WHEN 'number' THEN json_extract(ia.value, '$') {op} CAST(? AS REAL)
+                         WHEN 'boolean' THEN json_extract(ia.value, '$') {op} (lower(?) = 'true')
+                         ELSE json_extract(ia.value, '$') {op} ?
+                     END)",
+            op = self.operator
+        );
+        
+        (
+            condition,
+            vec![self.name.clone(), self.value.clone(), self.value.clone(), self.value.clone()],
+        )
+    }
+}
+
+impl CategoryAttribute {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let type_str: String = row.get("attribute_type")?;
+        let attribute_type = AttributeType::from_str(&type_str).unwrap_or(AttributeType::Text);
+        
+        let allowed_values: Option<String> = row.get("allowed_values")?;
+        let allowed_values = allowed_values
+            .and_then(|values| serde_json::from_str(&values).ok())
+            .unwrap_or_default();
+        
+        let created_at_str: String = row.get("created_at")?;
+        let created_at = parse_timestamp(&created_at_str);
+        
+        Ok(CategoryAttribute {
+            id: row.get("id")?,
+            category_id: row.get("category_id")?,
+            name: row.get("name")?,
+            attribute_type,
+            required: row.get("required")?,
+            allowed_values,
+            created_at,
+        })
+    }
+    
+    pub fn create(pool: &DbPool, category_id: i64, new_attribute: NewCategoryAttribute) -> DbResult<i64> {
+        Category::find_by_id(pool, category_id)?;
+        
+        let name = new_attribute.name.trim();
+        if name.is_empty() {
+            return Err(DbError::Validation("Attribute name is required".to_string()));
+        }
+        
+        match new_attribute.attribute_type {
+            AttributeType::Enum if new_attribute.allowed_values.is_empty() => {
+                return Err(DbError::Validation(
+                    "Enum attributes need at least one allowed value".to_string(),
+                ));
+            }
+            AttributeType::Enum => {}
+            _ if !new_attribute.allowed_values.is_empty() => {
+                return Err(DbError::Validation(
+                    "Allowed values only apply to enum attributes".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        
+        let conn = pool.get()?;
+        
+        // Names must stay unique along every path through the tree, since
+        // items see their own category's attributes plus every ancestor's
+        let taken: bool = conn.query_row(
+            &format!(
+                "{},
+                 subtree(id) AS (
+                    SELECT id FROM categories WHERE id = ?
+                    UNION
+                    SELECT c.id FROM categories c JOIN subtree ON c.parent_id = subtree.id
+                 )
+                 SELECT EXISTS (
+                    SELECT 1 FROM category_attributes
+                    WHERE name = ? AND (category_id IN (SELECT id FROM chain)
+                                        OR category_id IN (SELECT id FROM subtree))
+                 )",
+                CATEGORY_CHAIN
+            ),
+            params![category_id, category_id, name],
+            |row| row.get(0),
+        )?;
+        
+        if taken {
+            return Err(DbError::Validation(format!(
+                "Attribute '{}' is already defined for this category or a related one",
+                name
+            )));
+        }
+        
+        let allowed_values = if new_attribute.allowed_values.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&new_attribute.allowed_values).unwrap_or_default())
+        };
+        
+        conn.execute(
+            "INSERT INTO category_attributes (category_id, name, attribute_type, required, allowed_values)
+             VALUES (?, ?, ?, ?, ?)",
+            params![
+                category_id,
+                name,
+                new_attribute.attribute_type.to_string(),
+                new_attribute.required,
+                allowed_values,
+            ],
+        )?;
+        
+        Ok(conn.last_insert_rowid())
+    }
+    
+    pub fn find_by_id(pool: &DbPool, id: i64) -> DbResult<Self> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, category_id, name, attribute_type, required, allowed_values, created_at
+             FROM category_attributes WHERE id = ?"
+        )?;
+        
+        stmt.query_row(params![id], Self::from_row)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                _ => DbError::from(e),
+            })
+    }
+    
+    /// Removes an attribute from a category along with every item's value for it.
+    pub fn delete(pool: &DbPool, category_id: i64, id: i64) -> DbResult<()> {
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        
+        tx.execute("DELETE FROM item_attributes WHERE attribute_id = ?", params![id])?;
+        let result = tx.execute(
+            "DELETE FROM category_attributes WHERE id = ? AND category_id = ?",
+            params![id, category_id],
+        )?;
+        
+        if result == 0 {
+            return Err(DbError::NotFound);
+        }
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// Lists the attributes items in a category carry, including those
+    /// inherited from its parent categories.
+    pub fn list_for_category(pool: &DbPool, category_id: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        Self::schema(&conn, category_id)
+    }
+    
+    /// Names defined both under `category_id` (itself or any subcategory) and
+    /// along the chain of `parent_id`, which moving the category under
+    /// `parent_id` would put twice on one path.
+    pub fn clashes_on_move(conn: &Connection, category_id: i64, parent_id: i64) -> DbResult<Vec<String>> {
+        let mut stmt = conn.prepare(&format!(
+            "{},
+             subtree(id) AS (
+                SELECT id FROM categories WHERE id = ?
+                UNION
+                SELECT c.id FROM categories c JOIN subtree ON c.parent_id = subtree.id
+             )
+             SELECT DISTINCT moved.name FROM category_attributes moved
+             JOIN category_attributes inherited ON inherited.name = moved.name
+             WHERE moved.category_id IN (SELECT id FROM subtree)
+             AND inherited.category_id IN (SELECT id FROM chain)
+             ORDER BY moved.name",
+            CATEGORY_CHAIN
+        ))?;
+        
+        let names = stmt
+            .query_map(params![parent_id, category_id], |row| row.get(0))?
+            .collect::<SqliteResult<_>>()?;
+        
+        Ok(names)
+    }
+    
+    fn schema(conn: &Connection, category_id: i64) -> DbResult<Vec<Self>> {
+        let mut stmt = conn.prepare(&format!(
+            "{}
+             SELECT id, category_id, name, attribute_type, required, allowed_values, created_at
+             FROM category_attributes
+             WHERE category_id IN (SELECT id FROM chain)
+             ORDER BY name",
+            CATEGORY_CHAIN
+        ))?;
+        
+        let attributes_iter = stmt.query_map(params![category_id], Self::from_row)?;
+        let mut attributes = Vec::new();
+        
+        for attribute_result in attributes_iter {
+            match attribute_result {
+                Ok(attribute) => attributes.push(attribute),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(attributes)
+    }
+    
+    fn check_value(&self, value: &Value) -> DbResult<()> {
+        let valid = match (&self.attribute_type, value) {
+            (AttributeType::Text, Value::String(_)) => true,
+            (AttributeType::Number, Value::Number(_)) => true,
+            (AttributeType::Boolean, Value::Bool(_)) => true,
+            (AttributeType::Enum, Value::String(choice)) => {
+                if !self.allowed_values.contains(choice) {
+                    return Err(DbError::Validation(format!(
+                        "Attribute '{}' must be one of: {}",
+                        self.name,
+                        self.allowed_values.join(", ")
+                    )));
+                }
+                true
+            }
+            _ => false,
+        };
+        
+        if valid {
+            Ok(())
+        } else {
+            Err(DbError::Validation(format!(
+                "Attribute '{}' must be a {} value",
+                self.name, self.attribute_type
+            )))
+        }
+    }
+    
+    /// Validates and stores attribute values for an item in `category_id`.
+    /// Values given as `null` are cleared, values the category no longer
+    /// defines are dropped, and every required attribute must end up set.
+    pub fn store_item_values(
+        conn: &Connection,
+        item_id: i64,
+        category_id: i64,
+        values: &Map<String, Value>,
+    ) -> DbResult<()> {
+        let schema = Self::schema(conn, category_id)?;
+        
+        for (name, value) in values {
+            let attribute = schema
+                .iter()
+                .find(|attribute| &attribute.name == name)
+                .ok_or_else(|| DbError::Validation(format!("Unknown attribute '{}' for this category", name)))?;
+            
+            if value.is_null() {
+                conn.execute(
+                    "DELETE FROM item_attributes WHERE item_id = ? AND attribute_id = ?",
+                    params![item_id, attribute.id],
+                )?;
+                continue;
+            }
+            
+            attribute.check_value(value)?;
+            
+            conn.execute(
+                "INSERT INTO item_attributes (item_id, attribute_id, value) VALUES (?, ?, ?)
+                 ON CONFLICT (item_id, attribute_id) DO UPDATE SET value = excluded.value",
+                params![item_id, attribute.id, value.to_string()],
+            )?;
+        }
+        
+        let stored: Vec<i64> = {
+            let mut stmt = conn.prepare("SELECT attribute_id FROM item_attributes WHERE item_id = ?")?;
+            let rows = stmt.query_map(params![item_id], |row| row.get(0))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        
+        for attribute_id in &stored {
+            if !schema.iter().any(|attribute| attribute.id == Some(*attribute_id)) {
+                conn.execute(
+                    "DELETE FROM item_attributes WHERE item_id = ? AND attribute_id = ?",
+                    params![item_id, attribute_id],
+                )?;
+            }
+        }
+        
+        for attribute in schema.iter().filter(|attribute| attribute.required) {
+            if !attribute.id.is_some_and(|id| stored.contains(&id)) {
+                return Err(DbError::Validation(format!("Attribute '{}' is required", attribute.name)));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn parsed(condition: &str) -> Option<(String, String, String)> {
+        AttributeFilter::parse(condition).map(|filter| (filter.name, filter.operator, filter.value))
+    }
+    
+    fn filter(name: &str, operator: &str, value: &str) -> Option<(String, String, String)> {
+        Some((name.to_string(), operator.to_string(), value.to_string()))
+    }
+    
+    #[test]
+    fn parse_reads_each_operator() {
+        assert_eq!(parsed("colour=red"), filter("colour", "=", "red"));
+        assert_eq!(parsed(" voltage >= 12 "), filter("voltage", ">=", "12"));
+        assert_eq!(parsed("voltage<=1.5"), filter("voltage", "<=", "1.5"));
+        assert_eq!(parsed("voltage>-3"), filter("voltage", ">", "-3"));
+        assert_eq!(parsed("voltage<240"), filter("voltage", "<", "240"));
+    }
+    
+    #[test]
+    fn parse_keeps_equals_signs_in_the_value() {
+        assert_eq!(parsed("note=a=b"), filter("note", "=", "a=b"));
+    }
+    
+    #[test]
+    fn parse_rejects_malformed_conditions() {
+        assert_eq!(parsed("colour"), None);
+        assert_eq!(parsed("=red"), None);
+        assert_eq!(parsed("colour= "), None);
+        assert_eq!(parsed("voltage>high"), None);
+    }
+    
+    #[test]
+    fn parse_list_skips_empty_conditions_and_fails_on_any_bad_one() {
+        let filters = AttributeFilter::parse_list("voltage>=12, ,colour=red,").unwrap();
+        assert_eq!(filters.len(), 2);
+        assert_eq!(filters[1].name, "colour");
+        assert!(AttributeFilter::parse_list("voltage>=12,colour").is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,164 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum BarcodeKind {
+    Gtin,
+    Upc,
+    Supplier,
+    Other,
+}
+
+impl BarcodeKind {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(kind: &str) -> Option<Self> {
+        match kind.to_lowercase().as_str() {
+            "gtin" => Some(BarcodeKind::Gtin),
+            "upc" => Some(BarcodeKind::Upc),
+            "supplier" => Some(BarcodeKind::Supplier),
+            "other" => Some(BarcodeKind::Other),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BarcodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            BarcodeKind::Gtin => "gtin",
+            BarcodeKind::Upc => "upc",
+            BarcodeKind::Supplier => "supplier",
+            BarcodeKind::Other => "other",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// A code other than the SKU that identifies an item when scanned, such as
+/// the manufacturer's GTIN or a supplier's part number.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemBarcode {
+    pub id: Option<i64>,
+    pub item_id: i64,
+    pub code: String,
+    pub kind: BarcodeKind,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewItemBarcode {
+    pub code: String,
+    pub kind: BarcodeKind,
+}
+
+impl ItemBarcode {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let kind_str: String = row.get("kind")?;
+        let kind = BarcodeKind::from_str(&kind_str).unwrap_or(BarcodeKind::Other);
+        
+        let created_at_str: String = row.get("created_at")?;
+        let created_at = parse_timestamp(&created_at_str);
+        
+        Ok(ItemBarcode {
+            id: row.get("id")?,
+            item_id: row.get("item_id")?,
+            code: row.get("code")?,
+            kind,
+            created_at,
+        })
+    }
+    
+    pub fn create(pool: &DbPool, item_id: i64, new_barcode: NewItemBarcode) -> DbResult<i64> {
+        let code = new_barcode.code.trim();
+        if code.is_empty() {
+            return Err(DbError::Validation("Barcode is required".to_string()));
+        }
+        
+        let conn = pool.get()?;
+        
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM inventory_items WHERE id = ?)",
+            params![item_id],
+            |row| row.get(0),
+        )?;
+        
+        if !exists {
+            return Err(DbError::NotFound);
+        }
+        
+        // A scan has to resolve to exactly one item, so a code cannot double
+        // as another item's SKU
+        if let Some(owner) = Self::resolve(&conn, code)? {
+            return Err(DbError::Validation(format!(
+                "Code '{}' already identifies item {}",
+                code, owner
+            )));
+        }
+        
+        conn.execute(
+            "INSERT INTO item_barcodes (item_id, code, kind) VALUES (?, ?, ?)",
+            params![item_id, code, new_barcode.kind.to_string()],
+        )?;
+        
+        Ok(conn.last_insert_rowid())
+    }
+    
+    pub fn list_by_item(pool: &DbPool, item_id: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, item_id, code, kind, created_at FROM item_barcodes WHERE item_id = ? ORDER BY code"
+        )?;
+        
+        let barcodes_iter = stmt.query_map(params![item_id], Self::from_row)?;
+        let mut barcodes = Vec::new();
+        
+        for barcode_result in barcodes_iter {
+            match barcode_result {
+                Ok(barcode) => barcodes.push(barcode),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(barcodes)
+    }
+    
+    pub fn delete(pool: &DbPool, item_id: i64, barcode_id: i64) -> DbResult<()> {
+        let conn = pool.get()?;
+        let result = conn.execute(
+            "DELETE FROM item_barcodes WHERE item_id = ? AND id = ?",
+            params![item_id, barcode_id],
+        )?;
+        
+        if result > 0 {
+            Ok(())
+        } else {
+            Err(DbError::NotFound)
+        }
+    }
+    
+    /// Item id for a scanned SKU or alternate barcode.
+    pub fn find_item(pool: &DbPool, code: &str) -> DbResult<i64> {
+        let conn = pool.get()?;
+        Self::resolve(&conn, code.trim())?.ok_or(DbError::NotFound)
+    }
+    
+    /// Finds the item a scanned code belongs to, checking SKUs first and then
+    /// alternate barcodes.
+    pub fn resolve(conn: &Connection, code: &str) -> DbResult<Option<i64>> {
+        let item_id = conn
+            .query_row(
+                "SELECT id FROM inventory_items WHERE sku = ?1
+                 UNION ALL
+                 SELECT item_id FROM item_barcodes WHERE code = ?1
+                 LIMIT 1",
+                params![code],
+                |row| row.get(0),
+            )
+            .optional()?;
+        
+        Ok(item_id)
+    }
+}
\ No newline at end of file
@@ -1,13 +1,16 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
-use rusqlite::{params, Result as SqliteResult, Row};
+use serde::{Deserialize, Deserializer, Serialize};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
 use crate::db::{DbError, DbPool, DbResult};
+use crate::models::attribute::CategoryAttribute;
+use crate::models::search::match_expression;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Category {
     pub id: Option<i64>,
     pub name: String,
     pub description: Option<String>,
+    pub parent_id: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -16,12 +19,40 @@ pub struct Category {
 pub struct NewCategory {
     pub name: String,
     pub description: Option<String>,
+    pub parent_id: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateCategory {
     pub name: Option<String>,
     pub description: Option<String>,
+    /// `null` moves the category to the top level; omitting the field leaves
+    /// the parent unchanged.
+    #[serde(default, deserialize_with = "deserialize_parent")]
+    pub parent_id: Option<Option<i64>>,
+}
+
+fn deserialize_parent<'de, D>(deserializer: D) -> Result<Option<Option<i64>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<i64>::deserialize(deserializer).map(Some)
+}
+
+/// Separator between category names in a path such as "Electrical > Cables > HDMI".
+pub const PATH_SEPARATOR: &str = " > ";
+
+/// Names can't contain the path separator, or `find_by_path` could not split
+/// them back out of a path.
+fn validate_name(name: &str) -> DbResult<()> {
+    if name.contains(PATH_SEPARATOR.trim()) {
+        return Err(DbError::Validation(format!(
+            "Category names cannot contain '{}'",
+            PATH_SEPARATOR.trim()
+        )));
+    }
+    
+    Ok(())
 }
 
 impl Category {
@@ -41,6 +72,7 @@ impl Category {
             id: row.get("id")?,
             name: row.get("name")?,
             description: row.get("description")?,
+            parent_id: row.get("parent_id")?,
             created_at,
             updated_at,
         })
@@ -49,10 +81,10 @@ impl Category {
     pub fn find_by_id(pool: &DbPool, id: i64) -> DbResult<Self> {
         let conn = pool.get()?;
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, created_at, updated_at FROM categories WHERE id = ?"
+            "SELECT id, name, description, parent_id, created_at, updated_at FROM categories WHERE id = ?"
         )?;
         
-        stmt.query_row(params![id], |row| Self::from_row(row))
+        stmt.query_row(params![id], Self::from_row)
             .map_err(|e| match e {
                 rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
                 _ => DbError::from(e),
@@ -60,11 +92,16 @@ impl Category {
     }
     
     pub fn create(pool: &DbPool, new_category: NewCategory) -> DbResult<i64> {
+        validate_name(&new_category.name)?;
         let conn = pool.get()?;
         
+        if let Some(parent_id) = new_category.parent_id {
+            Self::ensure_exists(&conn, parent_id)?;
+        }
+        
         let result = conn.execute(
-            "INSERT INTO categories (name, description) VALUES (?, ?)",
-            params![new_category.name, new_category.description],
+            "INSERT INTO categories (name, description, parent_id) VALUES (?, ?, ?)",
+            params![new_category.name, new_category.description, new_category.parent_id],
         )?;
         
         if result > 0 {
@@ -80,6 +117,7 @@ impl Category {
         let mut params = Vec::new();
         
         if let Some(name) = update.name {
+            validate_name(&name)?;
             query_parts.push("name = ?");
             params.push(name);
         }
@@ -89,6 +127,31 @@ impl Category {
             params.push(description);
         }
         
+        match update.parent_id {
+            Some(Some(parent_id)) => {
+                Self::ensure_exists(&conn, parent_id)?;
+                
+                if Self::is_descendant_or_self(&conn, parent_id, id)? {
+                    return Err(DbError::Validation(
+                        "A category cannot be moved under itself or one of its subcategories".to_string(),
+                    ));
+                }
+                
+                let clashes = CategoryAttribute::clashes_on_move(&conn, id, parent_id)?;
+                if !clashes.is_empty() {
+                    return Err(DbError::Validation(format!(
+                        "The new parent categories already define attributes named: {}",
+                        clashes.join(", ")
+                    )));
+                }
+                
+                query_parts.push("parent_id = ?");
+                params.push(parent_id.to_string());
+            }
+            Some(None) => query_parts.push("parent_id = NULL"),
+            None => {}
+        }
+        
         if query_parts.is_empty() {
             return Ok(());
         }
@@ -113,6 +176,19 @@ impl Category {
     
     pub fn delete(pool: &DbPool, id: i64) -> DbResult<()> {
         let conn = pool.get()?;
+        
+        let has_children: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM categories WHERE parent_id = ?)",
+            params![id],
+            |row| row.get(0),
+        )?;
+        
+        if has_children {
+            return Err(DbError::Validation(
+                "Category has subcategories; move or delete them first".to_string(),
+            ));
+        }
+        
         let result = conn.execute("DELETE FROM categories WHERE id = ?", params![id])?;
         
         if result > 0 {
@@ -125,11 +201,11 @@ impl Category {
     pub fn list(pool: &DbPool) -> DbResult<Vec<Self>> {
         let conn = pool.get()?;
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, created_at, updated_at 
+            "SELECT id, name, description, parent_id, created_at, updated_at 
              FROM categories ORDER BY name"
         )?;
         
-        let categories_iter = stmt.query_map([], |row| Self::from_row(row))?;
+        let categories_iter = stmt.query_map([], Self::from_row)?;
         let mut categories = Vec::new();
         
         for category_result in categories_iter {
@@ -142,18 +218,23 @@ impl Category {
         Ok(categories)
     }
     
+    /// Full-text search over names and descriptions, best matches first.
     pub fn search(pool: &DbPool, search_query: &str) -> DbResult<Vec<Self>> {
-        let conn = pool.get()?;
-        let search_query = format!("%{}%", search_query);
+        let expression = match match_expression(search_query) {
+            Some(expression) => expression,
+            None => return Ok(Vec::new()),
+        };
         
+        let conn = pool.get()?;
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, created_at, updated_at 
-             FROM categories 
-             WHERE name LIKE ? OR description LIKE ? 
-             ORDER BY name"
+            "SELECT c.id, c.name, c.description, c.parent_id, c.created_at, c.updated_at 
+             FROM categories c
+             JOIN categories_fts ON categories_fts.rowid = c.id
+             WHERE categories_fts MATCH ? 
+             ORDER BY bm25(categories_fts)"
         )?;
         
-        let categories_iter = stmt.query_map(params![search_query, search_query], |row| {
+        let categories_iter = stmt.query_map(params![expression], |row| {
             Self::from_row(row)
         })?;
         
@@ -168,4 +249,147 @@ impl Category {
         
         Ok(categories)
     }
+    
+    pub fn list_children(pool: &DbPool, id: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, parent_id, created_at, updated_at
+             FROM categories WHERE parent_id = ? ORDER BY name"
+        )?;
+        
+        let categories_iter = stmt.query_map(params![id], Self::from_row)?;
+        let mut categories = Vec::new();
+        
+        for category_result in categories_iter {
+            match category_result {
+                Ok(category) => categories.push(category),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(categories)
+    }
+    
+    /// Returns the chain of categories from the top level down to `id`.
+    pub fn ancestors(pool: &DbPool, id: i64) -> DbResult<Vec<Self>> {
+        let mut chain = vec![Self::find_by_id(pool, id)?];
+        
+        while let Some(parent_id) = chain.last().and_then(|category| category.parent_id) {
+            chain.push(Self::find_by_id(pool, parent_id)?);
+        }
+        
+        chain.reverse();
+        Ok(chain)
+    }
+    
+    /// Resolves a path such as "Electrical > Cables > HDMI" one level at a
+    /// time, so each name has to sit under the one before it.
+    pub fn find_by_path(pool: &DbPool, path: &str) -> DbResult<Self> {
+        let conn = pool.get()?;
+        let mut current: Option<i64> = None;
+        
+        for name in path.split(PATH_SEPARATOR.trim()).map(str::trim) {
+            if name.is_empty() {
+                return Err(DbError::Validation(format!("Invalid category path: {}", path)));
+            }
+            
+            let id: i64 = conn
+                .query_row(
+                    "SELECT id FROM categories WHERE name = ? AND parent_id IS ?",
+                    params![name, current],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or(DbError::NotFound)?;
+            
+            current = Some(id);
+        }
+        
+        match current {
+            Some(id) => Self::find_by_id(pool, id),
+            None => Err(DbError::NotFound),
+        }
+    }
+    
+    fn ensure_exists(conn: &Connection, id: i64) -> DbResult<()> {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM categories WHERE id = ?)",
+            params![id],
+            |row| row.get(0),
+        )?;
+        
+        if exists {
+            Ok(())
+        } else {
+            Err(DbError::Validation(format!("Parent category {} not found", id)))
+        }
+    }
+    
+    /// Walks up from `candidate` to see whether `ancestor_id` is on its path.
+    fn is_descendant_or_self(conn: &Connection, candidate: i64, ancestor_id: i64) -> DbResult<bool> {
+        let found: bool = conn.query_row(
+            "WITH RECURSIVE chain(id, parent_id) AS (
+                SELECT id, parent_id FROM categories WHERE id = ?
+                UNION
+                SELECT c.id, c.parent_id FROM categories c JOIN chain ON c.id = chain.parent_id
+             )
+             SELECT EXISTS (SELECT 1 FROM chain WHERE id = ?)",
+            params![candidate, ancestor_id],
+            |row| row.get(0),
+        )?;
+        
+        Ok(found)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::models::attribute::{AttributeType, NewCategoryAttribute};
+    
+    fn category(pool: &DbPool, name: &str, parent_id: Option<i64>) -> i64 {
+        Category::create(pool, NewCategory {
+            name: name.to_string(),
+            description: None,
+            parent_id,
+        }).unwrap()
+    }
+    
+    fn attribute(pool: &DbPool, category_id: i64, name: &str) {
+        CategoryAttribute::create(pool, category_id, NewCategoryAttribute {
+            name: name.to_string(),
+            attribute_type: AttributeType::Text,
+            required: false,
+            allowed_values: Vec::new(),
+        }).unwrap();
+    }
+    
+    fn move_under(pool: &DbPool, id: i64, parent_id: i64) -> DbResult<()> {
+        Category::update(pool, id, UpdateCategory {
+            name: None,
+            description: None,
+            parent_id: Some(Some(parent_id)),
+        })
+    }
+    
+    #[test]
+    fn move_is_refused_when_the_new_ancestors_define_the_same_attribute() {
+        let (_dir, pool) = test_pool();
+        let electrical = category(&pool, "Electrical", None);
+        let cables = category(&pool, "Cables", Some(electrical));
+        let tools = category(&pool, "Tools", None);
+        let meters = category(&pool, "Meters", Some(tools));
+        attribute(&pool, electrical, "Voltage");
+        attribute(&pool, meters, "Voltage");
+        
+        match move_under(&pool, tools, cables) {
+            Err(DbError::Validation(message)) => assert!(message.ends_with("Voltage"), "{}", message),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        assert_eq!(Category::find_by_id(&pool, tools).unwrap().parent_id, None);
+        
+        move_under(&pool, meters, electrical).unwrap_err();
+        attribute(&pool, tools, "Brand");
+        move_under(&pool, tools, category(&pool, "Hardware", None)).unwrap();
+    }
 }
\ No newline at end of file
@@ -0,0 +1,535 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use rusqlite::{params, OptionalExtension, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::models::inventory_item::{InventoryItem, InventoryItemFilter};
+use crate::models::transaction::{NewTransaction, Transaction, TransactionType};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum CountStatus {
+    Open,
+    Approved,
+    Cancelled,
+}
+
+impl CountStatus {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(status: &str) -> Option<Self> {
+        match status.to_lowercase().as_str() {
+            "open" => Some(CountStatus::Open),
+            "approved" => Some(CountStatus::Approved),
+            "cancelled" => Some(CountStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CountStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            CountStatus::Open => "open",
+            CountStatus::Approved => "approved",
+            CountStatus::Cancelled => "cancelled",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CountSession {
+    pub id: Option<i64>,
+    pub name: String,
+    pub category_id: Option<i64>,
+    pub location: Option<String>,
+    pub status: CountStatus,
+    pub created_by: i64,
+    pub approved_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub approved_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<Vec<CountLine>>,
+}
+
+/// One row of a count sheet. `expected_quantity` is the system quantity when
+/// the sheet was generated; `variance` compares the count against the item's
+/// current quantity, so movements during the count are not double counted.
+/// Once an approval has posted an adjustment for the line, the system
+/// quantity is the one that adjustment recorded replacing.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CountLine {
+    pub item_id: i64,
+    pub item_name: String,
+    pub sku: Option<String>,
+    pub location: Option<String>,
+    pub expected_quantity: i32,
+    pub system_quantity: i32,
+    pub counted_quantity: Option<i32>,
+    pub variance: Option<i32>,
+    pub counts_agree: bool,
+    /// The adjustment posted for this line when the session was approved.
+    pub adjustment_id: Option<i64>,
+    pub entries: Vec<CountEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CountEntry {
+    pub counter_id: i64,
+    pub counted_quantity: i32,
+    pub counted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewCountSession {
+    pub name: String,
+    pub category_id: Option<i64>,
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewCountEntry {
+    pub item_id: i64,
+    pub counted_quantity: i32,
+}
+
+impl CountSession {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let status_str: String = row.get("status")?;
+        let status = CountStatus::from_str(&status_str).unwrap_or(CountStatus::Open);
+        
+        let created_at_str: String = row.get("created_at")?;
+        let created_at = parse_timestamp(&created_at_str);
+        
+        Ok(CountSession {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            category_id: row.get("category_id")?,
+            location: row.get("location")?,
+            status,
+            created_by: row.get("created_by")?,
+            approved_by: row.get("approved_by")?,
+            created_at,
+            approved_at: row.get("approved_at")?,
+            lines: None,
+        })
+    }
+    
+    pub fn find_by_id(pool: &DbPool, id: i64, with_lines: bool) -> DbResult<Self> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, category_id, location, status, created_by, approved_by, created_at, approved_at
+             FROM count_sessions WHERE id = ?"
+        )?;
+        
+        let mut session = stmt.query_row(params![id], Self::from_row)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                _ => DbError::from(e),
+            })?;
+        
+        if with_lines {
+            session.lines = Some(Self::lines(pool, id)?);
+        }
+        
+        Ok(session)
+    }
+    
+    pub fn list(pool: &DbPool) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, category_id, location, status, created_by, approved_by, created_at, approved_at
+             FROM count_sessions ORDER BY id DESC"
+        )?;
+        
+        let sessions_iter = stmt.query_map([], Self::from_row)?;
+        let mut sessions = Vec::new();
+        
+        for session_result in sessions_iter {
+            match session_result {
+                Ok(session) => sessions.push(session),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(sessions)
+    }
+    
+    /// Opens a count session and generates its sheet from the items matching
+    /// the category and location. Serialized items are left off because they
+    /// are reconciled by serial number rather than by quantity.
+    pub fn create(pool: &DbPool, new_session: NewCountSession, created_by: i64) -> DbResult<i64> {
+        let filter = InventoryItemFilter {
+            category_id: new_session.category_id,
+            min_quantity: None,
+            max_quantity: None,
+            min_price: None,
+            max_price: None,
+            location: new_session.location.clone(),
+            search_query: None,
+            attributes: Vec::new(),
+        };
+        let items: Vec<InventoryItem> = InventoryItem::search(pool, filter, false)?
+            .into_iter()
+            .filter(|item| !item.serialized)
+            .collect();
+        
+        if items.is_empty() {
+            return Err(DbError::Validation("No items match the count sheet filter".to_string()));
+        }
+        
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        
+        tx.execute(
+            "INSERT INTO count_sessions (name, category_id, location, status, created_by) VALUES (?, ?, ?, ?, ?)",
+            params![
+                new_session.name,
+                new_session.category_id,
+                new_session.location,
+                CountStatus::Open.to_string(),
+                created_by,
+            ],
+        )?;
+        let session_id = tx.last_insert_rowid();
+        
+        for item in &items {
+            tx.execute(
+                "INSERT INTO count_lines (session_id, item_id, expected_quantity) VALUES (?, ?, ?)",
+                params![session_id, item.id, item.quantity],
+            )?;
+        }
+        
+        tx.commit()?;
+        
+        Ok(session_id)
+    }
+    
+    /// Records a counter's figure for an item on the sheet. A recount by the
+    /// same counter replaces their earlier figure.
+    pub fn record_count(pool: &DbPool, id: i64, entry: NewCountEntry, counter_id: i64) -> DbResult<()> {
+        if entry.counted_quantity < 0 {
+            return Err(DbError::Validation("Counted quantity cannot be negative".to_string()));
+        }
+        
+        let session = Self::find_by_id(pool, id, false)?;
+        if session.status != CountStatus::Open {
+            return Err(DbError::Validation(format!("Count session {} is {}", id, session.status)));
+        }
+        
+        let conn = pool.get()?;
+        let line_id: i64 = conn
+            .query_row(
+                "SELECT id FROM count_lines WHERE session_id = ? AND item_id = ?",
+                params![id, entry.item_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| {
+                DbError::Validation(format!("Item {} is not on this count sheet", entry.item_id))
+            })?;
+        
+        conn.execute(
+            "INSERT INTO count_entries (line_id, counter_id, counted_quantity) VALUES (?, ?, ?)
+             ON CONFLICT (line_id, counter_id) DO UPDATE SET
+                 counted_quantity = excluded.counted_quantity,
+                 counted_at = CURRENT_TIMESTAMP",
+            params![line_id, counter_id, entry.counted_quantity],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Posts an adjustment for every counted line whose count differs from the
+    /// item's current quantity, all in one SQLite transaction. Lines where
+    /// counters disagree must be recounted first; uncounted lines are skipped.
+    /// Nothing is posted if the count differs for an item that has since
+    /// become serialized, or finds more stock of a lot-tracked item.
+    pub fn approve(pool: &DbPool, id: i64, approved_by: i64) -> DbResult<Vec<i64>> {
+        let session = Self::find_by_id(pool, id, true)?;
+        if session.status != CountStatus::Open {
+            return Err(DbError::Validation(format!("Count session {} is {}", id, session.status)));
+        }
+        
+        let lines = session.lines.unwrap_or_default();
+        let disputed: Vec<String> = lines
+            .iter()
+            .filter(|line| !line.counts_agree)
+            .map(|line| line.item_name.clone())
+            .collect();
+        
+        if !disputed.is_empty() {
+            return Err(DbError::Validation(format!(
+                "Counters disagree on: {}",
+                disputed.join(", ")
+            )));
+        }
+        
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        let mut transaction_ids = Vec::new();
+        let mut serialized_variances = Vec::new();
+        let mut lot_gains = Vec::new();
+        
+        for line in &lines {
+            let counted_quantity = match line.counted_quantity {
+                Some(counted_quantity) => counted_quantity,
+                None => continue,
+            };
+            
+            // Re-read inside the transaction so the adjustment reflects any
+            // movement since the sheet was loaded
+            let (system_quantity, serialized, lot_tracked): (i32, bool, bool) = tx.query_row(
+                "SELECT quantity, serialized, EXISTS (SELECT 1 FROM item_lots WHERE item_id = inventory_items.id)
+                 FROM inventory_items WHERE id = ?",
+                params![line.item_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+            
+            if counted_quantity == system_quantity {
+                continue;
+            }
+            
+            // Sheets leave serialized items off, but an item can be switched to
+            // serial tracking after its sheet was generated
+            if serialized {
+                serialized_variances.push(line.item_name.clone());
+                continue;
+            }
+            
+            // A shortfall is taken from lots in expiry order, but found stock
+            // has no lot to go into
+            if lot_tracked && counted_quantity > system_quantity {
+                lot_gains.push(line.item_name.clone());
+                continue;
+            }
+            
+            let adjustment = NewTransaction {
+                item_id: line.item_id,
+                transaction_type: TransactionType::Adjustment,
+                quantity: counted_quantity,
+                user_id: approved_by,
+                notes: Some(format!(
+                    "Cycle count session {}: counted {}, system {}",
+                    id, counted_quantity, system_quantity
+                )),
+                reference: Some(format!("COUNT-{}", id)),
+                lot: None,
+                serial_numbers: Vec::new(),
+                reservation_id: None,
+                unit: None,
+                unit_quantity: None,
+            };
+            
+            transaction_ids.push(Transaction::create_in_tx(&tx, &adjustment)?);
+        }
+        
+        if !serialized_variances.is_empty() {
+            return Err(DbError::Validation(format!(
+                "Counts differ for serialized items, which must be corrected by adding or removing their serial numbers: {}",
+                serialized_variances.join(", ")
+            )));
+        }
+        
+        if !lot_gains.is_empty() {
+            return Err(DbError::Validation(format!(
+                "Counts found more stock than recorded for lot-tracked items, which must be received into a lot: {}",
+                lot_gains.join(", ")
+            )));
+        }
+        
+        tx.execute(
+            "UPDATE count_sessions SET status = ?, approved_by = ?, approved_at = CURRENT_TIMESTAMP WHERE id = ?",
+            params![CountStatus::Approved.to_string(), approved_by, id],
+        )?;
+        
+        tx.commit()?;
+        
+        Ok(transaction_ids)
+    }
+    
+    pub fn cancel(pool: &DbPool, id: i64) -> DbResult<()> {
+        let session = Self::find_by_id(pool, id, false)?;
+        if session.status != CountStatus::Open {
+            return Err(DbError::Validation(format!("Count session {} is {}", id, session.status)));
+        }
+        
+        let conn = pool.get()?;
+        conn.execute(
+            "UPDATE count_sessions SET status = ? WHERE id = ?",
+            params![CountStatus::Cancelled.to_string(), id],
+        )?;
+        
+        Ok(())
+    }
+    
+    fn lines(pool: &DbPool, id: i64) -> DbResult<Vec<CountLine>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT l.id, l.item_id, i.name, i.sku, i.location, l.expected_quantity, i.quantity
+             FROM count_lines l
+             JOIN inventory_items i ON i.id = l.item_id
+             WHERE l.session_id = ?
+             ORDER BY i.location, i.name"
+        )?;
+        
+        // Lines are loaded first and filled in with their counts below
+        let mut lines: Vec<(i64, CountLine)> = stmt
+            .query_map(params![id], |row| {
+                Ok((
+                    row.get(0)?,
+                    CountLine {
+                        item_id: row.get(1)?,
+                        item_name: row.get(2)?,
+                        sku: row.get(3)?,
+                        location: row.get(4)?,
+                        expected_quantity: row.get(5)?,
+                        system_quantity: row.get(6)?,
+                        counted_quantity: None,
+                        variance: None,
+                        counts_agree: true,
+                        adjustment_id: None,
+                        entries: Vec::new(),
+                    },
+                ))
+            })?
+            .collect::<SqliteResult<_>>()?;
+        
+        let mut entries_stmt = conn.prepare(
+            "SELECT counter_id, counted_quantity, counted_at FROM count_entries
+             WHERE line_id = ?
+             ORDER BY counted_at, id"
+        )?;
+        
+        let mut adjustment_stmt = conn.prepare(
+            "SELECT id, quantity_before FROM inventory_transactions
+             WHERE reference = ? AND item_id = ? AND transaction_type = 'adjustment' AND reversal_of IS NULL
+             ORDER BY id DESC LIMIT 1"
+        )?;
+        let reference = format!("COUNT-{}", id);
+        
+        for (line_id, line) in &mut lines {
+            let adjustment: Option<(i64, Option<i32>)> = adjustment_stmt
+                .query_row(params![reference, line.item_id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()?;
+            
+            if let Some((adjustment_id, quantity_before)) = adjustment {
+                line.adjustment_id = Some(adjustment_id);
+                if let Some(quantity_before) = quantity_before {
+                    line.system_quantity = quantity_before;
+                }
+            }
+            
+            line.entries = entries_stmt
+                .query_map(params![*line_id], |row| {
+                    let counted_at_str: String = row.get(2)?;
+                    let counted_at = parse_timestamp(&counted_at_str);
+                    
+                    Ok(CountEntry {
+                        counter_id: row.get(0)?,
+                        counted_quantity: row.get(1)?,
+                        counted_at,
+                    })
+                })?
+                .collect::<SqliteResult<_>>()?;
+            
+            line.counted_quantity = line.entries.last().map(|entry| entry.counted_quantity);
+            line.counts_agree = line
+                .entries
+                .iter()
+                .all(|entry| Some(entry.counted_quantity) == line.counted_quantity);
+            line.variance = line
+                .counted_quantity
+                .map(|counted| counted - line.system_quantity);
+        }
+        
+        Ok(lines.into_iter().map(|(_, line)| line).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::models::category::{Category, NewCategory};
+    use crate::models::inventory_item::NewInventoryItem;
+    use crate::models::lot::{Lot, NewLot};
+    
+    fn lot_tracked_item(pool: &DbPool) -> i64 {
+        let category_id = Category::create(pool, NewCategory {
+            name: "Reagents".to_string(),
+            description: None,
+            parent_id: None,
+        }).unwrap();
+        let item_id = InventoryItem::create(pool, NewInventoryItem {
+            name: "Buffer".to_string(),
+            description: None,
+            category_id,
+            quantity: 0,
+            unit_price: 1.0,
+            sku: None,
+            location: None,
+            serialized: false,
+            base_unit_id: None,
+            reorder_point: None,
+            supplier: None,
+            attributes: Default::default(),
+        }).unwrap();
+        
+        Transaction::create(pool, NewTransaction {
+            item_id,
+            transaction_type: TransactionType::Addition,
+            quantity: 10,
+            user_id: 1,
+            notes: None,
+            reference: None,
+            lot: Some(NewLot {
+                lot_number: "L1".to_string(),
+                manufacture_date: None,
+                expiry_date: None,
+            }),
+            serial_numbers: Vec::new(),
+            reservation_id: None,
+            unit: None,
+            unit_quantity: None,
+        }).unwrap();
+        
+        item_id
+    }
+    
+    fn count(pool: &DbPool, item_id: i64, counted_quantity: i32) -> i64 {
+        let session_id = CountSession::create(pool, NewCountSession {
+            name: "Shelf count".to_string(),
+            category_id: None,
+            location: None,
+        }, 1).unwrap();
+        CountSession::record_count(pool, session_id, NewCountEntry { item_id, counted_quantity }, 1).unwrap();
+        session_id
+    }
+    
+    #[test]
+    fn approve_refuses_found_stock_of_lot_tracked_item() {
+        let (_dir, pool) = test_pool();
+        let item_id = lot_tracked_item(&pool);
+        let session_id = count(&pool, item_id, 12);
+        
+        match CountSession::approve(&pool, session_id, 1) {
+            Err(DbError::Validation(message)) => assert!(message.contains("received into a lot"), "{}", message),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        
+        let session = CountSession::find_by_id(&pool, session_id, false).unwrap();
+        assert_eq!(session.status, CountStatus::Open);
+        assert_eq!(InventoryItem::find_by_id(&pool, item_id, false).unwrap().quantity, 10);
+    }
+    
+    #[test]
+    fn approve_takes_shortfall_of_lot_tracked_item_from_its_lots() {
+        let (_dir, pool) = test_pool();
+        let item_id = lot_tracked_item(&pool);
+        let session_id = count(&pool, item_id, 7);
+        
+        assert_eq!(CountSession::approve(&pool, session_id, 1).unwrap().len(), 1);
+        assert_eq!(InventoryItem::find_by_id(&pool, item_id, false).unwrap().quantity, 7);
+        assert_eq!(Lot::list_by_item(&pool, item_id).unwrap()[0].quantity, 7);
+    }
+}
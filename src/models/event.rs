@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum EventType {
+    #[serde(rename = "transaction.created")]
+    TransactionCreated,
+    #[serde(rename = "item.created")]
+    ItemCreated,
+    #[serde(rename = "item.updated")]
+    ItemUpdated,
+    #[serde(rename = "item.deleted")]
+    ItemDeleted,
+    /// An item's quantity fell to or below its reorder point.
+    #[serde(rename = "item.low_stock")]
+    ItemLowStock,
+    /// The alert engine raised a stock alert.
+    #[serde(rename = "alert.raised")]
+    AlertRaised,
+}
+
+impl EventType {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(event_type: &str) -> Option<Self> {
+        match event_type {
+            "transaction.created" => Some(EventType::TransactionCreated),
+            "item.created" => Some(EventType::ItemCreated),
+            "item.updated" => Some(EventType::ItemUpdated),
+            "item.deleted" => Some(EventType::ItemDeleted),
+            "item.low_stock" => Some(EventType::ItemLowStock),
+            "alert.raised" => Some(EventType::AlertRaised),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            EventType::TransactionCreated => "transaction.created",
+            EventType::ItemCreated => "item.created",
+            EventType::ItemUpdated => "item.updated",
+            EventType::ItemDeleted => "item.deleted",
+            EventType::ItemLowStock => "item.low_stock",
+            EventType::AlertRaised => "alert.raised",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Event {
+    pub id: i64,
+    pub event_type: EventType,
+    pub payload: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Event {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let event_type_str: String = row.get("event_type")?;
+        let event_type = EventType::from_str(&event_type_str).ok_or_else(|| {
+            rusqlite::Error::InvalidColumnType(0, "event_type".to_string(), rusqlite::types::Type::Text)
+        })?;
+        
+        let payload_str: String = row.get("payload")?;
+        let created_at_str: String = row.get("created_at")?;
+        
+        Ok(Event {
+            id: row.get("id")?,
+            event_type,
+            payload: serde_json::from_str(&payload_str).unwrap_or(Value::Null),
+            created_at: parse_timestamp(&created_at_str),
+        })
+    }
+    
+    /// Records an event and queues it for every active webhook subscribed to
+    /// it. Call this inside the SQLite transaction making the change so the
+    /// event exists exactly when the change is committed.
+    pub fn record<T: Serialize>(conn: &Connection, event_type: EventType, data: &T) -> DbResult<i64> {
+        let payload = serde_json::to_string(data).unwrap_or_else(|_| "null".to_string());
+        
+        conn.execute(
+            "INSERT INTO events (event_type, payload) VALUES (?, ?)",
+            params![event_type.to_string(), payload],
+        )?;
+        let event_id = conn.last_insert_rowid();
+        
+        let webhooks: Vec<(i64, String)> = {
+            let mut stmt = conn.prepare("SELECT id, events FROM webhooks WHERE active = 1")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        
+        for (webhook_id, events_json) in webhooks {
+            // An empty filter subscribes to every event
+            let events: Vec<EventType> = serde_json::from_str(&events_json).unwrap_or_default();
+            if events.is_empty() || events.contains(&event_type) {
+                conn.execute(
+                    "INSERT INTO webhook_outbox (webhook_id, event_id) VALUES (?, ?)",
+                    params![webhook_id, event_id],
+                )?;
+            }
+        }
+        
+        Ok(event_id)
+    }
+    
+    /// Events recorded after `after_id`, oldest first.
+    pub fn list_after(pool: &DbPool, after_id: i64, limit: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, event_type, payload, created_at FROM events WHERE id > ? ORDER BY id LIMIT ?"
+        )?;
+        
+        let events_iter = stmt.query_map(params![after_id, limit], Self::from_row)?;
+        let mut events = Vec::new();
+        
+        for event_result in events_iter {
+            match event_result {
+                Ok(event) => events.push(event),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(events)
+    }
+    
+    /// Id of the most recent event, or 0 when there are none.
+    pub fn latest_id(pool: &DbPool) -> DbResult<i64> {
+        let conn = pool.get()?;
+        let latest_id = conn.query_row("SELECT COALESCE(MAX(id), 0) FROM events", [], |row| row.get(0))?;
+        Ok(latest_id)
+    }
+}
\ No newline at end of file
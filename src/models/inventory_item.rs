@@ -1,8 +1,25 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use rusqlite::{params, Result as SqliteResult, Row};
-use crate::db::{DbError, DbPool, DbResult};
+use serde_json::{json, Map, Value};
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::models::attribute::{AttributeFilter, CategoryAttribute};
 use crate::models::category::Category;
+use crate::models::event::{Event, EventType};
+use crate::models::search::match_expression;
+
+/// Item columns plus `available_quantity`, the on-hand quantity less units
+/// held by live reservations.
+pub const ITEM_COLUMNS: &str =
+    "id, name, description, category_id, quantity, unit_price, sku, location, serialized, base_unit_id, reorder_point, supplier,
+     (SELECT code FROM units WHERE units.id = inventory_items.base_unit_id) AS base_unit, created_at, updated_at,
+     quantity - COALESCE((SELECT SUM(r.quantity) FROM reservations r
+                          WHERE r.item_id = inventory_items.id AND r.status = 'active'
+                          AND (r.expires_at IS NULL OR datetime(r.expires_at) > datetime('now'))), 0)
+         AS available_quantity,
+     (SELECT json_group_object(ca.name, json(ia.value)) FROM item_attributes ia
+      JOIN category_attributes ca ON ca.id = ia.attribute_id
+      WHERE ia.item_id = inventory_items.id) AS attributes";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InventoryItem {
@@ -11,9 +28,19 @@ pub struct InventoryItem {
     pub description: Option<String>,
     pub category_id: i64,
     pub quantity: i32,
+    pub available_quantity: i32,
     pub unit_price: f64,
     pub sku: Option<String>,
     pub location: Option<String>,
+    pub serialized: bool,
+    /// Unit the quantity is kept in; `None` means plain units.
+    pub base_unit_id: Option<i64>,
+    pub base_unit: Option<String>,
+    /// Quantity at or below which the item is low on stock.
+    pub reorder_point: Option<i32>,
+    /// Who the item is bought from, used to group reorder suggestions.
+    pub supplier: Option<String>,
+    pub attributes: Map<String, Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -29,6 +56,13 @@ pub struct NewInventoryItem {
     pub unit_price: f64,
     pub sku: Option<String>,
     pub location: Option<String>,
+    #[serde(default)]
+    pub serialized: bool,
+    pub base_unit_id: Option<i64>,
+    pub reorder_point: Option<i32>,
+    pub supplier: Option<String>,
+    #[serde(default)]
+    pub attributes: Map<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,6 +74,12 @@ pub struct UpdateInventoryItem {
     pub unit_price: Option<f64>,
     pub sku: Option<String>,
     pub location: Option<String>,
+    pub serialized: Option<bool>,
+    pub base_unit_id: Option<i64>,
+    pub reorder_point: Option<i32>,
+    pub supplier: Option<String>,
+    /// Attribute values to set; a `null` value clears that attribute.
+    pub attributes: Option<Map<String, Value>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -51,6 +91,8 @@ pub struct InventoryItemFilter {
     pub max_price: Option<f64>,
     pub location: Option<String>,
     pub search_query: Option<String>,
+    #[serde(default)]
+    pub attributes: Vec<AttributeFilter>,
 }
 
 impl InventoryItem {
@@ -58,13 +100,13 @@ impl InventoryItem {
         let created_at_str: String = row.get("created_at")?;
         let updated_at_str: String = row.get("updated_at")?;
         
-        let created_at = DateTime::parse_from_rfc3339(&created_at_str)
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(|_| Utc::now());
+        let created_at = parse_timestamp(&created_at_str);
+        let updated_at = parse_timestamp(&updated_at_str);
         
-        let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(|_| Utc::now());
+        let attributes_json: Option<String> = row.get("attributes")?;
+        let attributes = attributes_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
         
         Ok(InventoryItem {
             id: row.get("id")?,
@@ -72,9 +114,16 @@ impl InventoryItem {
             description: row.get("description")?,
             category_id: row.get("category_id")?,
             quantity: row.get("quantity")?,
+            available_quantity: row.get("available_quantity")?,
             unit_price: row.get("unit_price")?,
             sku: row.get("sku")?,
             location: row.get("location")?,
+            serialized: row.get("serialized")?,
+            base_unit_id: row.get("base_unit_id")?,
+            base_unit: row.get("base_unit")?,
+            reorder_point: row.get("reorder_point")?,
+            supplier: row.get("supplier")?,
+            attributes,
             created_at,
             updated_at,
             category: None,
@@ -83,12 +132,12 @@ impl InventoryItem {
     
     pub fn find_by_id(pool: &DbPool, id: i64, with_category: bool) -> DbResult<Self> {
         let conn = pool.get()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, name, description, category_id, quantity, unit_price, sku, location, created_at, updated_at 
-             FROM inventory_items WHERE id = ?"
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM inventory_items WHERE id = ?",
+            ITEM_COLUMNS
+        ))?;
         
-        let mut item = stmt.query_row(params![id], |row| Self::from_row(row))?;
+        let mut item = stmt.query_row(params![id], Self::from_row)?;
         
         if with_category {
             item.category = Some(Category::find_by_id(pool, item.category_id)?);
@@ -98,11 +147,24 @@ impl InventoryItem {
     }
     
     pub fn create(pool: &DbPool, new_item: NewInventoryItem) -> DbResult<i64> {
-        let conn = pool.get()?;
+        // Serialized stock is received unit by unit so every unit has a serial
+        if new_item.serialized && new_item.quantity != 0 {
+            return Err(DbError::Validation(
+                "Serialized items must be created with zero quantity and received with serial numbers".to_string(),
+            ));
+        }
         
-        let result = conn.execute(
-            "INSERT INTO inventory_items (name, description, category_id, quantity, unit_price, sku, location) 
-             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        let mut conn = pool.get()?;
+        
+        if let Some(sku) = &new_item.sku {
+            Self::ensure_sku_free(&conn, sku)?;
+        }
+        
+        let tx = conn.transaction()?;
+        
+        let result = tx.execute(
+            "INSERT INTO inventory_items (name, description, category_id, quantity, unit_price, sku, location, serialized, base_unit_id, reorder_point, supplier) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 new_item.name,
                 new_item.description,
@@ -111,18 +173,54 @@ impl InventoryItem {
                 new_item.unit_price,
                 new_item.sku,
                 new_item.location,
+                new_item.serialized,
+                new_item.base_unit_id,
+                new_item.reorder_point,
+                new_item.supplier,
             ],
         )?;
         
-        if result > 0 {
-            Ok(conn.last_insert_rowid())
-        } else {
-            Err(DbError::NoRowsAffected)
+        if result == 0 {
+            return Err(DbError::NoRowsAffected);
         }
+        
+        let item_id = tx.last_insert_rowid();
+        CategoryAttribute::store_item_values(&tx, item_id, new_item.category_id, &new_item.attributes)?;
+        Event::record(&tx, EventType::ItemCreated, &Self::find_in_tx(&tx, item_id)?)?;
+        tx.commit()?;
+        
+        Ok(item_id)
     }
     
     pub fn update(pool: &DbPool, id: i64, update: UpdateInventoryItem) -> DbResult<()> {
-        let conn = pool.get()?;
+        let mut conn = pool.get()?;
+        
+        // Serialized stock only moves through transactions naming its serials
+        if update.quantity.is_some() || update.serialized.is_some() {
+            let (quantity, serialized): (i32, bool) = conn
+                .query_row(
+                    "SELECT quantity, serialized FROM inventory_items WHERE id = ?",
+                    params![id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .map_err(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                    _ => DbError::from(e),
+                })?;
+            
+            if update.serialized.is_some_and(|flag| flag != serialized) && quantity != 0 {
+                return Err(DbError::Validation(
+                    "Serial tracking can only be switched on or off while the item has no stock".to_string(),
+                ));
+            }
+            
+            if update.quantity.is_some() && (serialized || update.serialized == Some(true)) {
+                return Err(DbError::Validation(
+                    "The quantity of a serialized item changes only through transactions with serial numbers".to_string(),
+                ));
+            }
+        }
+        
         let mut query_parts = Vec::new();
         let mut params = Vec::new();
         
@@ -152,6 +250,7 @@ impl InventoryItem {
         }
         
         if let Some(sku) = update.sku {
+            Self::ensure_sku_free(&conn, &sku)?;
             query_parts.push("sku = ?");
             params.push(sku);
         }
@@ -161,7 +260,27 @@ impl InventoryItem {
             params.push(location);
         }
         
-        if query_parts.is_empty() {
+        if let Some(serialized) = update.serialized {
+            query_parts.push("serialized = ?");
+            params.push((serialized as i32).to_string());
+        }
+        
+        if let Some(base_unit_id) = update.base_unit_id {
+            query_parts.push("base_unit_id = ?");
+            params.push(base_unit_id.to_string());
+        }
+        
+        if let Some(reorder_point) = update.reorder_point {
+            query_parts.push("reorder_point = ?");
+            params.push(reorder_point.to_string());
+        }
+        
+        if let Some(supplier) = update.supplier {
+            query_parts.push("supplier = ?");
+            params.push(supplier);
+        }
+        
+        if query_parts.is_empty() && update.attributes.is_none() {
             return Ok(());
         }
         
@@ -174,34 +293,104 @@ impl InventoryItem {
         
         params.push(id.to_string());
         
-        let result = conn.execute(&query, rusqlite::params_from_iter(params))?;
+        let tx = conn.transaction()?;
+        let result = tx.execute(&query, rusqlite::params_from_iter(params))?;
         
-        if result > 0 {
-            Ok(())
-        } else {
-            Err(DbError::NoRowsAffected)
+        if result == 0 {
+            return Err(DbError::NoRowsAffected);
+        }
+        
+        // Re-check attributes against the (possibly new) category's schema
+        if update.attributes.is_some() || update.category_id.is_some() {
+            let category_id: i64 = tx.query_row(
+                "SELECT category_id FROM inventory_items WHERE id = ?",
+                params![id],
+                |row| row.get(0),
+            )?;
+            CategoryAttribute::store_item_values(&tx, id, category_id, &update.attributes.unwrap_or_default())?;
+        }
+        
+        Event::record(&tx, EventType::ItemUpdated, &Self::find_in_tx(&tx, id)?)?;
+        tx.commit()?;
+        Ok(())
+    }
+    
+    pub fn find_by_sku(pool: &DbPool, sku: &str, with_category: bool) -> DbResult<Self> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM inventory_items WHERE sku = ?",
+            ITEM_COLUMNS
+        ))?;
+        
+        let mut item = stmt.query_row(params![sku], Self::from_row)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                _ => DbError::from(e),
+            })?;
+        
+        if with_category {
+            item.category = Some(Category::find_by_id(pool, item.category_id)?);
         }
+        
+        Ok(item)
+    }
+    
+    /// SKUs and alternate barcodes share one namespace so a scan is never ambiguous.
+    fn ensure_sku_free(conn: &Connection, sku: &str) -> DbResult<()> {
+        let taken: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM item_barcodes WHERE code = ?)",
+            params![sku],
+            |row| row.get(0),
+        )?;
+        
+        if taken {
+            return Err(DbError::Validation(format!(
+                "SKU '{}' is already in use as an alternate barcode",
+                sku
+            )));
+        }
+        
+        Ok(())
     }
     
     pub fn delete(pool: &DbPool, id: i64) -> DbResult<()> {
-        let conn = pool.get()?;
-        let result = conn.execute("DELETE FROM inventory_items WHERE id = ?", params![id])?;
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
         
-        if result > 0 {
-            Ok(())
-        } else {
-            Err(DbError::NoRowsAffected)
+        tx.execute("DELETE FROM item_attributes WHERE item_id = ?", params![id])?;
+        tx.execute("DELETE FROM item_barcodes WHERE item_id = ?", params![id])?;
+        let result = tx.execute("DELETE FROM inventory_items WHERE id = ?", params![id])?;
+        
+        if result == 0 {
+            return Err(DbError::NoRowsAffected);
         }
+        
+        Event::record(&tx, EventType::ItemDeleted, &json!({ "id": id }))?;
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// Reads an item through a connection that may be mid-transaction.
+    pub fn find_in_tx(conn: &Connection, id: i64) -> DbResult<Self> {
+        conn.query_row(
+            &format!("SELECT {} FROM inventory_items WHERE id = ?", ITEM_COLUMNS),
+            params![id],
+            Self::from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::from(e),
+        })
     }
     
     pub fn list(pool: &DbPool, with_category: bool) -> DbResult<Vec<Self>> {
         let conn = pool.get()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, name, description, category_id, quantity, unit_price, sku, location, created_at, updated_at 
-             FROM inventory_items ORDER BY name"
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM inventory_items ORDER BY name",
+            ITEM_COLUMNS
+        ))?;
         
-        let items_iter = stmt.query_map([], |row| Self::from_row(row))?;
+        let items_iter = stmt.query_map([], Self::from_row)?;
         let mut items = Vec::new();
         
         for item_result in items_iter {
@@ -256,24 +445,51 @@ impl InventoryItem {
             params.push(format!("%{}%", location));
         }
         
-        if let Some(search_query) = filter.search_query {
-            conditions.push("(name LIKE ? OR description LIKE ? OR sku LIKE ?)");
-            let search_pattern = format!("%{}%", search_query);
-            params.push(search_pattern.clone());
-            params.push(search_pattern.clone());
-            params.push(search_pattern);
+        // Text queries go through the full-text index and rank the results;
+        // one with nothing searchable in it matches nothing
+        let search_expression = match filter.search_query.as_deref() {
+            Some(search_query) => match match_expression(search_query) {
+                Some(expression) => Some(expression),
+                None => return Ok(Vec::new()),
+            },
+            None => None,
+        };
+        
+        if let Some(expression) = &search_expression {
+            conditions.push("id IN (SELECT rowid FROM items_fts WHERE items_fts MATCH ?)");
+            params.push(expression.clone());
         }
         
+        let attribute_conditions: Vec<String> = filter
+            .attributes
+            .iter()
+            .map(|attribute_filter| {
+                let (condition, condition_params) = attribute_filter.to_sql();
+                params.extend(condition_params);
+                condition
+            })
+            .collect();
+        conditions.extend(attribute_conditions.iter().map(String::as_str));
+        
         let where_clause = if conditions.is_empty() {
             String::new()
         } else {
             format!("WHERE {}", conditions.join(" AND "))
         };
         
+        let order_by = match search_expression {
+            Some(expression) => {
+                params.push(expression);
+                "(SELECT bm25(items_fts) FROM items_fts WHERE items_fts MATCH ? AND rowid = inventory_items.id), name"
+            }
+            None => "name",
+        };
+        
         let query = format!(
-            "SELECT id, name, description, category_id, quantity, unit_price, sku, location, created_at, updated_at 
-             FROM inventory_items {} ORDER BY name",
-            where_clause
+            "SELECT {} FROM inventory_items {} ORDER BY {}",
+            ITEM_COLUMNS,
+            where_clause,
+            order_by
         );
         
         let mut stmt = conn.prepare(&query)?;
@@ -325,14 +541,15 @@ impl InventoryItem {
     
     pub fn get_low_stock_items(pool: &DbPool, threshold: i32) -> DbResult<Vec<Self>> {
         let conn = pool.get()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, name, description, category_id, quantity, unit_price, sku, location, created_at, updated_at 
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
              FROM inventory_items 
              WHERE quantity <= ? 
-             ORDER BY quantity ASC"
-        )?;
+             ORDER BY quantity ASC",
+            ITEM_COLUMNS
+        ))?;
         
-        let items_iter = stmt.query_map(params![threshold], |row| Self::from_row(row))?;
+        let items_iter = stmt.query_map(params![threshold], Self::from_row)?;
         let mut items = Vec::new();
         
         for item_result in items_iter {
@@ -0,0 +1,406 @@
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use crate::db::{DbError, DbPool, DbResult};
+use crate::models::inventory_item::{InventoryItem, ITEM_COLUMNS};
+use crate::models::lot::{Lot, NewLot};
+use crate::models::transaction::{NewTransaction, Transaction, TransactionType};
+
+/// One line of a kit's bill of materials.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BomComponent {
+    pub id: i64,
+    pub kit_id: i64,
+    pub component_id: i64,
+    /// Units of the component used in one kit.
+    pub quantity: i32,
+    pub component_name: String,
+    pub sku: Option<String>,
+    pub available_quantity: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewBomComponent {
+    pub component_id: i64,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Kit {
+    pub item: InventoryItem,
+    pub components: Vec<BomComponent>,
+    /// How many kits the available component stock is enough to build.
+    pub buildable_quantity: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KitOperation {
+    pub quantity: i32,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+    /// Lot the kits are built into, or taken from when disassembling.
+    pub lot: Option<NewLot>,
+}
+
+impl Kit {
+    pub fn find(pool: &DbPool, kit_id: i64) -> DbResult<Self> {
+        let item = InventoryItem::find_by_id(pool, kit_id, false).map_err(|e| match e {
+            DbError::Sqlite(rusqlite::Error::QueryReturnedNoRows) => DbError::NotFound,
+            _ => e,
+        })?;
+        
+        let conn = pool.get()?;
+        let components = Self::components(&conn, kit_id)?;
+        let buildable_quantity = Self::buildable(&components);
+        
+        Ok(Kit {
+            item,
+            components,
+            buildable_quantity,
+        })
+    }
+    
+    /// Adds a component to a kit's bill of materials. A kit cannot contain
+    /// itself, directly or through a sub-assembly.
+    pub fn add_component(pool: &DbPool, kit_id: i64, new_component: NewBomComponent) -> DbResult<()> {
+        if new_component.quantity <= 0 {
+            return Err(DbError::Validation("Component quantity must be positive".to_string()));
+        }
+        
+        let conn = pool.get()?;
+        
+        for item_id in [kit_id, new_component.component_id] {
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM inventory_items WHERE id = ?)",
+                params![item_id],
+                |row| row.get(0),
+            )?;
+            
+            if !exists {
+                return Err(DbError::NotFound);
+            }
+        }
+        
+        let creates_cycle: bool = conn.query_row(
+            "WITH RECURSIVE parts(id) AS (
+                SELECT ?
+                UNION
+                SELECT b.component_id FROM bom_components b JOIN parts ON b.kit_id = parts.id
+             )
+             SELECT EXISTS (SELECT 1 FROM parts WHERE id = ?)",
+            params![new_component.component_id, kit_id],
+            |row| row.get(0),
+        )?;
+        
+        if creates_cycle {
+            return Err(DbError::Validation(
+                "A kit cannot contain itself as a component".to_string(),
+            ));
+        }
+        
+        conn.execute(
+            "INSERT INTO bom_components (kit_id, component_id, quantity) VALUES (?, ?, ?)
+             ON CONFLICT (kit_id, component_id) DO UPDATE SET quantity = excluded.quantity",
+            params![kit_id, new_component.component_id, new_component.quantity],
+        )?;
+        
+        Ok(())
+    }
+    
+    pub fn remove_component(pool: &DbPool, kit_id: i64, component_id: i64) -> DbResult<()> {
+        let conn = pool.get()?;
+        let result = conn.execute(
+            "DELETE FROM bom_components WHERE kit_id = ? AND component_id = ?",
+            params![kit_id, component_id],
+        )?;
+        
+        if result > 0 {
+            Ok(())
+        } else {
+            Err(DbError::NotFound)
+        }
+    }
+    
+    /// Builds `quantity` kits: removes the components and adds the kits in a
+    /// single SQLite transaction. Returns the ids of the transactions posted.
+    pub fn assemble(pool: &DbPool, kit_id: i64, operation: KitOperation, user_id: i64) -> DbResult<Vec<i64>> {
+        Self::run(pool, kit_id, operation, user_id, true)
+    }
+    
+    /// Breaks `quantity` kits back down into their components. Components
+    /// tracked by lot can't be returned this way, since there is no lot to
+    /// return them to.
+    pub fn disassemble(pool: &DbPool, kit_id: i64, operation: KitOperation, user_id: i64) -> DbResult<Vec<i64>> {
+        Self::run(pool, kit_id, operation, user_id, false)
+    }
+    
+    fn run(pool: &DbPool, kit_id: i64, operation: KitOperation, user_id: i64, assemble: bool) -> DbResult<Vec<i64>> {
+        if operation.quantity <= 0 {
+            return Err(DbError::Validation("Quantity must be positive".to_string()));
+        }
+        
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        
+        let kit = tx
+            .query_row(
+                &format!("SELECT {} FROM inventory_items WHERE id = ?", ITEM_COLUMNS),
+                params![kit_id],
+                InventoryItem::from_row,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                _ => DbError::from(e),
+            })?;
+        
+        let components = Self::components(&tx, kit_id)?;
+        
+        if components.is_empty() {
+            return Err(DbError::Validation(format!("{} has no bill of materials", kit.name)));
+        }
+        
+        if assemble {
+            let buildable = Self::buildable(&components);
+            if operation.quantity > buildable {
+                return Err(DbError::Validation(format!(
+                    "Only {} of {} can be built from available components",
+                    buildable, kit.name
+                )));
+            }
+        } else if operation.quantity > kit.available_quantity {
+            return Err(DbError::Validation(format!(
+                "Only {} of {} are available to disassemble",
+                kit.available_quantity, kit.name
+            )));
+        }
+        
+        if !assemble {
+            let mut lot_tracked = Vec::new();
+            for component in &components {
+                if Lot::is_tracked(&tx, component.component_id)? {
+                    lot_tracked.push(component.component_name.clone());
+                }
+            }
+            
+            if !lot_tracked.is_empty() {
+                return Err(DbError::Validation(format!(
+                    "Disassembling {} would return lot-tracked components, which must be received into a lot: {}",
+                    kit.name,
+                    lot_tracked.join(", ")
+                )));
+            }
+        }
+        
+        let (verb, component_type, kit_type) = if assemble {
+            ("Assembly", TransactionType::Removal, TransactionType::Addition)
+        } else {
+            ("Disassembly", TransactionType::Addition, TransactionType::Removal)
+        };
+        
+        let reference = operation
+            .reference
+            .unwrap_or_else(|| format!("{}-{}", verb.to_uppercase(), kit_id));
+        let notes = operation
+            .notes
+            .unwrap_or_else(|| format!("{} of {} x {}", verb, operation.quantity, kit.name));
+        
+        let mut movements: Vec<(i64, TransactionType, i32, Option<NewLot>)> = Vec::with_capacity(components.len() + 1);
+        
+        for component in &components {
+            let quantity = component.quantity.checked_mul(operation.quantity).ok_or_else(|| {
+                DbError::Validation(format!(
+                    "{} x {} needs more {} than can be recorded",
+                    operation.quantity, kit.name, component.component_name
+                ))
+            })?;
+            movements.push((component.component_id, component_type.clone(), quantity, None));
+        }
+        movements.push((kit_id, kit_type, operation.quantity, operation.lot));
+        
+        let mut transaction_ids = Vec::with_capacity(movements.len());
+        
+        for (item_id, transaction_type, quantity, lot) in movements {
+            let new_transaction = NewTransaction {
+                item_id,
+                transaction_type,
+                quantity,
+                user_id,
+                notes: Some(notes.clone()),
+                reference: Some(reference.clone()),
+                lot,
+                serial_numbers: Vec::new(),
+                reservation_id: None,
+                unit: None,
+                unit_quantity: None,
+            };
+            transaction_ids.push(Transaction::create_in_tx(&tx, &new_transaction)?);
+        }
+        
+        tx.commit()?;
+        
+        Ok(transaction_ids)
+    }
+    
+    fn components(conn: &Connection, kit_id: i64) -> DbResult<Vec<BomComponent>> {
+        let lines: Vec<(i64, i64, i32)> = {
+            let mut stmt = conn.prepare(
+                "SELECT id, component_id, quantity FROM bom_components WHERE kit_id = ? ORDER BY id"
+            )?;
+            let rows = stmt.query_map(params![kit_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        
+        let mut components = Vec::with_capacity(lines.len());
+        
+        for (id, component_id, quantity) in lines {
+            let component = conn.query_row(
+                &format!("SELECT {} FROM inventory_items WHERE id = ?", ITEM_COLUMNS),
+                params![component_id],
+                InventoryItem::from_row,
+            )?;
+            
+            components.push(BomComponent {
+                id,
+                kit_id,
+                component_id,
+                quantity,
+                component_name: component.name,
+                sku: component.sku,
+                available_quantity: component.available_quantity,
+            });
+        }
+        
+        Ok(components)
+    }
+    
+    fn buildable(components: &[BomComponent]) -> i32 {
+        components
+            .iter()
+            .map(|component| (component.available_quantity / component.quantity).max(0))
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::models::category::{Category, NewCategory};
+    use crate::models::inventory_item::NewInventoryItem;
+    
+    fn item(pool: &DbPool, category_id: i64, name: &str, quantity: i32) -> i64 {
+        InventoryItem::create(pool, NewInventoryItem {
+            name: name.to_string(),
+            description: None,
+            category_id,
+            quantity,
+            unit_price: 1.0,
+            sku: None,
+            location: None,
+            serialized: false,
+            base_unit_id: None,
+            reorder_point: None,
+            supplier: None,
+            attributes: Default::default(),
+        }).unwrap()
+    }
+    
+    fn lot(lot_number: &str) -> Option<NewLot> {
+        Some(NewLot {
+            lot_number: lot_number.to_string(),
+            manufacture_date: None,
+            expiry_date: None,
+        })
+    }
+    
+    fn operation(quantity: i32, lot: Option<NewLot>) -> KitOperation {
+        KitOperation {
+            quantity,
+            reference: None,
+            notes: None,
+            lot,
+        }
+    }
+    
+    fn receive(pool: &DbPool, item_id: i64, quantity: i32, lot: Option<NewLot>) {
+        Transaction::create(pool, NewTransaction {
+            item_id,
+            transaction_type: TransactionType::Addition,
+            quantity,
+            user_id: 1,
+            notes: None,
+            reference: None,
+            lot,
+            serial_numbers: Vec::new(),
+            reservation_id: None,
+            unit: None,
+            unit_quantity: None,
+        }).unwrap();
+    }
+    
+    /// A first-aid kit of two bandages each, with `bandages` in stock.
+    fn first_aid_kit(pool: &DbPool, bandages: i32) -> (i64, i64) {
+        let category_id = Category::create(pool, NewCategory {
+            name: "Medical".to_string(),
+            description: None,
+            parent_id: None,
+        }).unwrap();
+        let kit_id = item(pool, category_id, "First aid kit", 0);
+        let bandage_id = item(pool, category_id, "Bandage", bandages);
+        Kit::add_component(pool, kit_id, NewBomComponent { component_id: bandage_id, quantity: 2 }).unwrap();
+        (kit_id, bandage_id)
+    }
+    
+    #[test]
+    fn assembly_builds_lot_tracked_kits_into_the_named_lot() {
+        let (_dir, pool) = test_pool();
+        let (kit_id, _) = first_aid_kit(&pool, 10);
+        receive(&pool, kit_id, 1, lot("K1"));
+        
+        assert!(Kit::assemble(&pool, kit_id, operation(2, None), 1).is_err());
+        Kit::assemble(&pool, kit_id, operation(2, lot("K2")), 1).unwrap();
+        
+        let lots = Lot::list_by_item(&pool, kit_id).unwrap();
+        assert_eq!(lots.iter().find(|lot| lot.lot_number == "K2").unwrap().quantity, 2);
+        
+        Kit::disassemble(&pool, kit_id, operation(1, lot("K2")), 1).unwrap();
+        let lots = Lot::list_by_item(&pool, kit_id).unwrap();
+        assert_eq!(lots.iter().find(|lot| lot.lot_number == "K2").unwrap().quantity, 1);
+    }
+    
+    #[test]
+    fn disassembly_refuses_to_return_lot_tracked_components() {
+        let (_dir, pool) = test_pool();
+        let (kit_id, bandage_id) = first_aid_kit(&pool, 0);
+        receive(&pool, bandage_id, 4, lot("B1"));
+        Kit::assemble(&pool, kit_id, operation(2, None), 1).unwrap();
+        
+        match Kit::disassemble(&pool, kit_id, operation(1, None), 1) {
+            Err(DbError::Validation(message)) => assert!(message.ends_with("Bandage"), "{}", message),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        assert_eq!(InventoryItem::find_by_id(&pool, kit_id, false).unwrap().quantity, 2);
+    }
+    
+    #[test]
+    fn disassembly_refuses_component_quantities_that_overflow() {
+        let (_dir, pool) = test_pool();
+        let category_id = Category::create(&pool, NewCategory {
+            name: "Bulk".to_string(),
+            description: None,
+            parent_id: None,
+        }).unwrap();
+        let kit_id = item(&pool, category_id, "Pallet", 3);
+        let grain_id = item(&pool, category_id, "Grain", 0);
+        Kit::add_component(&pool, kit_id, NewBomComponent { component_id: grain_id, quantity: 1_000_000_000 }).unwrap();
+        
+        match Kit::disassemble(&pool, kit_id, operation(3, None), 1) {
+            Err(DbError::Validation(message)) => assert!(message.contains("needs more Grain"), "{}", message),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        assert_eq!(InventoryItem::find_by_id(&pool, kit_id, false).unwrap().quantity, 3);
+        
+        Kit::disassemble(&pool, kit_id, operation(2, None), 1).unwrap();
+        assert_eq!(InventoryItem::find_by_id(&pool, grain_id, false).unwrap().quantity, 2_000_000_000);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,420 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::models::transaction::{NewTransaction, TransactionType};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lot {
+    pub id: Option<i64>,
+    pub item_id: i64,
+    pub lot_number: String,
+    pub manufacture_date: Option<NaiveDate>,
+    pub expiry_date: Option<NaiveDate>,
+    pub quantity: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Lot details carried by a transaction. Additions create or top up the lot,
+/// removals consume from it instead of the default first-expiry-first-out order,
+/// and adjustments move it by the change they make.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NewLot {
+    pub lot_number: String,
+    pub manufacture_date: Option<NaiveDate>,
+    pub expiry_date: Option<NaiveDate>,
+}
+
+impl Lot {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let created_at_str: String = row.get("created_at")?;
+        let created_at = parse_timestamp(&created_at_str);
+        
+        Ok(Lot {
+            id: row.get("id")?,
+            item_id: row.get("item_id")?,
+            lot_number: row.get("lot_number")?,
+            manufacture_date: row.get("manufacture_date")?,
+            expiry_date: row.get("expiry_date")?,
+            quantity: row.get("quantity")?,
+            created_at,
+        })
+    }
+    
+    pub fn list_by_item(pool: &DbPool, item_id: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, item_id, lot_number, manufacture_date, expiry_date, quantity, created_at
+             FROM item_lots
+             WHERE item_id = ?
+             ORDER BY expiry_date IS NULL, expiry_date, id"
+        )?;
+        
+        let lots_iter = stmt.query_map(params![item_id], Self::from_row)?;
+        let mut lots = Vec::new();
+        
+        for lot_result in lots_iter {
+            match lot_result {
+                Ok(lot) => lots.push(lot),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(lots)
+    }
+    
+    /// Updates lot stock for a transaction that has just been recorded.
+    /// Removals without a lot number consume lots first-expiry-first-out; any
+    /// quantity beyond what the lots hold comes from stock received before the
+    /// item had lots. Adjustments move the named lot by the change they made,
+    /// or without a lot draw lots down first-expiry-first-out until they fit
+    /// the new quantity. Once an item has lots, stock can only be added to it
+    /// through a lot, so lots never hold more than the item has on hand.
+    pub fn apply_transaction(
+        conn: &Connection,
+        transaction_id: i64,
+        new_transaction: &NewTransaction,
+    ) -> DbResult<()> {
+        let item_id = new_transaction.item_id;
+        
+        match (&new_transaction.transaction_type, &new_transaction.lot) {
+            (TransactionType::Addition, Some(lot)) => {
+                let lot_id = Self::receive(conn, item_id, lot, new_transaction.quantity)?;
+                Self::record_movement(conn, transaction_id, lot_id, new_transaction.quantity)?;
+            }
+            (TransactionType::Addition, None) => Self::ensure_untracked(conn, item_id, "Additions")?,
+            (TransactionType::Removal, Some(lot)) => {
+                Self::take_from_lot(conn, transaction_id, item_id, lot, new_transaction.quantity)?;
+            }
+            (TransactionType::Removal, None) => {
+                Self::consume_in_expiry_order(conn, transaction_id, item_id, new_transaction.quantity)?;
+            }
+            (TransactionType::Adjustment, lot) => {
+                let (quantity_before, quantity_after): (i32, i32) = conn.query_row(
+                    "SELECT quantity_before, quantity_after FROM inventory_transactions WHERE id = ?",
+                    params![transaction_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?;
+                let change = quantity_after - quantity_before;
+                
+                match lot {
+                    Some(lot) if change > 0 => {
+                        let lot_id = Self::receive(conn, item_id, lot, change)?;
+                        Self::record_movement(conn, transaction_id, lot_id, change)?;
+                    }
+                    Some(lot) if change < 0 => Self::take_from_lot(conn, transaction_id, item_id, lot, -change)?,
+                    Some(_) => {}
+                    None if change > 0 => Self::ensure_untracked(conn, item_id, "Adjustments that add stock")?,
+                    None => {
+                        let excess = Self::total(conn, item_id)? - quantity_after;
+                        Self::consume_in_expiry_order(conn, transaction_id, item_id, excess)?;
+                    }
+                }
+            }
+        }
+        
+        Self::ensure_within_stock(conn, item_id)
+    }
+    
+    /// Undoes the lot movements of `original_id`, recording them against the
+    /// compensating transaction `reversal_id`.
+    pub fn reverse_transaction(conn: &Connection, original_id: i64, reversal_id: i64) -> DbResult<()> {
+        let movements: Vec<(i64, String, i32, i32)> = {
+            let mut stmt = conn.prepare(
+                "SELECT tl.lot_id, l.lot_number, l.quantity, tl.quantity
+                 FROM transaction_lots tl
+                 JOIN item_lots l ON l.id = tl.lot_id
+                 WHERE tl.transaction_id = ?"
+            )?;
+            let rows = stmt.query_map(params![original_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        
+        for (lot_id, lot_number, available, quantity) in movements {
+            if available < quantity {
+                return Err(DbError::Validation(format!(
+                    "Lot {} only has {} of the {} units transaction {} put in it",
+                    lot_number, available, quantity, original_id
+                )));
+            }
+            
+            conn.execute(
+                "UPDATE item_lots SET quantity = quantity - ? WHERE id = ?",
+                params![quantity, lot_id],
+            )?;
+            Self::record_movement(conn, reversal_id, lot_id, -quantity)?;
+        }
+        
+        // Reversing a movement that bypassed the lots can still leave them
+        // holding more than is on hand
+        let item_id: i64 = conn.query_row(
+            "SELECT item_id FROM inventory_transactions WHERE id = ?",
+            params![original_id],
+            |row| row.get(0),
+        )?;
+        
+        Self::ensure_within_stock(conn, item_id)
+    }
+    
+    /// Total units held across an item's lots.
+    fn total(conn: &Connection, item_id: i64) -> DbResult<i32> {
+        let total = conn.query_row(
+            "SELECT COALESCE(SUM(quantity), 0) FROM item_lots WHERE item_id = ?",
+            params![item_id],
+            |row| row.get(0),
+        )?;
+        Ok(total)
+    }
+    
+    /// Whether the item has ever been received into a lot.
+    pub fn is_tracked(conn: &Connection, item_id: i64) -> DbResult<bool> {
+        let has_lots = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM item_lots WHERE item_id = ?)",
+            params![item_id],
+            |row| row.get(0),
+        )?;
+        
+        Ok(has_lots)
+    }
+    
+    fn ensure_untracked(conn: &Connection, item_id: i64, movement: &str) -> DbResult<()> {
+        if Self::is_tracked(conn, item_id)? {
+            return Err(DbError::Validation(format!(
+                "Item {} is tracked by lot; {} must name a lot",
+                item_id,
+                movement.to_lowercase()
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    fn ensure_within_stock(conn: &Connection, item_id: i64) -> DbResult<()> {
+        let on_hand: i32 = conn.query_row(
+            "SELECT quantity FROM inventory_items WHERE id = ?",
+            params![item_id],
+            |row| row.get(0),
+        )?;
+        let total = Self::total(conn, item_id)?;
+        
+        if total > on_hand {
+            return Err(DbError::Validation(format!(
+                "Lots of item {} would hold {} units but only {} are on hand",
+                item_id, total, on_hand
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    fn take_from_lot(
+        conn: &Connection,
+        transaction_id: i64,
+        item_id: i64,
+        lot: &NewLot,
+        quantity: i32,
+    ) -> DbResult<()> {
+        let (lot_id, available): (i64, i32) = conn
+            .query_row(
+                "SELECT id, quantity FROM item_lots WHERE item_id = ? AND lot_number = ?",
+                params![item_id, lot.lot_number],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .ok_or_else(|| {
+                DbError::Validation(format!("Lot {} not found for this item", lot.lot_number))
+            })?;
+        
+        if available < quantity {
+            return Err(DbError::Validation(format!(
+                "Lot {} only has {} units available",
+                lot.lot_number, available
+            )));
+        }
+        
+        Self::consume(conn, transaction_id, lot_id, quantity)
+    }
+    
+    fn consume_in_expiry_order(conn: &Connection, transaction_id: i64, item_id: i64, quantity: i32) -> DbResult<()> {
+        let mut remaining = quantity;
+        let lots: Vec<(i64, i32)> = {
+            let mut stmt = conn.prepare(
+                "SELECT id, quantity FROM item_lots
+                 WHERE item_id = ? AND quantity > 0
+                 ORDER BY expiry_date IS NULL, expiry_date, id"
+            )?;
+            let rows = stmt.query_map(params![item_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        
+        for (lot_id, available) in lots {
+            if remaining <= 0 {
+                break;
+            }
+            let taken = remaining.min(available);
+            Self::consume(conn, transaction_id, lot_id, taken)?;
+            remaining -= taken;
+        }
+        
+        Ok(())
+    }
+    
+    fn receive(conn: &Connection, item_id: i64, lot: &NewLot, quantity: i32) -> DbResult<i64> {
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM item_lots WHERE item_id = ? AND lot_number = ?",
+                params![item_id, lot.lot_number],
+                |row| row.get(0),
+            )
+            .optional()?;
+        
+        match existing {
+            Some(lot_id) => {
+                conn.execute(
+                    "UPDATE item_lots SET quantity = quantity + ?,
+                         manufacture_date = COALESCE(?, manufacture_date),
+                         expiry_date = COALESCE(?, expiry_date)
+                     WHERE id = ?",
+                    params![quantity, lot.manufacture_date, lot.expiry_date, lot_id],
+                )?;
+                Ok(lot_id)
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO item_lots (item_id, lot_number, manufacture_date, expiry_date, quantity)
+                     VALUES (?, ?, ?, ?, ?)",
+                    params![item_id, lot.lot_number, lot.manufacture_date, lot.expiry_date, quantity],
+                )?;
+                Ok(conn.last_insert_rowid())
+            }
+        }
+    }
+    
+    fn consume(conn: &Connection, transaction_id: i64, lot_id: i64, quantity: i32) -> DbResult<()> {
+        conn.execute(
+            "UPDATE item_lots SET quantity = quantity - ? WHERE id = ?",
+            params![quantity, lot_id],
+        )?;
+        Self::record_movement(conn, transaction_id, lot_id, -quantity)
+    }
+    
+    /// Records how much a transaction moved a lot; positive for stock
+    /// received into the lot, negative for stock taken out of it.
+    fn record_movement(conn: &Connection, transaction_id: i64, lot_id: i64, quantity: i32) -> DbResult<()> {
+        conn.execute(
+            "INSERT INTO transaction_lots (transaction_id, lot_id, quantity) VALUES (?, ?, ?)",
+            params![transaction_id, lot_id, quantity],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::models::category::{Category, NewCategory};
+    use crate::models::inventory_item::{InventoryItem, NewInventoryItem};
+    use crate::models::transaction::Transaction;
+    
+    fn transaction(item_id: i64, transaction_type: TransactionType, quantity: i32, lot: Option<NewLot>) -> NewTransaction {
+        NewTransaction {
+            item_id,
+            transaction_type,
+            quantity,
+            user_id: 1,
+            notes: None,
+            reference: None,
+            lot,
+            serial_numbers: Vec::new(),
+            reservation_id: None,
+            unit: None,
+            unit_quantity: None,
+        }
+    }
+    
+    fn lot(lot_number: &str, expiry_date: Option<&str>) -> Option<NewLot> {
+        Some(NewLot {
+            lot_number: lot_number.to_string(),
+            manufacture_date: None,
+            expiry_date: expiry_date.map(|date| date.parse().unwrap()),
+        })
+    }
+    
+    /// Milk received into a lot without expiry and two lots that expire,
+    /// the later one first.
+    fn milk(pool: &DbPool) -> i64 {
+        let category_id = Category::create(pool, NewCategory {
+            name: "Dairy".to_string(),
+            description: None,
+            parent_id: None,
+        }).unwrap();
+        let item_id = InventoryItem::create(pool, NewInventoryItem {
+            name: "Milk".to_string(),
+            description: None,
+            category_id,
+            quantity: 0,
+            unit_price: 1.0,
+            sku: None,
+            location: None,
+            serialized: false,
+            base_unit_id: None,
+            reorder_point: None,
+            supplier: None,
+            attributes: Default::default(),
+        }).unwrap();
+        
+        let receipts = [("C", None, 4), ("A", Some("2027-06-01"), 5), ("B", Some("2027-01-01"), 3)];
+        for (lot_number, expiry_date, quantity) in receipts {
+            let receipt = transaction(item_id, TransactionType::Addition, quantity, lot(lot_number, expiry_date));
+            Transaction::create(pool, receipt).unwrap();
+        }
+        
+        item_id
+    }
+    
+    fn lot_quantities(pool: &DbPool, item_id: i64) -> Vec<(String, i32)> {
+        Lot::list_by_item(pool, item_id)
+            .unwrap()
+            .into_iter()
+            .map(|lot| (lot.lot_number, lot.quantity))
+            .collect()
+    }
+    
+    fn expected(quantities: [(&str, i32); 3]) -> Vec<(String, i32)> {
+        quantities.iter().map(|(lot_number, quantity)| (lot_number.to_string(), *quantity)).collect()
+    }
+    
+    #[test]
+    fn removals_consume_lots_first_expiry_first_out() {
+        let (_dir, pool) = test_pool();
+        let item_id = milk(&pool);
+        
+        Transaction::create(&pool, transaction(item_id, TransactionType::Removal, 6, None)).unwrap();
+        assert_eq!(lot_quantities(&pool, item_id), expected([("B", 0), ("A", 2), ("C", 4)]));
+        
+        Transaction::create(&pool, transaction(item_id, TransactionType::Removal, 3, None)).unwrap();
+        assert_eq!(lot_quantities(&pool, item_id), expected([("B", 0), ("A", 0), ("C", 3)]));
+    }
+    
+    #[test]
+    fn removals_naming_a_lot_take_only_from_it() {
+        let (_dir, pool) = test_pool();
+        let item_id = milk(&pool);
+        
+        Transaction::create(&pool, transaction(item_id, TransactionType::Removal, 2, lot("C", None))).unwrap();
+        assert_eq!(lot_quantities(&pool, item_id), expected([("B", 3), ("A", 5), ("C", 2)]));
+        assert!(Transaction::create(&pool, transaction(item_id, TransactionType::Removal, 4, lot("B", None))).is_err());
+    }
+    
+    #[test]
+    fn adjustments_down_without_a_lot_draw_lots_first_expiry_first_out() {
+        let (_dir, pool) = test_pool();
+        let item_id = milk(&pool);
+        
+        Transaction::create(&pool, transaction(item_id, TransactionType::Adjustment, 8, None)).unwrap();
+        assert_eq!(lot_quantities(&pool, item_id), expected([("B", 0), ("A", 4), ("C", 4)]));
+    }
+}
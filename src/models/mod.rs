@@ -1,4 +1,19 @@
 pub mod user;
 pub mod category;
 pub mod inventory_item;
-pub mod transaction;
\ No newline at end of file
+pub mod transaction;
+pub mod lot;
+pub mod serial_number;
+pub mod reservation;
+pub mod cycle_count;
+pub mod attribute;
+pub mod search;
+pub mod unit;
+pub mod kit;
+pub mod barcode;
+pub mod event;
+pub mod webhook;
+pub mod alert;
+pub mod report;
+pub mod report_request;
+pub mod report_schedule;
\ No newline at end of file
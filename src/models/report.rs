@@ -0,0 +1,1662 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use rusqlite::{params, OptionalExtension};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::models::inventory_item::{InventoryItem, ITEM_COLUMNS};
+use crate::models::transaction::TransactionType;
+
+/// Units of an item removed in the trailing window, not counting removals
+/// that were later reversed or the removals that reverse an addition.
+const REMOVED_SQL: &str =
+    "(SELECT COALESCE(SUM(t.quantity), 0) FROM inventory_transactions t
+      WHERE t.item_id = inventory_items.id AND t.transaction_type = 'removal'
+      AND t.reversed_by IS NULL AND t.reversal_of IS NULL AND datetime(t.transaction_date) >= datetime('now', ?))";
+
+/// Change a transaction `t` made to the quantity on hand. Older transactions
+/// without recorded quantities fall back to their movement, and adjustments
+/// among them count as no change.
+const CHANGE_SQL: &str =
+    "COALESCE(t.quantity_after - t.quantity_before,
+              CASE t.transaction_type WHEN 'addition' THEN t.quantity WHEN 'removal' THEN -t.quantity ELSE 0 END)";
+
+/// Two-sided z-scores for the confidence levels a forecast can be asked for.
+const CONFIDENCE_LEVELS: [(f64, f64); 5] = [(0.8, 1.2816), (0.9, 1.6449), (0.95, 1.96), (0.98, 2.3263), (0.99, 2.5758)];
+/// Days of history needed before weekday seasonality is estimated.
+const SEASONAL_MIN_DAYS: usize = 28;
+/// Cumulative shares of consumption value that close the A and B classes.
+const ABC_A_SHARE: f64 = 0.8;
+const ABC_B_SHARE: f64 = 0.95;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InventorySummary {
+    pub total_items: i64,
+    pub total_quantity: i64,
+    pub total_value: f64,
+    pub categories_count: i64,
+    pub low_stock_count: i64,
+    pub zero_stock_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategorySummary {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+    pub path: String,
+    pub depth: i64,
+    pub items_count: i64,
+    pub total_quantity: i64,
+    pub total_value: f64,
+}
+
+/// Period length transaction history is totalled over.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum HistoryGrouping {
+    Day,
+    /// Weeks starting on Monday.
+    Week,
+    Month,
+}
+
+impl HistoryGrouping {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(grouping: &str) -> Option<Self> {
+        match grouping.to_lowercase().as_str() {
+            "day" => Some(HistoryGrouping::Day),
+            "week" => Some(HistoryGrouping::Week),
+            "month" => Some(HistoryGrouping::Month),
+            _ => None,
+        }
+    }
+    
+    /// SQL for the first and last day of the period holding `t.transaction_date`.
+    fn period_sql(&self) -> (&'static str, &'static str) {
+        match self {
+            HistoryGrouping::Day => ("date(t.transaction_date)", "date(t.transaction_date)"),
+            HistoryGrouping::Week => (
+                "date(t.transaction_date, 'weekday 0', '-6 days')",
+                "date(t.transaction_date, 'weekday 0')",
+            ),
+            HistoryGrouping::Month => (
+                "date(t.transaction_date, 'start of month')",
+                "date(t.transaction_date, 'start of month', '+1 month', '-1 day')",
+            ),
+        }
+    }
+}
+
+impl fmt::Display for HistoryGrouping {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            HistoryGrouping::Day => "day",
+            HistoryGrouping::Week => "week",
+            HistoryGrouping::Month => "month",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// Narrows transaction history; every filter that is set must match.
+#[derive(Debug, Default)]
+pub struct TransactionHistoryFilter {
+    pub item_id: Option<i64>,
+    /// Includes items in subcategories.
+    pub category_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub transaction_type: Option<TransactionType>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionSummary {
+    /// First day of the period.
+    pub date: String,
+    pub period_end: String,
+    pub transactions_count: i64,
+    pub additions: i64,
+    pub removals: i64,
+    /// Change in stock made by adjustments, not the quantities they set.
+    pub adjustments: i64,
+    pub net_change: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExpiringLot {
+    pub lot_id: i64,
+    pub item_id: i64,
+    pub item_name: String,
+    pub sku: Option<String>,
+    pub lot_number: String,
+    pub expiry_date: String,
+    pub days_until_expiry: i64,
+    pub quantity: i64,
+    pub value: f64,
+}
+
+pub fn inventory_summary(pool: &DbPool) -> DbResult<InventorySummary> {
+    let conn = pool.get()?;
+    
+    let total_items: i64 = conn.query_row("SELECT COUNT(*) FROM inventory_items", [], |row| row.get(0))?;
+    
+    let total_quantity: Option<i64> = conn.query_row(
+        "SELECT SUM(quantity) FROM inventory_items",
+        [],
+        |row| row.get(0),
+    )?;
+    
+    let total_value: Option<f64> = conn.query_row(
+        "SELECT SUM(quantity * unit_price) FROM inventory_items",
+        [],
+        |row| row.get(0),
+    )?;
+    
+    let categories_count: i64 = conn.query_row("SELECT COUNT(*) FROM categories", [], |row| row.get(0))?;
+    
+    // Low stock means 1 to 10 units on hand
+    let low_stock_count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM inventory_items WHERE quantity > 0 AND quantity <= 10",
+        [],
+        |row| row.get(0),
+    )?;
+    
+    let zero_stock_count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM inventory_items WHERE quantity = 0",
+        [],
+        |row| row.get(0),
+    )?;
+    
+    Ok(InventorySummary {
+        total_items,
+        total_quantity: total_quantity.unwrap_or(0),
+        total_value: total_value.unwrap_or(0.0),
+        categories_count,
+        low_stock_count,
+        zero_stock_count,
+    })
+}
+
+pub fn category_summary(pool: &DbPool) -> DbResult<Vec<CategorySummary>> {
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(
+        // Totals for each category include every item in its subcategories
+        "WITH RECURSIVE
+            tree(id, name, parent_id, path, depth) AS (
+                SELECT id, name, parent_id, name, 0 FROM categories WHERE parent_id IS NULL
+                UNION ALL
+                SELECT c.id, c.name, c.parent_id, tree.path || ' > ' || c.name, tree.depth + 1
+                FROM categories c JOIN tree ON c.parent_id = tree.id
+            ),
+            subtree(root_id, category_id) AS (
+                SELECT id, id FROM categories
+                UNION ALL
+                SELECT subtree.root_id, c.id
+                FROM categories c JOIN subtree ON c.parent_id = subtree.category_id
+            )
+         SELECT 
+            t.id, 
+            t.name, 
+            t.parent_id, 
+            t.path, 
+            t.depth, 
+            COUNT(i.id) as items_count, 
+            SUM(i.quantity) as total_quantity, 
+            SUM(i.quantity * i.unit_price) as total_value
+         FROM tree t
+         JOIN subtree s ON s.root_id = t.id
+         LEFT JOIN inventory_items i ON i.category_id = s.category_id
+         GROUP BY t.id
+         ORDER BY t.path"
+    )?;
+    
+    let summaries_iter = stmt.query_map([], |row| {
+        Ok(CategorySummary {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            parent_id: row.get(2)?,
+            path: row.get(3)?,
+            depth: row.get(4)?,
+            items_count: row.get(5)?,
+            total_quantity: row.get(6).unwrap_or(0),
+            total_value: row.get(7).unwrap_or(0.0),
+        })
+    })?;
+    
+    let mut summaries = Vec::new();
+    
+    for summary_result in summaries_iter {
+        match summary_result {
+            Ok(summary) => summaries.push(summary),
+            Err(e) => return Err(DbError::from(e)),
+        };
+    }
+    
+    Ok(summaries)
+}
+
+/// Rejects a date range that ends before it starts.
+pub fn check_date_range(start: NaiveDate, end: NaiveDate) -> DbResult<()> {
+    if start > end {
+        return Err(DbError::Validation("start_date must not be after end_date".to_string()));
+    }
+    
+    Ok(())
+}
+
+/// Additions, removals and adjustments per day, week or month between
+/// `start` and `end`, inclusive. Every transaction counts as the difference
+/// between the quantity on hand before and after it, so an adjustment counts
+/// as the change it made rather than the quantity it set.
+pub fn transaction_history(
+    pool: &DbPool,
+    start: NaiveDate,
+    end: NaiveDate,
+    group_by: &HistoryGrouping,
+    filter: &TransactionHistoryFilter,
+) -> DbResult<Vec<TransactionSummary>> {
+    check_date_range(start, end)?;
+    
+    let (period_start, period_end) = group_by.period_sql();
+    
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(&format!(
+        "WITH RECURSIVE subtree(id) AS (
+            SELECT id FROM categories WHERE id = ?4
+            UNION
+            SELECT c.id FROM categories c JOIN subtree ON c.parent_id = subtree.id
+         )
+         SELECT 
+            {start} as period_start,
+            {end} as period_end,
+            COUNT(*),
+            SUM(CASE WHEN t.transaction_type = 'addition' THEN t.quantity ELSE 0 END) as additions,
+            SUM(CASE WHEN t.transaction_type = 'removal' THEN t.quantity ELSE 0 END) as removals,
+            SUM(CASE WHEN t.transaction_type = 'adjustment' THEN {change} ELSE 0 END) as adjustments,
+            SUM({change}) as net_change
+         FROM inventory_transactions t
+         LEFT JOIN inventory_items i ON i.id = t.item_id
+         WHERE date(t.transaction_date) BETWEEN ?1 AND ?2
+            AND (?3 IS NULL OR t.item_id = ?3)
+            AND (?4 IS NULL OR i.category_id IN (SELECT id FROM subtree))
+            AND (?5 IS NULL OR t.user_id = ?5)
+            AND (?6 IS NULL OR t.transaction_type = ?6)
+         GROUP BY period_start
+         ORDER BY period_start",
+        start = period_start,
+        end = period_end,
+        change = CHANGE_SQL
+    ))?;
+    
+    let summaries_iter = stmt.query_map(
+        params![
+            start,
+            end,
+            filter.item_id,
+            filter.category_id,
+            filter.user_id,
+            filter.transaction_type.as_ref().map(|t| t.to_string())
+        ],
+        |row| {
+            Ok(TransactionSummary {
+                date: row.get(0)?,
+                period_end: row.get(1)?,
+                transactions_count: row.get(2)?,
+                additions: row.get(3)?,
+                removals: row.get(4)?,
+                adjustments: row.get(5)?,
+                net_change: row.get(6)?,
+            })
+        },
+    )?;
+    
+    let mut summaries = Vec::new();
+    
+    for summary_result in summaries_iter {
+        match summary_result {
+            Ok(summary) => summaries.push(summary),
+            Err(e) => return Err(DbError::from(e)),
+        };
+    }
+    
+    Ok(summaries)
+}
+
+pub fn check_expiring_window(within_days: i64) -> DbResult<()> {
+    if !(0..=3650).contains(&within_days) {
+        return Err(DbError::Validation("within_days must be between 0 and 3650".to_string()));
+    }
+    
+    Ok(())
+}
+
+/// Lots with stock expiring within `within_days` days. Already expired lots
+/// are included so they can be written off.
+pub fn expiring_lots(pool: &DbPool, within_days: i64) -> DbResult<Vec<ExpiringLot>> {
+    check_expiring_window(within_days)?;
+    
+    let today = Utc::now().format("%Y-%m-%d").to_string();
+    let cutoff = (Utc::now() + Duration::days(within_days))
+        .format("%Y-%m-%d")
+        .to_string();
+    
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(
+        "SELECT 
+            l.id,
+            l.item_id,
+            i.name,
+            i.sku,
+            l.lot_number,
+            l.expiry_date,
+            CAST(julianday(l.expiry_date) - julianday(?) AS INTEGER) as days_until_expiry,
+            l.quantity,
+            l.quantity * i.unit_price as value
+         FROM item_lots l
+         JOIN inventory_items i ON i.id = l.item_id
+         WHERE l.quantity > 0 AND l.expiry_date IS NOT NULL AND l.expiry_date <= ?
+         ORDER BY l.expiry_date, i.name"
+    )?;
+    
+    let lots_iter = stmt.query_map(params![today, cutoff], |row| {
+        Ok(ExpiringLot {
+            lot_id: row.get(0)?,
+            item_id: row.get(1)?,
+            item_name: row.get(2)?,
+            sku: row.get(3)?,
+            lot_number: row.get(4)?,
+            expiry_date: row.get(5)?,
+            days_until_expiry: row.get(6)?,
+            quantity: row.get(7)?,
+            value: row.get(8)?,
+        })
+    })?;
+    
+    let mut lots = Vec::new();
+    
+    for lot_result in lots_iter {
+        match lot_result {
+            Ok(lot) => lots.push(lot),
+            Err(e) => return Err(DbError::from(e)),
+        };
+    }
+    
+    Ok(lots)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReorderSuggestion {
+    pub item_id: i64,
+    pub item_name: String,
+    pub sku: Option<String>,
+    pub quantity: i32,
+    pub reserved_quantity: i32,
+    pub available_quantity: i32,
+    pub reorder_point: i32,
+    pub average_daily_usage: f64,
+    /// Days the available stock lasts at the average usage; `None` when
+    /// nothing was used in the window.
+    pub days_of_stock: Option<f64>,
+    pub suggested_quantity: i32,
+    pub unit_price: f64,
+    pub estimated_cost: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SupplierReorder {
+    /// `None` groups the items that have no supplier set.
+    pub supplier: Option<String>,
+    pub items: Vec<ReorderSuggestion>,
+    pub total_quantity: i64,
+    pub total_cost: f64,
+}
+
+pub fn check_reorder_windows(usage_days: i64, cover_days: i64) -> DbResult<()> {
+    if !(1..=3650).contains(&usage_days) {
+        return Err(DbError::Validation("Usage window must be between 1 and 3650 days".to_string()));
+    }
+    
+    if !(0..=3650).contains(&cover_days) {
+        return Err(DbError::Validation("Cover days must be between 0 and 3650".to_string()));
+    }
+    
+    Ok(())
+}
+
+/// Units to order to bring `available` back up to the reorder point plus
+/// `cover_days` of usage. Worked out in i64 and capped at `i32::MAX`, since
+/// heavy usage over a long cover can exceed what a quantity holds.
+fn suggested_order(reorder_point: i32, available: i32, average_daily_usage: f64, cover_days: i64) -> i32 {
+    let target = reorder_point as i64 + (average_daily_usage * cover_days as f64).ceil() as i64;
+    // Always order at least one unit for an item that is at its reorder point
+    (target - available as i64).clamp(1, i32::MAX as i64) as i32
+}
+
+/// Suggests what to buy for every item whose available stock is at or below
+/// its reorder point. The suggestion brings available stock back up to the
+/// reorder point plus `cover_days` of average usage, where usage is averaged
+/// over the last `usage_days` days of removals. Items without a reorder point
+/// are left out.
+pub fn reorder_suggestions(
+    pool: &DbPool,
+    usage_days: i64,
+    cover_days: i64,
+    supplier: Option<&str>,
+) -> DbResult<Vec<SupplierReorder>> {
+    check_reorder_windows(usage_days, cover_days)?;
+    
+    let conn = pool.get()?;
+    let mut conditions = vec!["reorder_point IS NOT NULL"];
+    if supplier.is_some() {
+        conditions.push("supplier = ?2");
+    }
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT * FROM (SELECT {}, {} AS removed FROM inventory_items)
+         WHERE {} AND available_quantity <= reorder_point
+         ORDER BY supplier IS NULL, supplier, name",
+        ITEM_COLUMNS,
+        REMOVED_SQL.replace('?', "?1"),
+        conditions.join(" AND ")
+    ))?;
+    
+    let window = format!("-{} days", usage_days);
+    let mapper = |row: &rusqlite::Row| Ok((InventoryItem::from_row(row)?, row.get::<_, i64>("removed")?));
+    let rows = match supplier {
+        Some(supplier) => stmt.query_map(params![window, supplier], mapper)?,
+        None => stmt.query_map(params![window], mapper)?,
+    };
+    
+    let mut groups: Vec<SupplierReorder> = Vec::new();
+    
+    for row_result in rows {
+        let (item, removed) = match row_result {
+            Ok(row) => row,
+            Err(e) => return Err(DbError::from(e)),
+        };
+        
+        let reorder_point = item.reorder_point.unwrap_or_default();
+        let average_daily_usage = removed as f64 / usage_days as f64;
+        let suggested_quantity =
+            suggested_order(reorder_point, item.available_quantity, average_daily_usage, cover_days);
+        
+        let suggestion = ReorderSuggestion {
+            item_id: item.id.unwrap_or_default(),
+            item_name: item.name,
+            sku: item.sku,
+            quantity: item.quantity,
+            reserved_quantity: item.quantity - item.available_quantity,
+            available_quantity: item.available_quantity,
+            reorder_point,
+            average_daily_usage,
+            days_of_stock: (average_daily_usage > 0.0)
+                .then(|| item.available_quantity.max(0) as f64 / average_daily_usage),
+            suggested_quantity,
+            unit_price: item.unit_price,
+            estimated_cost: suggested_quantity as f64 * item.unit_price,
+        };
+        
+        match groups.last_mut() {
+            Some(group) if group.supplier == item.supplier => {
+                group.total_quantity += suggestion.suggested_quantity as i64;
+                group.total_cost += suggestion.estimated_cost;
+                group.items.push(suggestion);
+            },
+            _ => groups.push(SupplierReorder {
+                supplier: item.supplier,
+                total_quantity: suggestion.suggested_quantity as i64,
+                total_cost: suggestion.estimated_cost,
+                items: vec![suggestion],
+            }),
+        }
+    }
+    
+    Ok(groups)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ForecastMethod {
+    MovingAverage,
+    ExponentialSmoothing,
+}
+
+impl ForecastMethod {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(method: &str) -> Option<Self> {
+        match method.to_lowercase().as_str() {
+            "moving_average" => Some(ForecastMethod::MovingAverage),
+            "exponential_smoothing" => Some(ForecastMethod::ExponentialSmoothing),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ForecastMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            ForecastMethod::MovingAverage => "moving_average",
+            ForecastMethod::ExponentialSmoothing => "exponential_smoothing",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug)]
+pub struct ForecastOptions {
+    pub method: ForecastMethod,
+    /// Days to forecast, starting today.
+    pub horizon: i64,
+    /// Days of removal history to fit the forecast to.
+    pub history_days: i64,
+    /// Days averaged by the moving average, not counting weekdays that
+    /// never see demand.
+    pub window: usize,
+    /// Smoothing factor for exponential smoothing, in (0, 1].
+    pub alpha: f64,
+    /// Confidence level of the interval: 0.8, 0.9, 0.95, 0.98 or 0.99.
+    pub confidence: f64,
+}
+
+impl ForecastOptions {
+    /// Checks the options are in range and returns the z-score of the
+    /// confidence level.
+    pub fn z_score(&self) -> DbResult<f64> {
+        if !(1..=365).contains(&self.horizon) {
+            return Err(DbError::Validation("Horizon must be between 1 and 365 days".to_string()));
+        }
+        
+        if !(1..=3650).contains(&self.history_days) {
+            return Err(DbError::Validation("History must be between 1 and 3650 days".to_string()));
+        }
+        
+        if self.window == 0 {
+            return Err(DbError::Validation("Moving average window must be at least one day".to_string()));
+        }
+        
+        if !(self.alpha > 0.0 && self.alpha <= 1.0) {
+            return Err(DbError::Validation("Alpha must be greater than 0 and at most 1".to_string()));
+        }
+        
+        CONFIDENCE_LEVELS
+            .iter()
+            .find(|(level, _)| (level - self.confidence).abs() < 1e-9)
+            .map(|(_, z)| *z)
+            .ok_or_else(|| {
+                DbError::Validation("Confidence must be one of 0.8, 0.9, 0.95, 0.98 or 0.99".to_string())
+            })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForecastPoint {
+    pub date: NaiveDate,
+    pub quantity: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DemandForecast {
+    pub item_id: i64,
+    pub item_name: String,
+    pub available_quantity: i32,
+    pub method: ForecastMethod,
+    pub confidence: f64,
+    /// First and last days of the history used, or `None` with no history.
+    pub history_start: Option<NaiveDate>,
+    pub history_end: Option<NaiveDate>,
+    pub total_removed: i64,
+    pub average_daily_usage: f64,
+    /// Whether weekday seasonality was applied, which needs four weeks of history.
+    pub seasonal: bool,
+    /// Demand on each weekday relative to the average, Monday first.
+    pub seasonal_indices: Option<Vec<f64>>,
+    pub forecast: Vec<ForecastPoint>,
+    pub total_quantity: f64,
+    pub total_lower: f64,
+    pub total_upper: f64,
+}
+
+/// Forecasts daily removals of an item over `horizon` days.
+///
+/// The history is one value per complete day, from the start of the window or
+/// the item's first movement (whichever is later) up to yesterday; reversed
+/// removals are left out. With four weeks of history the series is divided by
+/// multiplicative weekday indices before fitting, and forecasts are scaled
+/// back up by them. The interval comes from the spread of the method's
+/// one-day-ahead errors over the history.
+pub fn forecast(pool: &DbPool, item_id: i64, options: &ForecastOptions) -> DbResult<DemandForecast> {
+    let z = options.z_score()?;
+    
+    let item = InventoryItem::find_by_id(pool, item_id, false).map_err(|e| match e {
+        DbError::Sqlite(rusqlite::Error::QueryReturnedNoRows) => DbError::NotFound,
+        _ => e,
+    })?;
+    
+    let conn = pool.get()?;
+    let today = Utc::now().date_naive();
+    let history_end = today - Duration::days(1);
+    
+    let first_movement: Option<NaiveDate> = conn.query_row(
+        "SELECT MIN(date(transaction_date)) FROM inventory_transactions WHERE item_id = ?",
+        params![item_id],
+        |row| row.get(0),
+    )?;
+    let first_day = first_movement
+        .map_or(item.created_at.date_naive(), |first| first.min(item.created_at.date_naive()));
+    let history_start = (today - Duration::days(options.history_days)).max(first_day);
+    
+    let mut stmt = conn.prepare(
+        "SELECT date(transaction_date) AS day, SUM(quantity) FROM inventory_transactions
+         WHERE item_id = ? AND transaction_type = 'removal' AND reversed_by IS NULL AND reversal_of IS NULL
+         AND date(transaction_date) BETWEEN ? AND ?
+         GROUP BY day"
+    )?;
+    
+    let removals_iter = stmt.query_map(params![item_id, history_start, history_end], |row| {
+        Ok((row.get::<_, NaiveDate>(0)?, row.get::<_, i64>(1)?))
+    })?;
+    let mut removals = HashMap::new();
+    
+    for removal_result in removals_iter {
+        match removal_result {
+            Ok((day, quantity)) => removals.insert(day, quantity),
+            Err(e) => return Err(DbError::from(e)),
+        };
+    }
+    
+    let history: Vec<(NaiveDate, f64)> = history_start
+        .iter_days()
+        .take_while(|day| *day <= history_end)
+        .map(|day| (day, removals.get(&day).copied().unwrap_or(0) as f64))
+        .collect();
+    
+    let total_removed: i64 = removals.values().sum();
+    let average_daily_usage = if history.is_empty() {
+        0.0
+    } else {
+        total_removed as f64 / history.len() as f64
+    };
+    
+    let seasonal_indices = (history.len() >= SEASONAL_MIN_DAYS && average_daily_usage > 0.0).then(|| {
+        let mut sums = [0.0; 7];
+        let mut counts = [0.0; 7];
+        for (day, quantity) in &history {
+            let weekday = day.weekday().num_days_from_monday() as usize;
+            sums[weekday] += quantity;
+            counts[weekday] += 1.0;
+        }
+        (0..7).map(|d| sums[d] / counts[d] / average_daily_usage).collect::<Vec<f64>>()
+    });
+    
+    let index_for = |day: NaiveDate| {
+        seasonal_indices
+            .as_ref()
+            .map_or(1.0, |indices| indices[day.weekday().num_days_from_monday() as usize])
+    };
+    
+    // Fit on the deseasonalized series. Weekdays that never see demand carry no
+    // level to fit, so they are left out and their forecasts stay at zero
+    let series: Vec<f64> = history
+        .iter()
+        .filter_map(|(day, quantity)| {
+            let index = index_for(*day);
+            (index > 0.0).then(|| quantity / index)
+        })
+        .collect();
+    
+    let mut errors = Vec::new();
+    let level = match options.method {
+        ForecastMethod::MovingAverage => {
+            for t in options.window..series.len() {
+                let mean = series[t - options.window..t].iter().sum::<f64>() / options.window as f64;
+                errors.push(series[t] - mean);
+            }
+            let recent = &series[series.len().saturating_sub(options.window)..];
+            if recent.is_empty() { 0.0 } else { recent.iter().sum::<f64>() / recent.len() as f64 }
+        }
+        ForecastMethod::ExponentialSmoothing => {
+            let mut level = series.first().copied().unwrap_or(0.0);
+            for value in series.iter().skip(1) {
+                errors.push(value - level);
+                level += options.alpha * (value - level);
+            }
+            level
+        }
+    };
+    
+    let sigma = if errors.is_empty() {
+        0.0
+    } else {
+        (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt()
+    };
+    
+    let mut forecast = Vec::with_capacity(options.horizon as usize);
+    let mut total_quantity = 0.0;
+    let mut total_variance = 0.0;
+    
+    for h in 0..options.horizon {
+        let date = today + Duration::days(h);
+        let index = index_for(date);
+        // Uncertainty grows with the horizon for smoothing; a moving average adds
+        // the error of its own mean instead
+        let spread = match options.method {
+            ForecastMethod::MovingAverage => sigma * (1.0 + 1.0 / options.window as f64).sqrt(),
+            ForecastMethod::ExponentialSmoothing => sigma * (1.0 + h as f64 * options.alpha.powi(2)).sqrt(),
+        } * index;
+        let quantity = level * index;
+        
+        total_quantity += quantity;
+        total_variance += spread * spread;
+        forecast.push(ForecastPoint {
+            date,
+            quantity,
+            lower: (quantity - z * spread).max(0.0),
+            upper: quantity + z * spread,
+        });
+    }
+    
+    let total_spread = z * total_variance.sqrt();
+    
+    Ok(DemandForecast {
+        item_id,
+        item_name: item.name,
+        available_quantity: item.available_quantity,
+        method: options.method.clone(),
+        confidence: options.confidence,
+        history_start: history.first().map(|(day, _)| *day),
+        history_end: history.last().map(|(day, _)| *day),
+        total_removed,
+        average_daily_usage,
+        seasonal: seasonal_indices.is_some(),
+        seasonal_indices,
+        forecast,
+        total_quantity,
+        total_lower: (total_quantity - total_spread).max(0.0),
+        total_upper: total_quantity + total_spread,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum AbcClass {
+    A,
+    B,
+    C,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AbcItem {
+    pub item_id: i64,
+    pub item_name: String,
+    pub sku: Option<String>,
+    pub category_id: i64,
+    pub quantity_used: i64,
+    pub unit_price: f64,
+    pub consumption_value: f64,
+    /// Consumption value scaled to a 365-day year.
+    pub annual_consumption_value: f64,
+    pub share: f64,
+    pub cumulative_share: f64,
+    pub class: AbcClass,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AbcClassSummary {
+    pub class: AbcClass,
+    pub items_count: i64,
+    pub consumption_value: f64,
+    pub share: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AbcReport {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub total_consumption_value: f64,
+    pub classes: Vec<AbcClassSummary>,
+    pub items: Vec<AbcItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemTurnover {
+    pub item_id: i64,
+    pub item_name: String,
+    pub sku: Option<String>,
+    pub category_id: i64,
+    pub category_name: String,
+    pub quantity_used: i64,
+    pub opening_quantity: i64,
+    pub closing_quantity: i64,
+    pub average_quantity: f64,
+    pub cost_of_goods_used: f64,
+    pub average_inventory_value: f64,
+    pub turnover_ratio: f64,
+    /// Days the average stock lasts at the period's rate of use; `None` when
+    /// nothing was used.
+    pub days_of_supply: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryTurnover {
+    pub category_id: i64,
+    pub category_name: String,
+    pub items_count: i64,
+    pub cost_of_goods_used: f64,
+    pub average_inventory_value: f64,
+    pub turnover_ratio: f64,
+    pub days_of_supply: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TurnoverReport {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub items: Vec<ItemTurnover>,
+    pub categories: Vec<CategoryTurnover>,
+}
+
+/// Removals over a period and the stock on either side of it, for one item.
+struct ItemUsage {
+    item_id: i64,
+    item_name: String,
+    sku: Option<String>,
+    category_id: i64,
+    category_name: String,
+    unit_price: f64,
+    removed: i64,
+    opening_quantity: i64,
+    closing_quantity: i64,
+}
+
+/// Reads every item's usage between `start` and `end`, inclusive. Stock at a
+/// point in time is the `quantity_before` of the first movement after it, or
+/// the current quantity when nothing has moved since.
+fn item_usage(pool: &DbPool, start: NaiveDate, end: NaiveDate) -> DbResult<Vec<ItemUsage>> {
+    check_date_range(start, end)?;
+    
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(
+        "SELECT i.id, i.name, i.sku, i.category_id, c.name, i.unit_price,
+            (SELECT COALESCE(SUM(t.quantity), 0) FROM inventory_transactions t
+             WHERE t.item_id = i.id AND t.transaction_type = 'removal'
+             AND t.reversed_by IS NULL AND t.reversal_of IS NULL
+             AND date(t.transaction_date) BETWEEN ?1 AND ?2) AS removed,
+            COALESCE((SELECT t.quantity_before FROM inventory_transactions t
+                      WHERE t.item_id = i.id AND date(t.transaction_date) >= ?1
+                      ORDER BY t.transaction_date, t.id LIMIT 1), i.quantity) AS opening_quantity,
+            COALESCE((SELECT t.quantity_before FROM inventory_transactions t
+                      WHERE t.item_id = i.id AND date(t.transaction_date) > ?2
+                      ORDER BY t.transaction_date, t.id LIMIT 1), i.quantity) AS closing_quantity
+         FROM inventory_items i
+         JOIN categories c ON c.id = i.category_id
+         ORDER BY i.name"
+    )?;
+    
+    let usage_iter = stmt.query_map(params![start, end], |row| {
+        Ok(ItemUsage {
+            item_id: row.get(0)?,
+            item_name: row.get(1)?,
+            sku: row.get(2)?,
+            category_id: row.get(3)?,
+            category_name: row.get(4)?,
+            unit_price: row.get(5)?,
+            removed: row.get(6)?,
+            opening_quantity: row.get(7)?,
+            closing_quantity: row.get(8)?,
+        })
+    })?;
+    
+    let mut usage = Vec::new();
+    
+    for usage_result in usage_iter {
+        match usage_result {
+            Ok(item) => usage.push(item),
+            Err(e) => return Err(DbError::from(e)),
+        };
+    }
+    
+    Ok(usage)
+}
+
+fn days_of_supply(days: f64, turnover_ratio: f64) -> Option<f64> {
+    (turnover_ratio > 0.0).then(|| days / turnover_ratio)
+}
+
+/// Ranks items by the value of stock removed between `start` and `end` and
+/// splits them into classes: A up to 80% of the total value, B up to 95%,
+/// and C for the rest, including everything that was not used.
+pub fn abc_analysis(pool: &DbPool, start: NaiveDate, end: NaiveDate) -> DbResult<AbcReport> {
+    let mut usage = item_usage(pool, start, end)?;
+    let days = ((end - start).num_days() + 1) as f64;
+    
+    usage.sort_by(|a, b| {
+        (b.removed as f64 * b.unit_price)
+            .total_cmp(&(a.removed as f64 * a.unit_price))
+            .then_with(|| a.item_name.cmp(&b.item_name))
+    });
+    
+    let total_consumption_value: f64 = usage.iter().map(|item| item.removed as f64 * item.unit_price).sum();
+    let mut classes: Vec<AbcClassSummary> = [AbcClass::A, AbcClass::B, AbcClass::C]
+        .into_iter()
+        .map(|class| AbcClassSummary {
+            class,
+            items_count: 0,
+            consumption_value: 0.0,
+            share: 0.0,
+        })
+        .collect();
+    let mut items = Vec::with_capacity(usage.len());
+    let mut cumulative_share = 0.0;
+    
+    for item in usage {
+        let consumption_value = item.removed as f64 * item.unit_price;
+        let share = if total_consumption_value > 0.0 {
+            consumption_value / total_consumption_value
+        } else {
+            0.0
+        };
+        
+        // An item goes in the class where its value starts, so the top item is always A
+        let class = if consumption_value <= 0.0 {
+            AbcClass::C
+        } else if cumulative_share < ABC_A_SHARE {
+            AbcClass::A
+        } else if cumulative_share < ABC_B_SHARE {
+            AbcClass::B
+        } else {
+            AbcClass::C
+        };
+        cumulative_share += share;
+        
+        let summary = &mut classes[class as usize];
+        summary.items_count += 1;
+        summary.consumption_value += consumption_value;
+        summary.share += share;
+        
+        items.push(AbcItem {
+            item_id: item.item_id,
+            item_name: item.item_name,
+            sku: item.sku,
+            category_id: item.category_id,
+            quantity_used: item.removed,
+            unit_price: item.unit_price,
+            consumption_value,
+            annual_consumption_value: consumption_value * 365.0 / days,
+            share,
+            cumulative_share,
+            class,
+        });
+    }
+    
+    Ok(AbcReport {
+        start_date: start,
+        end_date: end,
+        total_consumption_value,
+        classes,
+        items,
+    })
+}
+
+/// Turnover for each item and category between `start` and `end`: the value
+/// of stock removed over the average of the opening and closing stock value.
+pub fn turnover(pool: &DbPool, start: NaiveDate, end: NaiveDate) -> DbResult<TurnoverReport> {
+    let usage = item_usage(pool, start, end)?;
+    let days = ((end - start).num_days() + 1) as f64;
+    
+    let mut items = Vec::with_capacity(usage.len());
+    let mut categories: Vec<CategoryTurnover> = Vec::new();
+    
+    for item in usage {
+        let average_quantity = (item.opening_quantity + item.closing_quantity) as f64 / 2.0;
+        let cost_of_goods_used = item.removed as f64 * item.unit_price;
+        let average_inventory_value = average_quantity * item.unit_price;
+        let turnover_ratio = if average_quantity > 0.0 {
+            item.removed as f64 / average_quantity
+        } else {
+            0.0
+        };
+        
+        match categories.iter_mut().find(|category| category.category_id == item.category_id) {
+            Some(category) => {
+                category.items_count += 1;
+                category.cost_of_goods_used += cost_of_goods_used;
+                category.average_inventory_value += average_inventory_value;
+            },
+            None => categories.push(CategoryTurnover {
+                category_id: item.category_id,
+                category_name: item.category_name.clone(),
+                items_count: 1,
+                cost_of_goods_used,
+                average_inventory_value,
+                turnover_ratio: 0.0,
+                days_of_supply: None,
+            }),
+        }
+        
+        items.push(ItemTurnover {
+            item_id: item.item_id,
+            item_name: item.item_name,
+            sku: item.sku,
+            category_id: item.category_id,
+            category_name: item.category_name,
+            quantity_used: item.removed,
+            opening_quantity: item.opening_quantity,
+            closing_quantity: item.closing_quantity,
+            average_quantity,
+            cost_of_goods_used,
+            average_inventory_value,
+            turnover_ratio,
+            days_of_supply: days_of_supply(days, turnover_ratio),
+        });
+    }
+    
+    for category in &mut categories {
+        if category.average_inventory_value > 0.0 {
+            category.turnover_ratio = category.cost_of_goods_used / category.average_inventory_value;
+        }
+        category.days_of_supply = days_of_supply(days, category.turnover_ratio);
+    }
+    categories.sort_by(|a, b| a.category_name.cmp(&b.category_name));
+    
+    Ok(TurnoverReport {
+        start_date: start,
+        end_date: end,
+        items,
+        categories,
+    })
+}
+
+/// An item holding stock that has barely moved. Flat so it can be exported as CSV.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadStockItem {
+    pub item_id: i64,
+    pub item_name: String,
+    pub sku: Option<String>,
+    pub category_name: String,
+    pub location: Option<String>,
+    pub quantity: i32,
+    pub unit_price: f64,
+    /// Value of the stock on hand at the current unit price.
+    pub value: f64,
+    pub quantity_removed: i64,
+    pub last_movement_at: Option<DateTime<Utc>>,
+    pub last_movement_type: Option<String>,
+    pub last_movement_by: Option<String>,
+    pub days_since_last_movement: Option<i64>,
+}
+
+pub fn check_dead_stock_window(days: i64, max_removed: i64) -> DbResult<()> {
+    if !(1..=3650).contains(&days) {
+        return Err(DbError::Validation("days must be between 1 and 3650".to_string()));
+    }
+    
+    if max_removed < 0 {
+        return Err(DbError::Validation("max_removed cannot be negative".to_string()));
+    }
+    
+    Ok(())
+}
+
+/// Lists items with stock on hand that had at most `max_removed` units
+/// removed in the last `days` days: no removals at all by default, or slow
+/// movers with a higher limit. Items created during the period are left out
+/// since they have not had the chance to move. The last movement shown skips
+/// reversal entries, which undo a movement rather than make one. Highest
+/// value first.
+pub fn dead_stock(pool: &DbPool, days: i64, max_removed: i64) -> DbResult<Vec<DeadStockItem>> {
+    check_dead_stock_window(days, max_removed)?;
+    
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT * FROM (
+            SELECT i.id, i.name, i.sku, c.name AS category_name, i.location, i.quantity, i.unit_price,
+                {} AS removed,
+                last.transaction_date AS last_movement_at,
+                last.transaction_type AS last_movement_type,
+                u.username AS last_movement_by
+            FROM inventory_items i
+            JOIN categories c ON c.id = i.category_id
+            LEFT JOIN inventory_transactions last ON last.id = (
+                SELECT t.id FROM inventory_transactions t
+                WHERE t.item_id = i.id AND t.reversal_of IS NULL
+                ORDER BY datetime(t.transaction_date) DESC, t.id DESC LIMIT 1)
+            LEFT JOIN users u ON u.id = last.user_id
+            WHERE i.quantity > 0 AND datetime(i.created_at) <= datetime('now', ?1)
+         )
+         WHERE removed <= ?2
+         ORDER BY quantity * unit_price DESC, name",
+        REMOVED_SQL.replace("inventory_items.id", "i.id").replace('?', "?1")
+    ))?;
+    
+    let now = Utc::now();
+    let window = format!("-{} days", days);
+    let items_iter = stmt.query_map(params![window, max_removed], |row| {
+        let quantity: i32 = row.get("quantity")?;
+        let unit_price: f64 = row.get("unit_price")?;
+        let last_movement_at = row
+            .get::<_, Option<String>>("last_movement_at")?
+            .map(|at| parse_timestamp(&at));
+        
+        Ok(DeadStockItem {
+            item_id: row.get("id")?,
+            item_name: row.get("name")?,
+            sku: row.get("sku")?,
+            category_name: row.get("category_name")?,
+            location: row.get("location")?,
+            quantity,
+            unit_price,
+            value: quantity as f64 * unit_price,
+            quantity_removed: row.get("removed")?,
+            last_movement_at,
+            last_movement_type: row.get("last_movement_type")?,
+            last_movement_by: row.get("last_movement_by")?,
+            days_since_last_movement: last_movement_at.map(|at| (now - at).num_days()),
+        })
+    })?;
+    
+    let mut items = Vec::new();
+    
+    for item_result in items_iter {
+        match item_result {
+            Ok(item) => items.push(item),
+            Err(e) => return Err(DbError::from(e)),
+        };
+    }
+    
+    Ok(items)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub transaction_id: i64,
+    pub transaction_date: DateTime<Utc>,
+    pub transaction_type: TransactionType,
+    pub quantity: i32,
+    /// Signed change to the stock on hand.
+    pub change: i32,
+    /// Stock on hand after this movement.
+    pub balance: i32,
+    pub username: Option<String>,
+    pub reference: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemLedger {
+    pub item_id: i64,
+    pub item_name: String,
+    pub sku: Option<String>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub opening_balance: i32,
+    pub total_in: i64,
+    pub total_out: i64,
+    pub closing_balance: i32,
+    pub entries: Vec<LedgerEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserItemActivity {
+    pub item_id: i64,
+    /// `None` when the item has since been deleted.
+    pub item_name: Option<String>,
+    pub transactions_count: i64,
+    pub added: i64,
+    pub removed: i64,
+    /// Net change made by adjustments.
+    pub adjusted: i64,
+    pub net_change: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserActivity {
+    pub user_id: i64,
+    pub username: String,
+    pub transactions_count: i64,
+    pub items_count: i64,
+    pub added: i64,
+    pub removed: i64,
+    pub adjusted: i64,
+    pub value_added: f64,
+    pub value_removed: f64,
+    pub first_at: DateTime<Utc>,
+    pub last_at: DateTime<Utc>,
+    pub items: Vec<UserItemActivity>,
+}
+
+/// Every movement of an item between `start` and `end`, inclusive, with the
+/// stock on hand after each one. Balances follow the quantity each movement
+/// recorded before it applied, so direct edits to the item's quantity show as
+/// a jump between entries rather than throwing the rest of the ledger off.
+pub fn item_ledger(pool: &DbPool, item_id: i64, start: NaiveDate, end: NaiveDate) -> DbResult<ItemLedger> {
+    check_date_range(start, end)?;
+    
+    let item = InventoryItem::find_by_id(pool, item_id, false).map_err(|e| match e {
+        DbError::Sqlite(rusqlite::Error::QueryReturnedNoRows) => DbError::NotFound,
+        _ => e,
+    })?;
+    
+    let conn = pool.get()?;
+    
+    // Stock at the start of the range, as recorded by the first movement since
+    let opening_balance: Option<Option<i32>> = conn.query_row(
+        "SELECT quantity_before FROM inventory_transactions
+         WHERE item_id = ? AND date(transaction_date) >= ?
+         ORDER BY datetime(transaction_date), id LIMIT 1",
+        params![item_id, start],
+        |row| row.get(0),
+    ).optional()?;
+    let opening_balance = opening_balance.flatten().unwrap_or(item.quantity);
+    
+    let mut stmt = conn.prepare(
+        "SELECT t.id, t.transaction_date, t.transaction_type, t.quantity, t.quantity_before, t.quantity_after,
+                u.username, t.reference, t.notes
+         FROM inventory_transactions t
+         LEFT JOIN users u ON u.id = t.user_id
+         WHERE t.item_id = ? AND date(t.transaction_date) BETWEEN ? AND ?
+         ORDER BY datetime(t.transaction_date), t.id"
+    )?;
+    
+    let entries_iter = stmt.query_map(params![item_id, start, end], |row| {
+        let transaction_type: String = row.get(2)?;
+        Ok((
+            row.get::<_, i64>(0)?,
+            parse_timestamp(&row.get::<_, String>(1)?),
+            TransactionType::from_str(&transaction_type).unwrap_or(TransactionType::Adjustment),
+            row.get::<_, i32>(3)?,
+            row.get::<_, Option<i32>>(4)?,
+            row.get::<_, Option<i32>>(5)?,
+            row.get(6)?,
+            row.get(7)?,
+            row.get(8)?,
+        ))
+    })?;
+    
+    let mut entries = Vec::new();
+    let mut balance = opening_balance;
+    let mut total_in = 0;
+    let mut total_out = 0;
+    
+    for entry_result in entries_iter {
+        let (transaction_id, transaction_date, transaction_type, quantity, quantity_before, quantity_after, username, reference, notes) =
+            match entry_result {
+                Ok(entry) => entry,
+                Err(e) => return Err(DbError::from(e)),
+            };
+        
+        // Older transactions without recorded quantities carry on from the running balance
+        let before = quantity_before.unwrap_or(balance);
+        balance = quantity_after.unwrap_or(match transaction_type {
+            TransactionType::Addition => before + quantity,
+            TransactionType::Removal => before - quantity,
+            TransactionType::Adjustment => quantity,
+        });
+        let change = balance - before;
+        
+        if change > 0 {
+            total_in += change as i64;
+        } else {
+            total_out -= change as i64;
+        }
+        
+        entries.push(LedgerEntry {
+            transaction_id,
+            transaction_date,
+            transaction_type,
+            quantity,
+            change,
+            balance,
+            username,
+            reference,
+            notes,
+        });
+    }
+    
+    Ok(ItemLedger {
+        item_id,
+        item_name: item.name,
+        sku: item.sku,
+        start_date: start,
+        end_date: end,
+        opening_balance,
+        total_in,
+        total_out,
+        closing_balance: balance,
+        entries,
+    })
+}
+
+/// What each user moved between `start` and `end`, inclusive, item by item.
+/// Values are at the items' current unit prices.
+pub fn user_activity(
+    pool: &DbPool,
+    start: NaiveDate,
+    end: NaiveDate,
+    user_id: Option<i64>,
+) -> DbResult<Vec<UserActivity>> {
+    check_date_range(start, end)?;
+    
+    let conn = pool.get()?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT t.user_id, u.username, t.item_id, i.name, COALESCE(i.unit_price, 0.0), COUNT(*),
+            SUM(CASE WHEN t.transaction_type = 'addition' THEN t.quantity ELSE 0 END),
+            SUM(CASE WHEN t.transaction_type = 'removal' THEN t.quantity ELSE 0 END),
+            SUM(CASE WHEN t.transaction_type = 'adjustment' THEN {} ELSE 0 END),
+            MIN(t.transaction_date), MAX(t.transaction_date)
+         FROM inventory_transactions t
+         JOIN users u ON u.id = t.user_id
+         LEFT JOIN inventory_items i ON i.id = t.item_id
+         WHERE date(t.transaction_date) BETWEEN ?1 AND ?2 AND (?3 IS NULL OR t.user_id = ?3)
+         GROUP BY t.user_id, t.item_id
+         ORDER BY u.username, i.name",
+        CHANGE_SQL
+    ))?;
+    
+    let rows = stmt.query_map(params![start, end, user_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, f64>(4)?,
+            parse_timestamp(&row.get::<_, String>(9)?),
+            parse_timestamp(&row.get::<_, String>(10)?),
+            UserItemActivity {
+                item_id: row.get(2)?,
+                item_name: row.get(3)?,
+                transactions_count: row.get(5)?,
+                added: row.get(6)?,
+                removed: row.get(7)?,
+                adjusted: row.get(8)?,
+                net_change: row.get::<_, i64>(6)? - row.get::<_, i64>(7)? + row.get::<_, i64>(8)?,
+            },
+        ))
+    })?;
+    
+    let mut users: Vec<UserActivity> = Vec::new();
+    
+    for row_result in rows {
+        let (user_id, username, unit_price, first_at, last_at, item) = match row_result {
+            Ok(row) => row,
+            Err(e) => return Err(DbError::from(e)),
+        };
+        
+        let user = match users.last_mut() {
+            Some(user) if user.user_id == user_id => user,
+            _ => {
+                users.push(UserActivity {
+                    user_id,
+                    username,
+                    transactions_count: 0,
+                    items_count: 0,
+                    added: 0,
+                    removed: 0,
+                    adjusted: 0,
+                    value_added: 0.0,
+                    value_removed: 0.0,
+                    first_at,
+                    last_at,
+                    items: Vec::new(),
+                });
+                users.last_mut().expect("user was just pushed")
+            }
+        };
+        
+        user.transactions_count += item.transactions_count;
+        user.items_count += 1;
+        user.added += item.added;
+        user.removed += item.removed;
+        user.adjusted += item.adjusted;
+        user.value_added += item.added as f64 * unit_price;
+        user.value_removed += item.removed as f64 * unit_price;
+        user.first_at = user.first_at.min(first_at);
+        user.last_at = user.last_at.max(last_at);
+        user.items.push(item);
+    }
+    
+    Ok(users)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::models::category::{Category, NewCategory};
+    use crate::models::inventory_item::NewInventoryItem;
+    use crate::models::transaction::{NewTransaction, Transaction};
+    
+    fn item(pool: &DbPool, name: &str, quantity: i32) -> i64 {
+        let category_id = Category::create(pool, NewCategory {
+            name: format!("{} category", name),
+            description: None,
+            parent_id: None,
+        }).unwrap();
+        InventoryItem::create(pool, NewInventoryItem {
+            name: name.to_string(),
+            description: None,
+            category_id,
+            quantity,
+            unit_price: 1.0,
+            sku: None,
+            location: None,
+            serialized: false,
+            base_unit_id: None,
+            reorder_point: None,
+            supplier: None,
+            attributes: Default::default(),
+        }).unwrap()
+    }
+    
+    fn options(method: ForecastMethod) -> ForecastOptions {
+        ForecastOptions {
+            method,
+            horizon: 7,
+            history_days: 90,
+            window: 7,
+            alpha: 0.3,
+            confidence: 0.95,
+        }
+    }
+    
+    fn age(pool: &DbPool, item_id: i64, days: i64) {
+        pool.get().unwrap().execute(
+            "UPDATE inventory_items SET created_at = datetime('now', ?) WHERE id = ?",
+            params![format!("-{} days", days), item_id],
+        ).unwrap();
+    }
+    
+    fn removal(pool: &DbPool, item_id: i64, quantity: i32, day: NaiveDate) -> i64 {
+        let transaction_id = Transaction::create(pool, NewTransaction {
+            item_id,
+            transaction_type: TransactionType::Removal,
+            quantity,
+            user_id: 1,
+            notes: None,
+            reference: None,
+            lot: None,
+            serial_numbers: Vec::new(),
+            reservation_id: None,
+            unit: None,
+            unit_quantity: None,
+        }).unwrap();
+        pool.get().unwrap().execute(
+            "UPDATE inventory_transactions SET transaction_date = ? WHERE id = ?",
+            params![format!("{} 12:00:00", day), transaction_id],
+        ).unwrap();
+        transaction_id
+    }
+    
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+    
+    #[test]
+    fn seasonal_forecast_ignores_weekdays_without_demand() {
+        let (_dir, pool) = test_pool();
+        let item_id = item(&pool, "Bolt", 100);
+        age(&pool, item_id, 35);
+        let today = Utc::now().date_naive();
+        for days_ago in 1..=35 {
+            let day = today - Duration::days(days_ago);
+            if day.weekday() == chrono::Weekday::Mon {
+                removal(&pool, item_id, 8, day);
+            }
+        }
+        
+        for method in [ForecastMethod::MovingAverage, ForecastMethod::ExponentialSmoothing] {
+            let forecast = forecast(&pool, item_id, &options(method)).unwrap();
+            assert!(forecast.seasonal);
+            assert_close(forecast.seasonal_indices.as_ref().unwrap()[0], 7.0);
+            for point in &forecast.forecast {
+                let expected = if point.date.weekday() == chrono::Weekday::Mon { 8.0 } else { 0.0 };
+                assert_close(point.quantity, expected);
+                assert_close(point.upper, expected);
+            }
+            assert_close(forecast.total_quantity, 8.0);
+        }
+    }
+    
+    #[test]
+    fn forecast_history_starts_when_the_item_was_created() {
+        let (_dir, pool) = test_pool();
+        let item_id = item(&pool, "Bolt", 50);
+        age(&pool, item_id, 20);
+        
+        let forecast = forecast(&pool, item_id, &options(ForecastMethod::MovingAverage)).unwrap();
+        let today = Utc::now().date_naive();
+        assert_eq!(forecast.history_start, Some(today - Duration::days(20)));
+        assert_eq!(forecast.history_end, Some(today - Duration::days(1)));
+    }    
+    #[test]
+    fn suggested_order_covers_reorder_point_plus_usage() {
+        assert_eq!(suggested_order(10, 4, 2.5, 3), 14);
+        assert_eq!(suggested_order(10, 4, 0.1, 3), 7);
+        // Reserved stock can leave less than nothing available
+        assert_eq!(suggested_order(0, -5, 0.0, 30), 5);
+    }
+    
+    #[test]
+    fn suggested_order_is_at_least_one_and_at_most_i32_max() {
+        assert_eq!(suggested_order(5, 5, 0.0, 30), 1);
+        assert_eq!(suggested_order(5, 20, 0.0, 30), 1);
+        assert_eq!(suggested_order(i32::MAX, i32::MIN, 1e6, 3650), i32::MAX);
+    }
+    
+    #[test]
+    fn reorder_suggestions_average_usage_over_the_window() {
+        let (_dir, pool) = test_pool();
+        let item_id = item(&pool, "Bolt", 20);
+        pool.get().unwrap().execute(
+            "UPDATE inventory_items SET reorder_point = 10, supplier = 'Acme' WHERE id = ?",
+            params![item_id],
+        ).unwrap();
+        removal(&pool, item_id, 14, Utc::now().date_naive());
+        
+        let groups = reorder_suggestions(&pool, 7, 5, None).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].supplier.as_deref(), Some("Acme"));
+        let suggestion = &groups[0].items[0];
+        assert_eq!(suggestion.available_quantity, 6);
+        assert_close(suggestion.average_daily_usage, 2.0);
+        assert_close(suggestion.days_of_stock.unwrap(), 3.0);
+        assert_eq!(suggestion.suggested_quantity, 14);
+        
+        assert!(reorder_suggestions(&pool, 7, 5, Some("Other")).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn flat_demand_forecasts_the_same_each_day_with_no_spread() {
+        let (_dir, pool) = test_pool();
+        let item_id = item(&pool, "Bolt", 100);
+        age(&pool, item_id, 10);
+        let today = Utc::now().date_naive();
+        for days_ago in 1..=10 {
+            removal(&pool, item_id, 4, today - Duration::days(days_ago));
+        }
+        
+        for method in [ForecastMethod::MovingAverage, ForecastMethod::ExponentialSmoothing] {
+            let forecast = forecast(&pool, item_id, &options(method)).unwrap();
+            assert!(!forecast.seasonal);
+            assert_eq!(forecast.total_removed, 40);
+            assert_close(forecast.average_daily_usage, 4.0);
+            assert_eq!(forecast.forecast.len(), 7);
+            assert_eq!(forecast.forecast[0].date, today);
+            for point in &forecast.forecast {
+                assert_close(point.quantity, 4.0);
+                assert_close(point.lower, 4.0);
+                assert_close(point.upper, 4.0);
+            }
+            assert_close(forecast.total_quantity, 28.0);
+        }
+    }
+    
+    #[test]
+    fn forecast_leaves_out_reversed_removals_and_widens_with_noise() {
+        let (_dir, pool) = test_pool();
+        let item_id = item(&pool, "Bolt", 100);
+        age(&pool, item_id, 4);
+        let today = Utc::now().date_naive();
+        for (days_ago, quantity) in [(4, 2), (3, 6), (2, 2), (1, 6)] {
+            removal(&pool, item_id, quantity, today - Duration::days(days_ago));
+        }
+        let mistake = removal(&pool, item_id, 50, today - Duration::days(1));
+        Transaction::reverse(&pool, mistake, 1, "Wrong item").unwrap();
+        
+        let mut moving_average = options(ForecastMethod::MovingAverage);
+        moving_average.window = 2;
+        let forecast = forecast(&pool, item_id, &moving_average).unwrap();
+        assert_eq!(forecast.total_removed, 16);
+        // Each day's error against the mean of the two before it is +2 or -2
+        let spread = 2.0 * (1.5_f64).sqrt() * 1.96;
+        assert_close(forecast.forecast[0].quantity, 4.0);
+        assert_close(forecast.forecast[0].upper, 4.0 + spread);
+        // The lower bound stops at zero rather than forecasting returns
+        assert_close(forecast.forecast[0].lower, 0.0);
+    }
+    
+    #[test]
+    fn forecast_options_are_range_checked() {
+        let mut bad = options(ForecastMethod::MovingAverage);
+        bad.confidence = 0.5;
+        assert!(bad.z_score().is_err());
+        bad = options(ForecastMethod::ExponentialSmoothing);
+        bad.alpha = 0.0;
+        assert!(bad.z_score().is_err());
+        bad = options(ForecastMethod::ExponentialSmoothing);
+        bad.history_days = i64::MAX;
+        assert!(bad.z_score().is_err());
+        assert_close(options(ForecastMethod::MovingAverage).z_score().unwrap(), 1.96);
+    }
+    
+    #[test]
+    fn abc_classes_go_where_each_item_starts_on_the_cumulative_share() {
+        let (_dir, pool) = test_pool();
+        let today = Utc::now().date_naive();
+        let mut ids = Vec::new();
+        for (name, price, removed) in [("Motor", 10.0, 7), ("Belt", 2.0, 10), ("Screw", 1.0, 10), ("Spare", 5.0, 0)] {
+            let item_id = item(&pool, name, 20);
+            pool.get().unwrap().execute(
+                "UPDATE inventory_items SET unit_price = ? WHERE id = ?",
+                params![price, item_id],
+            ).unwrap();
+            if removed > 0 {
+                removal(&pool, item_id, removed, today);
+            }
+            ids.push(item_id);
+        }
+        
+        let report = abc_analysis(&pool, today - Duration::days(29), today).unwrap();
+        assert_close(report.total_consumption_value, 100.0);
+        let classes: Vec<(i64, AbcClass)> = report.items.iter().map(|item| (item.item_id, item.class)).collect();
+        assert_eq!(classes, vec![
+            (ids[0], AbcClass::A),
+            (ids[1], AbcClass::A),
+            (ids[2], AbcClass::B),
+            (ids[3], AbcClass::C),
+        ]);
+        assert_close(report.items[0].share, 0.7);
+        assert_close(report.items[2].cumulative_share, 1.0);
+        assert_close(report.items[0].annual_consumption_value, 70.0 * 365.0 / 30.0);
+        
+        let counts: Vec<i64> = report.classes.iter().map(|class| class.items_count).collect();
+        assert_eq!(counts, vec![2, 1, 1]);
+        assert_close(report.classes[0].share, 0.9);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,559 @@
+use chrono::{NaiveDate, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fmt;
+use crate::db::{DbError, DbPool, DbResult};
+use crate::models::report::{self, ForecastMethod, ForecastOptions, HistoryGrouping, TransactionHistoryFilter};
+use crate::models::transaction::TransactionType;
+use crate::utils::export::{self, ExportError, ExportFormat, Sheet};
+
+/// Column names for sheets that may have no rows, in the order the rows'
+/// fields serialize.
+const CATEGORY_SUMMARY_COLUMNS: &[&str] = &[
+    "id", "name", "parent_id", "path", "depth", "items_count", "total_quantity", "total_value",
+];
+const TRANSACTION_HISTORY_COLUMNS: &[&str] = &[
+    "date", "period_end", "transactions_count", "additions", "removals", "adjustments",
+    "net_change",
+];
+const EXPIRING_LOT_COLUMNS: &[&str] = &[
+    "lot_id", "item_id", "item_name", "sku", "lot_number", "expiry_date", "days_until_expiry",
+    "quantity", "value",
+];
+const REORDER_LINE_COLUMNS: &[&str] = &[
+    "supplier", "item_id", "item_name", "sku", "quantity", "reserved_quantity",
+    "available_quantity", "reorder_point", "average_daily_usage", "days_of_stock",
+    "suggested_quantity", "unit_price", "estimated_cost",
+];
+const SUPPLIER_TOTAL_COLUMNS: &[&str] = &["supplier", "items", "total_quantity", "total_cost"];
+const FORECAST_COLUMNS: &[&str] = &["date", "quantity", "lower", "upper"];
+const ABC_ITEM_COLUMNS: &[&str] = &[
+    "item_id", "item_name", "sku", "category_id", "quantity_used", "unit_price",
+    "consumption_value", "annual_consumption_value", "share", "cumulative_share", "class",
+];
+const ABC_CLASS_COLUMNS: &[&str] = &["class", "items_count", "consumption_value", "share"];
+const TURNOVER_ITEM_COLUMNS: &[&str] = &[
+    "item_id", "item_name", "sku", "category_id", "category_name", "quantity_used",
+    "opening_quantity", "closing_quantity", "average_quantity", "cost_of_goods_used",
+    "average_inventory_value", "turnover_ratio", "days_of_supply",
+];
+const TURNOVER_CATEGORY_COLUMNS: &[&str] = &[
+    "category_id", "category_name", "items_count", "cost_of_goods_used", "average_inventory_value",
+    "turnover_ratio", "days_of_supply",
+];
+const DEAD_STOCK_COLUMNS: &[&str] = &[
+    "item_id", "item_name", "sku", "category_name", "location", "quantity", "unit_price", "value",
+    "quantity_removed", "last_movement_at", "last_movement_type", "last_movement_by",
+    "days_since_last_movement",
+];
+const LEDGER_COLUMNS: &[&str] = &[
+    "transaction_id", "transaction_date", "transaction_type", "quantity", "change", "balance",
+    "username", "reference", "notes",
+];
+const USER_ACTIVITY_COLUMNS: &[&str] = &[
+    "user_id", "username", "transactions_count", "items_count", "added", "removed", "adjusted",
+    "value_added", "value_removed", "first_at", "last_at",
+];
+const USER_ITEM_COLUMNS: &[&str] = &[
+    "username", "item_id", "item_name", "transactions_count", "added", "removed", "adjusted",
+    "net_change",
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExpiringQuery {
+    pub within_days: Option<i64>,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReorderQuery {
+    /// Days of removal history to average usage over.
+    pub usage_days: Option<i64>,
+    /// Days of average usage the suggested order should cover.
+    pub cover_days: Option<i64>,
+    pub supplier: Option<String>,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForecastQuery {
+    /// `moving_average` or `exponential_smoothing` (the default).
+    pub method: Option<String>,
+    pub horizon: Option<i64>,
+    pub history_days: Option<i64>,
+    pub window: Option<usize>,
+    pub alpha: Option<f64>,
+    pub confidence: Option<f64>,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadStockQuery {
+    /// Days without removals before stock counts as dead.
+    pub days: Option<i64>,
+    /// Units that may have been removed in the period, to include slow movers.
+    pub max_removed: Option<i64>,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserActivityQuery {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub user_id: Option<i64>,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionHistoryQuery {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    /// `day` (the default), `week` or `month`.
+    pub group_by: Option<String>,
+    pub item_id: Option<i64>,
+    pub category_id: Option<i64>,
+    pub user_id: Option<i64>,
+    /// `addition`, `removal` or `adjustment`.
+    pub transaction_type: Option<String>,
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DateRangeQuery {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub format: Option<String>,
+}
+
+/// Every report takes `format`: `json` (the default), `csv`, `xlsx` or `pdf`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormatQuery {
+    pub format: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ReportError {
+    BadRequest(String),
+    NotFound(String),
+    Internal(String),
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReportError::BadRequest(message) | ReportError::NotFound(message) | ReportError::Internal(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl ReportError {
+    fn from_db(e: DbError, context: &str) -> Self {
+        match e {
+            DbError::Validation(message) => ReportError::BadRequest(message),
+            e => ReportError::Internal(format!("Error {}: {}", context, e)),
+        }
+    }
+}
+
+impl From<ExportError> for ReportError {
+    fn from(e: ExportError) -> Self {
+        ReportError::Internal(format!("Error exporting report: {}", e))
+    }
+}
+
+/// A report and its parameters, from a request's path and query string or
+/// from a stored report schedule.
+#[derive(Debug)]
+pub enum ReportRequest {
+    InventorySummary,
+    CategorySummary,
+    TransactionHistory(TransactionHistoryQuery),
+    Expiring(ExpiringQuery),
+    ReorderSuggestions(ReorderQuery),
+    Forecast(i64, ForecastQuery),
+    Abc(DateRangeQuery),
+    Turnover(DateRangeQuery),
+    DeadStock(DeadStockQuery),
+    Ledger(i64, DateRangeQuery),
+    UserActivity(UserActivityQuery),
+}
+
+/// A generated report, ready to answer as JSON or render to a file.
+pub struct BuiltReport {
+    /// File name stem, such as `inventory-summary`.
+    pub name: String,
+    pub title: &'static str,
+    pub data: Value,
+    pub sheets: Vec<Sheet>,
+}
+
+impl BuiltReport {
+    fn new<T: Serialize>(name: &str, title: &'static str, data: &T, sheets: Vec<Sheet>) -> Result<Self, ReportError> {
+        Ok(BuiltReport {
+            name: name.to_string(),
+            title,
+            data: serde_json::to_value(data).map_err(|e| ReportError::Internal(e.to_string()))?,
+            sheets,
+        })
+    }
+    
+    pub fn render(&self, format: &ExportFormat) -> Result<Vec<u8>, ExportError> {
+        match format {
+            ExportFormat::Json => serde_json::to_vec_pretty(&self.data).map_err(|e| ExportError::Io(e.to_string())),
+            _ => export::render(format, self.title, &self.sheets),
+        }
+    }
+    
+    /// File name with today's date, such as `inventory-summary-2024-01-08.xlsx`.
+    pub fn filename(&self, format: &ExportFormat) -> String {
+        format!("{}-{}.{}", self.name, Utc::now().format("%Y-%m-%d"), format)
+    }
+}
+
+fn params<T: DeserializeOwned>(parameters: &Value) -> Result<T, ReportError> {
+    serde_json::from_value(parameters.clone())
+        .map_err(|e| ReportError::BadRequest(format!("Invalid report parameters: {}", e)))
+}
+
+fn item_param(parameters: &Value) -> Result<i64, ReportError> {
+    parameters
+        .get("item_id")
+        .and_then(Value::as_i64)
+        .ok_or_else(|| ReportError::BadRequest("This report needs an item_id parameter".to_string()))
+}
+
+/// Parses optional `YYYY-MM-DD` bounds, defaulting to the `default_days` days up to today.
+fn parse_date_range(
+    start_date: &Option<String>,
+    end_date: &Option<String>,
+    default_days: i64,
+) -> Result<(NaiveDate, NaiveDate), ReportError> {
+    let parse = |value: &Option<String>, name: &str| match value {
+        Some(value) => NaiveDate::parse_from_str(value, "%Y-%m-%d").map(Some).map_err(|_| {
+            ReportError::BadRequest(format!("{} must be a date in YYYY-MM-DD format", name))
+        }),
+        None => Ok(None),
+    };
+    
+    let end_date = parse(end_date, "end_date")?.unwrap_or_else(|| Utc::now().date_naive());
+    let start_date = parse(start_date, "start_date")?
+        .unwrap_or_else(|| end_date - chrono::Duration::days(default_days - 1));
+    
+    Ok((start_date, end_date))
+}
+
+/// A row with one leading column added, for flattening grouped reports.
+fn with_column<T: Serialize>(name: &str, value: Value, row: &T) -> Value {
+    let mut record = Map::new();
+    record.insert(name.to_string(), value);
+    if let Ok(Value::Object(fields)) = serde_json::to_value(row) {
+        record.extend(fields);
+    }
+    Value::Object(record)
+}
+
+impl TransactionHistoryQuery {
+    fn grouping(&self) -> Result<HistoryGrouping, ReportError> {
+        match self.group_by.as_deref() {
+            None => Ok(HistoryGrouping::Day),
+            Some(group_by) => HistoryGrouping::from_str(group_by).ok_or_else(|| {
+                ReportError::BadRequest(format!("Unknown group_by '{}'; use day, week or month", group_by))
+            }),
+        }
+    }
+    
+    fn filter(&self) -> Result<TransactionHistoryFilter, ReportError> {
+        let transaction_type = match self.transaction_type.as_deref() {
+            None => None,
+            Some(transaction_type) => Some(TransactionType::from_str(transaction_type).ok_or_else(|| {
+                ReportError::BadRequest(format!("Unknown transaction type '{}'", transaction_type))
+            })?),
+        };
+        
+        Ok(TransactionHistoryFilter {
+            item_id: self.item_id,
+            category_id: self.category_id,
+            user_id: self.user_id,
+            transaction_type,
+        })
+    }
+}
+
+impl ExpiringQuery {
+    fn within_days(&self) -> i64 {
+        self.within_days.unwrap_or(30)
+    }
+}
+
+impl ReorderQuery {
+    fn usage_days(&self) -> i64 {
+        self.usage_days.unwrap_or(90)
+    }
+    
+    fn cover_days(&self) -> i64 {
+        self.cover_days.unwrap_or(30)
+    }
+}
+
+impl ForecastQuery {
+    fn options(&self) -> Result<ForecastOptions, ReportError> {
+        let method = match self.method.as_deref() {
+            None => ForecastMethod::ExponentialSmoothing,
+            Some(method) => ForecastMethod::from_str(method).ok_or_else(|| {
+                ReportError::BadRequest(format!("Unknown forecast method '{}'", method))
+            })?,
+        };
+        
+        Ok(ForecastOptions {
+            method,
+            horizon: self.horizon.unwrap_or(30),
+            history_days: self.history_days.unwrap_or(180),
+            window: self.window.unwrap_or(14),
+            alpha: self.alpha.unwrap_or(0.3),
+            confidence: self.confidence.unwrap_or(0.95),
+        })
+    }
+}
+
+impl DeadStockQuery {
+    fn days(&self) -> i64 {
+        self.days.unwrap_or(90)
+    }
+    
+    fn max_removed(&self) -> i64 {
+        self.max_removed.unwrap_or(0)
+    }
+}
+
+impl ReportRequest {
+    /// Report types are named after their endpoints, e.g. `inventory-summary`
+    /// or `forecast`. `parameters` holds the query parameters as a JSON object,
+    /// plus `item_id` for the per-item reports.
+    pub fn parse(report_type: &str, parameters: &Value) -> Result<Self, ReportError> {
+        let parameters = if parameters.is_null() { &json!({}) } else { parameters };
+        
+        let request = match report_type {
+            "inventory-summary" => Ok(ReportRequest::InventorySummary),
+            "category-summary" => Ok(ReportRequest::CategorySummary),
+            "transaction-history" => Ok(ReportRequest::TransactionHistory(params(parameters)?)),
+            "expiring" => Ok(ReportRequest::Expiring(params(parameters)?)),
+            "reorder-suggestions" => Ok(ReportRequest::ReorderSuggestions(params(parameters)?)),
+            "forecast" => Ok(ReportRequest::Forecast(item_param(parameters)?, params(parameters)?)),
+            "abc" => Ok(ReportRequest::Abc(params(parameters)?)),
+            "turnover" => Ok(ReportRequest::Turnover(params(parameters)?)),
+            "dead-stock" => Ok(ReportRequest::DeadStock(params(parameters)?)),
+            "ledger" => Ok(ReportRequest::Ledger(item_param(parameters)?, params(parameters)?)),
+            "user-activity" => Ok(ReportRequest::UserActivity(params(parameters)?)),
+            _ => Err(ReportError::BadRequest(format!("Unknown report type '{}'", report_type))),
+        }?;
+        
+        request.check()?;
+        Ok(request)
+    }
+    
+    /// Applies the range checks the report itself would, so a schedule with
+    /// parameters its report rejects is refused when it is saved.
+    fn check(&self) -> Result<(), ReportError> {
+        let check = |result: DbResult<()>| result.map_err(|e| ReportError::from_db(e, "checking report parameters"));
+        let check_dates = |start_date: &Option<String>, end_date: &Option<String>, default_days: i64| {
+            let (start_date, end_date) = parse_date_range(start_date, end_date, default_days)?;
+            check(report::check_date_range(start_date, end_date))
+        };
+        
+        match self {
+            ReportRequest::InventorySummary | ReportRequest::CategorySummary => Ok(()),
+            ReportRequest::TransactionHistory(query) => {
+                check_dates(&query.start_date, &query.end_date, 31)?;
+                query.grouping()?;
+                query.filter()?;
+                Ok(())
+            }
+            ReportRequest::Expiring(query) => check(report::check_expiring_window(query.within_days())),
+            ReportRequest::ReorderSuggestions(query) => {
+                check(report::check_reorder_windows(query.usage_days(), query.cover_days()))
+            }
+            ReportRequest::Forecast(_, query) => check(query.options()?.z_score().map(|_| ())),
+            ReportRequest::Abc(query) | ReportRequest::Turnover(query) => {
+                check_dates(&query.start_date, &query.end_date, 365)
+            }
+            ReportRequest::DeadStock(query) => check(report::check_dead_stock_window(query.days(), query.max_removed())),
+            ReportRequest::Ledger(_, query) => check_dates(&query.start_date, &query.end_date, 30),
+            ReportRequest::UserActivity(query) => check_dates(&query.start_date, &query.end_date, 30),
+        }
+    }
+    
+    pub fn build(&self, pool: &DbPool) -> Result<BuiltReport, ReportError> {
+        match self {
+            ReportRequest::InventorySummary => {
+                let summary = report::inventory_summary(pool)
+                    .map_err(|e| ReportError::from_db(e, "building inventory summary"))?;
+                let sheets = vec![Sheet::from_rows("Inventory Summary", &[&summary])?];
+                BuiltReport::new("inventory-summary", "Inventory Summary", &summary, sheets)
+            }
+            ReportRequest::CategorySummary => {
+                let summaries = report::category_summary(pool)
+                    .map_err(|e| ReportError::from_db(e, "building category summary"))?;
+                let sheets = vec![Sheet::from_rows("Category Summary", &summaries)?.or_columns(CATEGORY_SUMMARY_COLUMNS)];
+                BuiltReport::new("category-summary", "Category Summary", &summaries, sheets)
+            }
+            ReportRequest::TransactionHistory(query) => {
+                let (start_date, end_date) = parse_date_range(&query.start_date, &query.end_date, 31)?;
+                
+                let group_by = query.grouping()?;
+                let filter = query.filter()?;
+                
+                let summaries = report::transaction_history(pool, start_date, end_date, &group_by, &filter)
+                    .map_err(|e| ReportError::from_db(e, "building transaction history"))?;
+                let sheets = vec![Sheet::from_rows("Transaction History", &summaries)?.or_columns(TRANSACTION_HISTORY_COLUMNS)];
+                BuiltReport::new("transaction-history", "Transaction History", &summaries, sheets)
+            }
+            ReportRequest::Expiring(query) => {
+                let lots = report::expiring_lots(pool, query.within_days())
+                    .map_err(|e| ReportError::from_db(e, "finding expiring lots"))?;
+                let sheets = vec![Sheet::from_rows("Expiring Lots", &lots)?.or_columns(EXPIRING_LOT_COLUMNS)];
+                BuiltReport::new("expiring-lots", "Expiring Lots", &lots, sheets)
+            }
+            ReportRequest::ReorderSuggestions(query) => {
+                let suggestions = report::reorder_suggestions(
+                    pool,
+                    query.usage_days(),
+                    query.cover_days(),
+                    query.supplier.as_deref(),
+                )
+                .map_err(|e| ReportError::from_db(e, "calculating reorder suggestions"))?;
+                
+                let lines: Vec<Value> = suggestions
+                    .iter()
+                    .flat_map(|group| group.items.iter().map(|item| with_column("supplier", json!(group.supplier), item)))
+                    .collect();
+                let totals: Vec<Value> = suggestions
+                    .iter()
+                    .map(|group| json!({
+                        "supplier": group.supplier,
+                        "items": group.items.len(),
+                        "total_quantity": group.total_quantity,
+                        "total_cost": group.total_cost,
+                    }))
+                    .collect();
+                let sheets = vec![
+                    Sheet::from_rows("Reorder Suggestions", &lines)?.or_columns(REORDER_LINE_COLUMNS),
+                    Sheet::from_rows("Suppliers", &totals)?.or_columns(SUPPLIER_TOTAL_COLUMNS),
+                ];
+                BuiltReport::new("reorder-suggestions", "Reorder Suggestions", &suggestions, sheets)
+            }
+            ReportRequest::Forecast(item_id, query) => {
+                let options = query.options()?;
+                
+                let forecast = report::forecast(pool, *item_id, &options).map_err(|e| match e {
+                    DbError::NotFound => ReportError::NotFound(format!("Inventory item with ID {} not found", item_id)),
+                    e => ReportError::from_db(e, "forecasting demand"),
+                })?;
+                
+                let summary = json!({
+                    "item_id": forecast.item_id,
+                    "item_name": forecast.item_name,
+                    "available_quantity": forecast.available_quantity,
+                    "method": forecast.method.to_string(),
+                    "confidence": forecast.confidence,
+                    "history_start": forecast.history_start,
+                    "history_end": forecast.history_end,
+                    "total_removed": forecast.total_removed,
+                    "average_daily_usage": forecast.average_daily_usage,
+                    "seasonal": forecast.seasonal,
+                    "total_quantity": forecast.total_quantity,
+                    "total_lower": forecast.total_lower,
+                    "total_upper": forecast.total_upper,
+                });
+                let sheets = vec![
+                    Sheet::from_rows("Forecast", &forecast.forecast)?.or_columns(FORECAST_COLUMNS),
+                    Sheet::from_rows("Summary", &[summary])?,
+                ];
+                BuiltReport::new(&format!("forecast-{}", item_id), "Demand Forecast", &forecast, sheets)
+            }
+            ReportRequest::Abc(query) => {
+                let (start_date, end_date) = parse_date_range(&query.start_date, &query.end_date, 365)?;
+                let analysis = report::abc_analysis(pool, start_date, end_date)
+                    .map_err(|e| ReportError::from_db(e, "running ABC analysis"))?;
+                let sheets = vec![
+                    Sheet::from_rows("Items", &analysis.items)?.or_columns(ABC_ITEM_COLUMNS),
+                    Sheet::from_rows("Classes", &analysis.classes)?.or_columns(ABC_CLASS_COLUMNS),
+                ];
+                BuiltReport::new("abc-analysis", "ABC Analysis", &analysis, sheets)
+            }
+            ReportRequest::Turnover(query) => {
+                let (start_date, end_date) = parse_date_range(&query.start_date, &query.end_date, 365)?;
+                let turnover = report::turnover(pool, start_date, end_date)
+                    .map_err(|e| ReportError::from_db(e, "calculating inventory turnover"))?;
+                let sheets = vec![
+                    Sheet::from_rows("Items", &turnover.items)?.or_columns(TURNOVER_ITEM_COLUMNS),
+                    Sheet::from_rows("Categories", &turnover.categories)?.or_columns(TURNOVER_CATEGORY_COLUMNS),
+                ];
+                BuiltReport::new("turnover", "Inventory Turnover", &turnover, sheets)
+            }
+            ReportRequest::DeadStock(query) => {
+                let items = report::dead_stock(pool, query.days(), query.max_removed())
+                    .map_err(|e| ReportError::from_db(e, "finding dead stock"))?;
+                let sheets = vec![Sheet::from_rows("Dead Stock", &items)?.or_columns(DEAD_STOCK_COLUMNS)];
+                BuiltReport::new("dead-stock", "Dead Stock", &items, sheets)
+            }
+            ReportRequest::Ledger(item_id, query) => {
+                let (start_date, end_date) = parse_date_range(&query.start_date, &query.end_date, 30)?;
+                let ledger = report::item_ledger(pool, *item_id, start_date, end_date).map_err(|e| match e {
+                    DbError::NotFound => ReportError::NotFound(format!("Inventory item with ID {} not found", item_id)),
+                    e => ReportError::from_db(e, "building item ledger"),
+                })?;
+                
+                let summary = json!({
+                    "item_id": ledger.item_id,
+                    "item_name": ledger.item_name,
+                    "sku": ledger.sku,
+                    "start_date": ledger.start_date,
+                    "end_date": ledger.end_date,
+                    "opening_balance": ledger.opening_balance,
+                    "total_in": ledger.total_in,
+                    "total_out": ledger.total_out,
+                    "closing_balance": ledger.closing_balance,
+                });
+                let sheets = vec![
+                    Sheet::from_rows("Movements", &ledger.entries)?.or_columns(LEDGER_COLUMNS),
+                    Sheet::from_rows("Summary", &[summary])?,
+                ];
+                BuiltReport::new(&format!("ledger-{}", item_id), "Item Ledger", &ledger, sheets)
+            }
+            ReportRequest::UserActivity(query) => {
+                let (start_date, end_date) = parse_date_range(&query.start_date, &query.end_date, 30)?;
+                let activity = report::user_activity(pool, start_date, end_date, query.user_id)
+                    .map_err(|e| ReportError::from_db(e, "summarising user activity"))?;
+                
+                let users: Vec<Value> = activity
+                    .iter()
+                    .map(|user| json!({
+                        "user_id": user.user_id,
+                        "username": user.username,
+                        "transactions_count": user.transactions_count,
+                        "items_count": user.items_count,
+                        "added": user.added,
+                        "removed": user.removed,
+                        "adjusted": user.adjusted,
+                        "value_added": user.value_added,
+                        "value_removed": user.value_removed,
+                        "first_at": user.first_at,
+                        "last_at": user.last_at,
+                    }))
+                    .collect();
+                let items: Vec<Value> = activity
+                    .iter()
+                    .flat_map(|user| user.items.iter().map(|item| with_column("username", json!(user.username), item)))
+                    .collect();
+                let sheets = vec![
+                    Sheet::from_rows("Users", &users)?.or_columns(USER_ACTIVITY_COLUMNS),
+                    Sheet::from_rows("Items", &items)?.or_columns(USER_ITEM_COLUMNS),
+                ];
+                BuiltReport::new("user-activity", "User Activity", &activity, sheets)
+            }
+        }
+    }
+}
@@ -0,0 +1,484 @@
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+use rusqlite::{params, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::utils::export::ExportFormat;
+
+const SCHEDULE_COLUMNS: &str =
+    "id, name, cron, report_type, parameters, format, recipients, active, created_by,
+     last_run_at, next_run_at, created_at";
+
+const RUN_COLUMNS: &str =
+    "id, schedule_id, status, file_path, file_size, recipients, error, started_at, finished_at";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReportSchedule {
+    pub id: i64,
+    pub name: String,
+    /// Cron expression in UTC, with five fields (minute first) or six (second first).
+    pub cron: String,
+    /// Report endpoint name, such as `inventory-summary` or `forecast`.
+    pub report_type: String,
+    /// Query parameters for the report, plus `item_id` for per-item reports.
+    pub parameters: Value,
+    pub format: String,
+    /// Addresses the report is emailed to; empty only archives it.
+    pub recipients: Vec<String>,
+    pub active: bool,
+    pub created_by: i64,
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// `None` while the schedule is paused and has no run pending.
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewReportSchedule {
+    pub name: String,
+    pub cron: String,
+    pub report_type: String,
+    #[serde(default)]
+    pub parameters: Value,
+    /// Defaults to `pdf`.
+    pub format: Option<String>,
+    #[serde(default)]
+    pub recipients: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateReportSchedule {
+    pub name: Option<String>,
+    pub cron: Option<String>,
+    pub report_type: Option<String>,
+    pub parameters: Option<Value>,
+    pub format: Option<String>,
+    pub recipients: Option<Vec<String>>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum RunStatus {
+    /// Archived and emailed to every recipient.
+    Delivered,
+    /// Archived only, because there were no recipients or no SMTP relay.
+    Archived,
+    Failed,
+}
+
+impl RunStatus {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(status: &str) -> Option<Self> {
+        match status.to_lowercase().as_str() {
+            "delivered" => Some(RunStatus::Delivered),
+            "archived" => Some(RunStatus::Archived),
+            "failed" => Some(RunStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            RunStatus::Delivered => "delivered",
+            RunStatus::Archived => "archived",
+            RunStatus::Failed => "failed",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// One generation of a scheduled report.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportRun {
+    pub id: i64,
+    pub schedule_id: i64,
+    pub status: RunStatus,
+    /// Archived file; `None` when the report could not be generated.
+    pub file_path: Option<String>,
+    pub file_size: Option<i64>,
+    pub recipients: Vec<String>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Outcome of a run, as recorded by the scheduler.
+#[derive(Debug)]
+pub struct NewReportRun {
+    pub status: RunStatus,
+    pub file_path: Option<String>,
+    pub file_size: Option<i64>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Parses a cron expression. Five-field expressions are taken to start at
+/// second zero of the minute.
+pub fn parse_cron(expression: &str) -> DbResult<Schedule> {
+    let expression = expression.trim();
+    let full_expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    
+    // The parser's message points at the offending field over several lines;
+    // its last line says what is wrong
+    Schedule::from_str(&full_expression).map_err(|e| {
+        let message = e.to_string();
+        DbError::Validation(format!(
+            "Invalid cron expression '{}': {}",
+            expression,
+            message.lines().last().unwrap_or_default()
+        ))
+    })
+}
+
+/// Formats a time the way SQLite's `CURRENT_TIMESTAMP` does, so it compares
+/// correctly against it.
+fn sqlite_timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn next_run(cron: &str, after: DateTime<Utc>) -> DbResult<Option<String>> {
+    Ok(parse_cron(cron)?.after(&after).next().map(sqlite_timestamp))
+}
+
+fn validate_format(format: &str) -> DbResult<()> {
+    match ExportFormat::from_str(format) {
+        Some(_) => Ok(()),
+        None => Err(DbError::Validation(format!(
+            "Unknown format '{}'; use json, csv, xlsx or pdf",
+            format
+        ))),
+    }
+}
+
+fn validate_recipients(recipients: &[String]) -> DbResult<()> {
+    for recipient in recipients {
+        if !recipient.contains('@') {
+            return Err(DbError::Validation(format!("'{}' is not an email address", recipient)));
+        }
+    }
+    Ok(())
+}
+
+fn recipients_json(recipients: &[String]) -> String {
+    serde_json::to_string(recipients).unwrap_or_else(|_| "[]".to_string())
+}
+
+impl ReportSchedule {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let parameters_str: String = row.get("parameters")?;
+        let recipients_str: String = row.get("recipients")?;
+        let last_run_at_str: Option<String> = row.get("last_run_at")?;
+        let next_run_at_str: Option<String> = row.get("next_run_at")?;
+        let created_at_str: String = row.get("created_at")?;
+        
+        Ok(ReportSchedule {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            cron: row.get("cron")?,
+            report_type: row.get("report_type")?,
+            parameters: serde_json::from_str(&parameters_str).unwrap_or_default(),
+            format: row.get("format")?,
+            recipients: serde_json::from_str(&recipients_str).unwrap_or_default(),
+            active: row.get("active")?,
+            created_by: row.get("created_by")?,
+            last_run_at: last_run_at_str.as_deref().map(parse_timestamp),
+            next_run_at: next_run_at_str.as_deref().map(parse_timestamp),
+            created_at: parse_timestamp(&created_at_str),
+        })
+    }
+    
+    pub fn find_by_id(pool: &DbPool, id: i64) -> DbResult<Self> {
+        let conn = pool.get()?;
+        conn.query_row(
+            &format!("SELECT {} FROM report_schedules WHERE id = ?", SCHEDULE_COLUMNS),
+            params![id],
+            Self::from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::from(e),
+        })
+    }
+    
+    /// Creates a schedule. The report type and parameters are checked by the
+    /// caller, which knows how to build reports.
+    pub fn create(pool: &DbPool, new_schedule: NewReportSchedule, user_id: i64) -> DbResult<i64> {
+        if new_schedule.name.trim().is_empty() {
+            return Err(DbError::Validation("Schedule name is required".to_string()));
+        }
+        
+        let format = new_schedule.format.unwrap_or_else(|| "pdf".to_string()).to_lowercase();
+        validate_format(&format)?;
+        validate_recipients(&new_schedule.recipients)?;
+        let next_run_at = next_run(&new_schedule.cron, Utc::now())?;
+        
+        let parameters = if new_schedule.parameters.is_null() {
+            "{}".to_string()
+        } else {
+            new_schedule.parameters.to_string()
+        };
+        
+        let conn = pool.get()?;
+        let result = conn.execute(
+            "INSERT INTO report_schedules (name, cron, report_type, parameters, format, recipients, created_by, next_run_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                new_schedule.name.trim(),
+                new_schedule.cron.trim(),
+                new_schedule.report_type,
+                parameters,
+                format,
+                recipients_json(&new_schedule.recipients),
+                user_id,
+                next_run_at
+            ],
+        )?;
+        
+        if result > 0 {
+            Ok(conn.last_insert_rowid())
+        } else {
+            Err(DbError::NoRowsAffected)
+        }
+    }
+    
+    pub fn list(pool: &DbPool) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM report_schedules ORDER BY id",
+            SCHEDULE_COLUMNS
+        ))?;
+        
+        let schedules_iter = stmt.query_map([], Self::from_row)?;
+        let mut schedules = Vec::new();
+        
+        for schedule_result in schedules_iter {
+            match schedule_result {
+                Ok(schedule) => schedules.push(schedule),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(schedules)
+    }
+    
+    /// Updates a schedule and works out its next run again. Pausing a
+    /// schedule clears its next run.
+    pub fn update(pool: &DbPool, id: i64, update: UpdateReportSchedule) -> DbResult<()> {
+        let mut schedule = Self::find_by_id(pool, id)?;
+        
+        if let Some(name) = update.name {
+            if name.trim().is_empty() {
+                return Err(DbError::Validation("Schedule name is required".to_string()));
+            }
+            schedule.name = name.trim().to_string();
+        }
+        
+        if let Some(cron) = update.cron {
+            parse_cron(&cron)?;
+            schedule.cron = cron.trim().to_string();
+        }
+        
+        if let Some(report_type) = update.report_type {
+            schedule.report_type = report_type;
+        }
+        
+        if let Some(parameters) = update.parameters {
+            schedule.parameters = parameters;
+        }
+        
+        if let Some(format) = update.format {
+            validate_format(&format)?;
+            schedule.format = format.to_lowercase();
+        }
+        
+        if let Some(recipients) = update.recipients {
+            validate_recipients(&recipients)?;
+            schedule.recipients = recipients;
+        }
+        
+        if let Some(active) = update.active {
+            schedule.active = active;
+        }
+        
+        let next_run_at = if schedule.active {
+            next_run(&schedule.cron, Utc::now())?
+        } else {
+            None
+        };
+        
+        let conn = pool.get()?;
+        conn.execute(
+            "UPDATE report_schedules SET name = ?, cron = ?, report_type = ?, parameters = ?, format = ?,
+             recipients = ?, active = ?, next_run_at = ? WHERE id = ?",
+            params![
+                schedule.name,
+                schedule.cron,
+                schedule.report_type,
+                schedule.parameters.to_string(),
+                schedule.format,
+                recipients_json(&schedule.recipients),
+                schedule.active,
+                next_run_at,
+                id
+            ],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Deletes a schedule and its run history. Archived files are kept.
+    pub fn delete(pool: &DbPool, id: i64) -> DbResult<()> {
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        
+        tx.execute("DELETE FROM report_runs WHERE schedule_id = ?", params![id])?;
+        let result = tx.execute("DELETE FROM report_schedules WHERE id = ?", params![id])?;
+        
+        if result == 0 {
+            return Err(DbError::NotFound);
+        }
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// Makes a schedule due now, so the scheduler runs it on its next poll,
+    /// even if it is paused.
+    pub fn run_now(pool: &DbPool, id: i64) -> DbResult<()> {
+        let conn = pool.get()?;
+        let result = conn.execute(
+            "UPDATE report_schedules SET next_run_at = CURRENT_TIMESTAMP WHERE id = ?",
+            params![id],
+        )?;
+        
+        if result > 0 {
+            Ok(())
+        } else {
+            Err(DbError::NotFound)
+        }
+    }
+    
+    /// Schedules whose next run is due, earliest first.
+    pub fn due(pool: &DbPool) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM report_schedules
+             WHERE next_run_at IS NOT NULL AND next_run_at <= CURRENT_TIMESTAMP
+             ORDER BY next_run_at, id",
+            SCHEDULE_COLUMNS
+        ))?;
+        
+        let schedules_iter = stmt.query_map([], Self::from_row)?;
+        let mut schedules = Vec::new();
+        
+        for schedule_result in schedules_iter {
+            match schedule_result {
+                Ok(schedule) => schedules.push(schedule),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(schedules)
+    }
+    
+    /// Logs a run and moves the schedule on to its next run after now.
+    /// Returns the run's id.
+    pub fn record_run(&self, pool: &DbPool, run: NewReportRun) -> DbResult<i64> {
+        let next_run_at = if self.active {
+            next_run(&self.cron, Utc::now())?
+        } else {
+            None
+        };
+        
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        
+        tx.execute(
+            "INSERT INTO report_runs (schedule_id, status, file_path, file_size, recipients, error, started_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                self.id,
+                run.status.to_string(),
+                run.file_path,
+                run.file_size,
+                recipients_json(&self.recipients),
+                run.error,
+                sqlite_timestamp(run.started_at)
+            ],
+        )?;
+        let run_id = tx.last_insert_rowid();
+        
+        tx.execute(
+            "UPDATE report_schedules SET last_run_at = ?, next_run_at = ? WHERE id = ?",
+            params![sqlite_timestamp(run.started_at), next_run_at, self.id],
+        )?;
+        
+        tx.commit()?;
+        Ok(run_id)
+    }
+}
+
+impl ReportRun {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let status_str: String = row.get("status")?;
+        let recipients_str: String = row.get("recipients")?;
+        let started_at_str: String = row.get("started_at")?;
+        let finished_at_str: String = row.get("finished_at")?;
+        
+        Ok(ReportRun {
+            id: row.get("id")?,
+            schedule_id: row.get("schedule_id")?,
+            status: RunStatus::from_str(&status_str).unwrap_or(RunStatus::Failed),
+            file_path: row.get("file_path")?,
+            file_size: row.get("file_size")?,
+            recipients: serde_json::from_str(&recipients_str).unwrap_or_default(),
+            error: row.get("error")?,
+            started_at: parse_timestamp(&started_at_str),
+            finished_at: parse_timestamp(&finished_at_str),
+        })
+    }
+    
+    pub fn find(pool: &DbPool, schedule_id: i64, id: i64) -> DbResult<Self> {
+        let conn = pool.get()?;
+        conn.query_row(
+            &format!("SELECT {} FROM report_runs WHERE schedule_id = ? AND id = ?", RUN_COLUMNS),
+            params![schedule_id, id],
+            Self::from_row,
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+            _ => DbError::from(e),
+        })
+    }
+    
+    /// Run history for a schedule, most recent first.
+    pub fn list_by_schedule(pool: &DbPool, schedule_id: i64, limit: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM report_runs WHERE schedule_id = ? ORDER BY id DESC LIMIT ?",
+            RUN_COLUMNS
+        ))?;
+        
+        let runs_iter = stmt.query_map(params![schedule_id, limit], Self::from_row)?;
+        let mut runs = Vec::new();
+        
+        for run_result in runs_iter {
+            match run_result {
+                Ok(run) => runs.push(run),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(runs)
+    }
+
+}
\ No newline at end of file
@@ -0,0 +1,338 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::models::inventory_item::{InventoryItem, ITEM_COLUMNS};
+use crate::models::transaction::{NewTransaction, TransactionType};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ReservationStatus {
+    Active,
+    Released,
+    Fulfilled,
+    Expired,
+}
+
+impl ReservationStatus {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(status: &str) -> Option<Self> {
+        match status.to_lowercase().as_str() {
+            "active" => Some(ReservationStatus::Active),
+            "released" => Some(ReservationStatus::Released),
+            "fulfilled" => Some(ReservationStatus::Fulfilled),
+            "expired" => Some(ReservationStatus::Expired),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ReservationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            ReservationStatus::Active => "active",
+            ReservationStatus::Released => "released",
+            ReservationStatus::Fulfilled => "fulfilled",
+            ReservationStatus::Expired => "expired",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Reservation {
+    pub id: Option<i64>,
+    pub item_id: i64,
+    pub holder: String,
+    pub quantity: i32,
+    pub status: ReservationStatus,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewReservation {
+    pub item_id: i64,
+    pub holder: String,
+    pub quantity: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Reservation {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let status_str: String = row.get("status")?;
+        let mut status = ReservationStatus::from_str(&status_str).unwrap_or(ReservationStatus::Active);
+        
+        let expires_at: Option<DateTime<Utc>> = row.get("expires_at")?;
+        
+        // Reservations lapse on their own; report them as expired once past due
+        if status == ReservationStatus::Active && expires_at.is_some_and(|at| at <= Utc::now()) {
+            status = ReservationStatus::Expired;
+        }
+        
+        let created_at_str: String = row.get("created_at")?;
+        let updated_at_str: String = row.get("updated_at")?;
+        
+        let created_at = parse_timestamp(&created_at_str);
+        let updated_at = parse_timestamp(&updated_at_str);
+        
+        Ok(Reservation {
+            id: row.get("id")?,
+            item_id: row.get("item_id")?,
+            holder: row.get("holder")?,
+            quantity: row.get("quantity")?,
+            status,
+            expires_at,
+            created_by: row.get("created_by")?,
+            created_at,
+            updated_at,
+        })
+    }
+    
+    pub fn find_by_id(pool: &DbPool, id: i64) -> DbResult<Self> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, item_id, holder, quantity, status, expires_at, created_by, created_at, updated_at
+             FROM reservations WHERE id = ?"
+        )?;
+        
+        stmt.query_row(params![id], Self::from_row)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                _ => DbError::from(e),
+            })
+    }
+    
+    /// Reserves units of an item, refusing to promise more than is available
+    /// after existing reservations.
+    pub fn create(pool: &DbPool, new_reservation: NewReservation, created_by: i64) -> DbResult<i64> {
+        if new_reservation.quantity <= 0 {
+            return Err(DbError::Validation("Reservation quantity must be positive".to_string()));
+        }
+        
+        if new_reservation.expires_at.is_some_and(|at| at <= Utc::now()) {
+            return Err(DbError::Validation("Reservation expiry must be in the future".to_string()));
+        }
+        
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        
+        let item = tx
+            .query_row(
+                &format!("SELECT {} FROM inventory_items WHERE id = ?", ITEM_COLUMNS),
+                params![new_reservation.item_id],
+                InventoryItem::from_row,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                _ => DbError::from(e),
+            })?;
+        
+        if new_reservation.quantity > item.available_quantity {
+            return Err(DbError::Validation(format!(
+                "Only {} units are available to promise",
+                item.available_quantity
+            )));
+        }
+        
+        tx.execute(
+            "INSERT INTO reservations (item_id, holder, quantity, status, expires_at, created_by)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                new_reservation.item_id,
+                new_reservation.holder,
+                new_reservation.quantity,
+                ReservationStatus::Active.to_string(),
+                new_reservation.expires_at,
+                created_by,
+            ],
+        )?;
+        
+        let reservation_id = tx.last_insert_rowid();
+        tx.commit()?;
+        
+        Ok(reservation_id)
+    }
+    
+    pub fn release(pool: &DbPool, id: i64) -> DbResult<()> {
+        let reservation = Self::find_by_id(pool, id)?;
+        
+        if reservation.status != ReservationStatus::Active {
+            return Err(DbError::Validation(format!(
+                "Reservation {} is {} and cannot be released",
+                id, reservation.status
+            )));
+        }
+        
+        let conn = pool.get()?;
+        conn.execute(
+            "UPDATE reservations SET status = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            params![ReservationStatus::Released.to_string(), id],
+        )?;
+        
+        Ok(())
+    }
+    
+    pub fn list(pool: &DbPool, item_id: Option<i64>, active_only: bool) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        
+        if let Some(item_id) = item_id {
+            conditions.push("item_id = ?");
+            params.push(item_id.to_string());
+        }
+        
+        if active_only {
+            conditions.push("status = 'active' AND (expires_at IS NULL OR datetime(expires_at) > datetime('now'))");
+        }
+        
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        
+        let query = format!(
+            "SELECT id, item_id, holder, quantity, status, expires_at, created_by, created_at, updated_at
+             FROM reservations {} ORDER BY id DESC",
+            where_clause
+        );
+        
+        let mut stmt = conn.prepare(&query)?;
+        let reservations_iter = stmt.query_map(rusqlite::params_from_iter(params), Self::from_row)?;
+        let mut reservations = Vec::new();
+        
+        for reservation_result in reservations_iter {
+            match reservation_result {
+                Ok(reservation) => reservations.push(reservation),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(reservations)
+    }
+    
+    /// Draws a removal down against the reservation it fulfils. A partial
+    /// removal leaves the rest of the reservation in place. Removals that
+    /// don't name a reservation can only take stock no reservation holds.
+    pub fn apply_transaction(
+        conn: &Connection,
+        transaction_id: i64,
+        new_transaction: &NewTransaction,
+    ) -> DbResult<()> {
+        let reservation_id = match new_transaction.reservation_id {
+            Some(reservation_id) => reservation_id,
+            None if matches!(new_transaction.transaction_type, TransactionType::Removal) => {
+                return Self::ensure_unreserved(conn, new_transaction);
+            }
+            None => return Ok(()),
+        };
+        
+        if !matches!(new_transaction.transaction_type, TransactionType::Removal) {
+            return Err(DbError::Validation(
+                "Only removals can consume a reservation".to_string(),
+            ));
+        }
+        
+        let reservation: Option<(i64, i32)> = conn
+            .query_row(
+                "SELECT item_id, quantity FROM reservations
+                 WHERE id = ? AND status = 'active'
+                 AND (expires_at IS NULL OR datetime(expires_at) > datetime('now'))",
+                params![reservation_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        
+        let (item_id, reserved) = reservation.ok_or_else(|| {
+            DbError::Validation(format!("Reservation {} is not active", reservation_id))
+        })?;
+        
+        if item_id != new_transaction.item_id {
+            return Err(DbError::Validation(format!(
+                "Reservation {} is for a different item",
+                reservation_id
+            )));
+        }
+        
+        if new_transaction.quantity > reserved {
+            return Err(DbError::Validation(format!(
+                "Reservation {} only holds {} units",
+                reservation_id, reserved
+            )));
+        }
+        
+        let remaining = reserved - new_transaction.quantity;
+        let status = if remaining == 0 {
+            ReservationStatus::Fulfilled
+        } else {
+            ReservationStatus::Active
+        };
+        
+        conn.execute(
+            "UPDATE reservations SET quantity = ?, status = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            params![remaining, status.to_string(), reservation_id],
+        )?;
+        conn.execute(
+            "UPDATE inventory_transactions SET reservation_id = ? WHERE id = ?",
+            params![reservation_id, transaction_id],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Checks a removal that has just been applied left enough stock to cover
+    /// every live reservation on the item.
+    fn ensure_unreserved(conn: &Connection, new_transaction: &NewTransaction) -> DbResult<()> {
+        let (on_hand, reserved): (i32, i32) = conn.query_row(
+            "SELECT i.quantity,
+                COALESCE((SELECT SUM(r.quantity) FROM reservations r
+                          WHERE r.item_id = i.id AND r.status = 'active'
+                          AND (r.expires_at IS NULL OR datetime(r.expires_at) > datetime('now'))), 0)
+             FROM inventory_items i WHERE i.id = ?",
+            params![new_transaction.item_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        
+        if on_hand < reserved {
+            return Err(DbError::Validation(format!(
+                "Only {} units are available to promise; the rest are held by reservations",
+                (on_hand + new_transaction.quantity - reserved).max(0)
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    /// Gives the units a removal drew from its reservation back to it when the
+    /// removal is reversed, reopening a fulfilled reservation. Reservations
+    /// released since are left closed.
+    pub fn reverse_transaction(conn: &Connection, original_id: i64) -> DbResult<()> {
+        let (reservation_id, quantity): (Option<i64>, i32) = conn.query_row(
+            "SELECT reservation_id, quantity FROM inventory_transactions WHERE id = ?",
+            params![original_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        
+        let reservation_id = match reservation_id {
+            Some(reservation_id) => reservation_id,
+            None => return Ok(()),
+        };
+        
+        conn.execute(
+            "UPDATE reservations SET quantity = quantity + ?, status = ?, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ? AND status != ?",
+            params![
+                quantity,
+                ReservationStatus::Active.to_string(),
+                reservation_id,
+                ReservationStatus::Released.to_string(),
+            ],
+        )?;
+        
+        Ok(())
+    }
+}
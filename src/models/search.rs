@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use rusqlite::params;
+use crate::db::{DbError, DbPool, DbResult};
+use crate::models::category::Category;
+use crate::models::inventory_item::{InventoryItem, ITEM_COLUMNS};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemSearchHit {
+    pub item: InventoryItem,
+    /// Best matching field with the matched terms wrapped in `<mark>` tags.
+    pub snippet: String,
+    /// BM25 score; lower is a better match.
+    pub rank: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategorySearchHit {
+    pub category: Category,
+    pub snippet: String,
+    pub rank: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub items: Vec<ItemSearchHit>,
+    pub categories: Vec<CategorySearchHit>,
+}
+
+/// Turns free text into an FTS5 query matching every word as a prefix, so
+/// "hdmi cab" finds "HDMI Cable" while it is still being typed. Returns
+/// `None` when the text has no searchable words.
+pub fn match_expression(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"*", term))
+        .collect();
+    
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Searches items and categories together, best matches first.
+pub fn search(pool: &DbPool, text: &str, limit: i64) -> DbResult<SearchResults> {
+    let expression = match match_expression(text) {
+        Some(expression) => expression,
+        None => {
+            return Ok(SearchResults {
+                items: Vec::new(),
+                categories: Vec::new(),
+            });
+        }
+    };
+    
+    let conn = pool.get()?;
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, m.snippet, m.rank
+         FROM inventory_items
+         JOIN (SELECT rowid AS item_id,
+                      snippet(items_fts, -1, '<mark>', '</mark>', '...', 12) AS snippet,
+                      bm25(items_fts) AS rank
+               FROM items_fts WHERE items_fts MATCH ?) m ON m.item_id = inventory_items.id
+         ORDER BY m.rank
+         LIMIT ?",
+        ITEM_COLUMNS
+    ))?;
+    
+    let items_iter = stmt.query_map(params![expression, limit], |row| {
+        Ok(ItemSearchHit {
+            item: InventoryItem::from_row(row)?,
+            snippet: row.get("snippet")?,
+            rank: row.get("rank")?,
+        })
+    })?;
+    
+    let mut items = Vec::new();
+    
+    for item_result in items_iter {
+        match item_result {
+            Ok(mut hit) => {
+                hit.item.category = Some(Category::find_by_id(pool, hit.item.category_id)?);
+                items.push(hit);
+            },
+            Err(e) => return Err(DbError::from(e)),
+        }
+    }
+    
+    let mut stmt = conn.prepare(
+        "SELECT c.id, c.name, c.description, c.parent_id, c.created_at, c.updated_at, m.snippet, m.rank
+         FROM categories c
+         JOIN (SELECT rowid AS category_id,
+                      snippet(categories_fts, -1, '<mark>', '</mark>', '...', 12) AS snippet,
+                      bm25(categories_fts) AS rank
+               FROM categories_fts WHERE categories_fts MATCH ?) m ON m.category_id = c.id
+         ORDER BY m.rank
+         LIMIT ?"
+    )?;
+    
+    let categories_iter = stmt.query_map(params![expression, limit], |row| {
+        Ok(CategorySearchHit {
+            category: Category::from_row(row)?,
+            snippet: row.get("snippet")?,
+            rank: row.get("rank")?,
+        })
+    })?;
+    
+    let mut categories = Vec::new();
+    
+    for category_result in categories_iter {
+        match category_result {
+            Ok(hit) => categories.push(hit),
+            Err(e) => return Err(DbError::from(e)),
+        };
+    }
+    
+    Ok(SearchResults { items, categories })
+}
\ No newline at end of file
@@ -0,0 +1,313 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::models::inventory_item::InventoryItem;
+use crate::models::transaction::{NewTransaction, Transaction, TransactionType};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SerialStatus {
+    InStock,
+    Issued,
+    Retired,
+    /// Registered by a receipt that was later reversed, so never really in
+    /// stock; it can be received again.
+    Inactive,
+}
+
+impl SerialStatus {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(status: &str) -> Option<Self> {
+        match status.to_lowercase().as_str() {
+            "in_stock" => Some(SerialStatus::InStock),
+            "issued" => Some(SerialStatus::Issued),
+            "retired" => Some(SerialStatus::Retired),
+            "inactive" => Some(SerialStatus::Inactive),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SerialStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            SerialStatus::InStock => "in_stock",
+            SerialStatus::Issued => "issued",
+            SerialStatus::Retired => "retired",
+            SerialStatus::Inactive => "inactive",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SerialNumber {
+    pub id: Option<i64>,
+    pub item_id: i64,
+    pub serial_number: String,
+    pub status: SerialStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SerialHistory {
+    pub serial: SerialNumber,
+    pub item: InventoryItem,
+    pub transactions: Vec<Transaction>,
+}
+
+impl SerialNumber {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let status_str: String = row.get("status")?;
+        let status = SerialStatus::from_str(&status_str).unwrap_or(SerialStatus::InStock);
+        
+        let created_at_str: String = row.get("created_at")?;
+        let updated_at_str: String = row.get("updated_at")?;
+        
+        let created_at = parse_timestamp(&created_at_str);
+        let updated_at = parse_timestamp(&updated_at_str);
+        
+        Ok(SerialNumber {
+            id: row.get("id")?,
+            item_id: row.get("item_id")?,
+            serial_number: row.get("serial_number")?,
+            status,
+            created_at,
+            updated_at,
+        })
+    }
+    
+    pub fn find_by_serial(pool: &DbPool, serial_number: &str) -> DbResult<Self> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, item_id, serial_number, status, created_at, updated_at
+             FROM serial_numbers WHERE serial_number = ?"
+        )?;
+        
+        stmt.query_row(params![serial_number], Self::from_row)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                _ => DbError::from(e),
+            })
+    }
+    
+    pub fn list_by_item(pool: &DbPool, item_id: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, item_id, serial_number, status, created_at, updated_at
+             FROM serial_numbers
+             WHERE item_id = ?
+             ORDER BY serial_number"
+        )?;
+        
+        let serials_iter = stmt.query_map(params![item_id], Self::from_row)?;
+        let mut serials = Vec::new();
+        
+        for serial_result in serials_iter {
+            match serial_result {
+                Ok(serial) => serials.push(serial),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(serials)
+    }
+    
+    /// Looks up a serial together with its item and every transaction that
+    /// moved it, oldest first.
+    pub fn history(pool: &DbPool, serial_number: &str) -> DbResult<SerialHistory> {
+        let serial = Self::find_by_serial(pool, serial_number)?;
+        let item = InventoryItem::find_by_id(pool, serial.item_id, true)?;
+        
+        let transaction_ids: Vec<i64> = {
+            let conn = pool.get()?;
+            let mut stmt = conn.prepare(
+                "SELECT transaction_id FROM transaction_serials
+                 WHERE serial_id = ?
+                 ORDER BY transaction_id"
+            )?;
+            let rows = stmt.query_map(params![serial.id], |row| row.get(0))?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        
+        let mut transactions = Vec::with_capacity(transaction_ids.len());
+        for transaction_id in transaction_ids {
+            transactions.push(Transaction::find_by_id(pool, transaction_id, false)?);
+        }
+        
+        Ok(SerialHistory {
+            serial,
+            item,
+            transactions,
+        })
+    }
+    
+    /// Marks an issued unit as permanently out of service. Units still in
+    /// stock have to be removed with a transaction first.
+    pub fn retire(pool: &DbPool, serial_number: &str) -> DbResult<()> {
+        let serial = Self::find_by_serial(pool, serial_number)?;
+        
+        if serial.status != SerialStatus::Issued {
+            return Err(DbError::Validation(format!(
+                "Serial {} is {} and only issued units can be retired",
+                serial_number, serial.status
+            )));
+        }
+        
+        let conn = pool.get()?;
+        conn.execute(
+            "UPDATE serial_numbers SET status = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            params![SerialStatus::Retired.to_string(), serial.id],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Moves the units named by a transaction on a serialized item. Additions
+    /// put units in stock (registering new serials or reactivating inactive
+    /// ones), removals issue them. The number of serials must match the
+    /// transaction quantity.
+    pub fn apply_transaction(
+        conn: &Connection,
+        transaction_id: i64,
+        new_transaction: &NewTransaction,
+    ) -> DbResult<()> {
+        let serialized: bool = conn.query_row(
+            "SELECT serialized FROM inventory_items WHERE id = ?",
+            params![new_transaction.item_id],
+            |row| row.get(0),
+        )?;
+        
+        if !serialized {
+            if !new_transaction.serial_numbers.is_empty() {
+                return Err(DbError::Validation(
+                    "Serial numbers can only be given for serialized items".to_string(),
+                ));
+            }
+            return Ok(());
+        }
+        
+        let (expected_status, new_status) = match new_transaction.transaction_type {
+            TransactionType::Addition => (SerialStatus::Issued, SerialStatus::InStock),
+            TransactionType::Removal => (SerialStatus::InStock, SerialStatus::Issued),
+            TransactionType::Adjustment => {
+                return Err(DbError::Validation(
+                    "Serialized items cannot be adjusted; add or remove specific serial numbers instead".to_string(),
+                ));
+            }
+        };
+        
+        if new_transaction.serial_numbers.len() != new_transaction.quantity as usize {
+            return Err(DbError::Validation(format!(
+                "Expected {} serial numbers but {} were given",
+                new_transaction.quantity,
+                new_transaction.serial_numbers.len()
+            )));
+        }
+        
+        for serial_number in &new_transaction.serial_numbers {
+            let existing: Option<(i64, i64, String)> = conn
+                .query_row(
+                    "SELECT id, item_id, status FROM serial_numbers WHERE serial_number = ?",
+                    params![serial_number],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+            
+            let (serial_id, status_before) = match existing {
+                Some((serial_id, item_id, status)) => {
+                    if item_id != new_transaction.item_id {
+                        return Err(DbError::Validation(format!(
+                            "Serial {} belongs to a different item",
+                            serial_number
+                        )));
+                    }
+                    let current_status = SerialStatus::from_str(&status);
+                    let reactivated = new_status == SerialStatus::InStock
+                        && current_status == Some(SerialStatus::Inactive);
+                    
+                    if current_status != Some(expected_status.clone()) && !reactivated {
+                        return Err(DbError::Validation(format!(
+                            "Serial {} is {}",
+                            serial_number, status
+                        )));
+                    }
+                    conn.execute(
+                        "UPDATE serial_numbers SET status = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+                        params![new_status.to_string(), serial_id],
+                    )?;
+                    (serial_id, Some(status))
+                }
+                None if new_status == SerialStatus::InStock => {
+                    conn.execute(
+                        "INSERT INTO serial_numbers (item_id, serial_number, status) VALUES (?, ?, ?)",
+                        params![new_transaction.item_id, serial_number, new_status.to_string()],
+                    )?;
+                    (conn.last_insert_rowid(), None)
+                }
+                None => {
+                    return Err(DbError::Validation(format!(
+                        "Serial {} not found",
+                        serial_number
+                    )));
+                }
+            };
+            
+            conn.execute(
+                "INSERT INTO transaction_serials (transaction_id, serial_id, status_before) VALUES (?, ?, ?)",
+                params![transaction_id, serial_id, status_before],
+            )?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Returns the units moved by `original_id` to the status they had before
+    /// it, recording the change against `reversal_id`. Serials first
+    /// registered by the original transaction become inactive, keeping their
+    /// history.
+    pub fn reverse_transaction(conn: &Connection, original_id: i64, reversal_id: i64) -> DbResult<()> {
+        let movements: Vec<(i64, String, String, Option<String>)> = {
+            let mut stmt = conn.prepare(
+                "SELECT ts.serial_id, s.serial_number, s.status, ts.status_before
+                 FROM transaction_serials ts
+                 JOIN serial_numbers s ON s.id = ts.serial_id
+                 WHERE ts.transaction_id = ?"
+            )?;
+            let rows = stmt.query_map(params![original_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?;
+            rows.collect::<SqliteResult<_>>()?
+        };
+        
+        for (serial_id, serial_number, status, status_before) in movements {
+            let moved_since: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM transaction_serials WHERE serial_id = ? AND transaction_id > ?)",
+                params![serial_id, original_id],
+                |row| row.get(0),
+            )?;
+            
+            if moved_since || status == SerialStatus::Retired.to_string() {
+                return Err(DbError::Validation(format!(
+                    "Serial {} has changed since transaction {} and is now {}",
+                    serial_number, original_id, status
+                )));
+            }
+            
+            let restored_status = status_before.unwrap_or_else(|| SerialStatus::Inactive.to_string());
+            
+            conn.execute(
+                "UPDATE serial_numbers SET status = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+                params![restored_status, serial_id],
+            )?;
+            conn.execute(
+                "INSERT INTO transaction_serials (transaction_id, serial_id, status_before) VALUES (?, ?, ?)",
+                params![reversal_id, serial_id, status],
+            )?;
+        }
+        
+        Ok(())
+    }
+}
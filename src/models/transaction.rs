@@ -1,10 +1,20 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use rusqlite::{params, Result as SqliteResult, Row};
+use std::fmt;
+use rusqlite::{params, Connection, Result as SqliteResult, Row};
 use crate::db::{DbError, DbPool, DbResult};
+use crate::models::event::{Event, EventType};
 use crate::models::inventory_item::InventoryItem;
+use crate::models::lot::{Lot, NewLot};
+use crate::models::reservation::Reservation;
+use crate::models::serial_number::SerialNumber;
+use crate::models::unit::UnitOfMeasure;
 use crate::models::user::User;
 
+const TRANSACTION_COLUMNS: &str =
+    "id, item_id, transaction_type, quantity, user_id, notes, reference, transaction_date, reversal_of, reversed_by,
+     quantity_before, quantity_after, reservation_id, unit_quantity, (SELECT code FROM units WHERE units.id = inventory_transactions.unit_id) AS unit";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum TransactionType {
     Addition,
@@ -13,6 +23,7 @@ pub enum TransactionType {
 }
 
 impl TransactionType {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(transaction_type: &str) -> Option<Self> {
         match transaction_type.to_lowercase().as_str() {
             "addition" => Some(TransactionType::Addition),
@@ -21,13 +32,16 @@ impl TransactionType {
             _ => None,
         }
     }
-    
-    pub fn to_string(&self) -> String {
-        match self {
-            TransactionType::Addition => "addition".to_string(),
-            TransactionType::Removal => "removal".to_string(),
-            TransactionType::Adjustment => "adjustment".to_string(),
-        }
+}
+
+impl fmt::Display for TransactionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            TransactionType::Addition => "addition",
+            TransactionType::Removal => "removal",
+            TransactionType::Adjustment => "adjustment",
+        };
+        write!(f, "{}", value)
     }
 }
 
@@ -39,20 +53,80 @@ pub struct Transaction {
     pub quantity: i32,
     pub user_id: i64,
     pub notes: Option<String>,
+    pub reference: Option<String>,
     pub transaction_date: DateTime<Utc>,
+    pub reversal_of: Option<i64>,
+    pub reversed_by: Option<i64>,
+    /// Quantity on hand just before and after this transaction; `None` for
+    /// transactions recorded before these were kept.
+    pub quantity_before: Option<i32>,
+    pub quantity_after: Option<i32>,
+    /// Reservation a removal drew down.
+    pub reservation_id: Option<i64>,
+    /// Unit and quantity as entered, when given in something other than the base unit.
+    pub unit: Option<String>,
+    pub unit_quantity: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub item: Option<InventoryItem>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<User>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// When `unit` is given the quantity is taken from `unit_quantity` and
+/// converted to the item's base unit, replacing `quantity`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NewTransaction {
     pub item_id: i64,
     pub transaction_type: TransactionType,
+    #[serde(default)]
     pub quantity: i32,
     pub user_id: i64,
     pub notes: Option<String>,
+    pub reference: Option<String>,
+    pub lot: Option<NewLot>,
+    #[serde(default)]
+    pub serial_numbers: Vec<String>,
+    pub reservation_id: Option<i64>,
+    pub unit: Option<String>,
+    pub unit_quantity: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewTransactionBatch {
+    pub reference: String,
+    pub user_id: i64,
+    pub notes: Option<String>,
+    pub lines: Vec<NewTransactionLine>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewTransactionLine {
+    pub item_id: i64,
+    pub transaction_type: TransactionType,
+    #[serde(default)]
+    pub quantity: i32,
+    pub notes: Option<String>,
+    pub lot: Option<NewLot>,
+    #[serde(default)]
+    pub serial_numbers: Vec<String>,
+    pub reservation_id: Option<i64>,
+    pub unit: Option<String>,
+    pub unit_quantity: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchLineResult {
+    pub line: usize,
+    pub item_id: i64,
+    pub transaction_id: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub reference: String,
+    pub committed: bool,
+    pub lines: Vec<BatchLineResult>,
 }
 
 impl Transaction {
@@ -73,7 +147,15 @@ impl Transaction {
             quantity: row.get("quantity")?,
             user_id: row.get("user_id")?,
             notes: row.get("notes")?,
+            reference: row.get("reference")?,
             transaction_date,
+            reversal_of: row.get("reversal_of")?,
+            reversed_by: row.get("reversed_by")?,
+            quantity_before: row.get("quantity_before")?,
+            quantity_after: row.get("quantity_after")?,
+            reservation_id: row.get("reservation_id")?,
+            unit: row.get("unit")?,
+            unit_quantity: row.get("unit_quantity")?,
             item: None,
             user: None,
         })
@@ -81,12 +163,13 @@ impl Transaction {
     
     pub fn find_by_id(pool: &DbPool, id: i64, with_relations: bool) -> DbResult<Self> {
         let conn = pool.get()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, item_id, transaction_type, quantity, user_id, notes, transaction_date 
-             FROM inventory_transactions WHERE id = ?"
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
+             FROM inventory_transactions WHERE id = ?",
+            TRANSACTION_COLUMNS
+        ))?;
         
-        let mut transaction = stmt.query_row(params![id], |row| Self::from_row(row))?;
+        let mut transaction = stmt.query_row(params![id], Self::from_row)?;
         
         if with_relations {
             transaction.item = Some(InventoryItem::find_by_id(pool, transaction.item_id, false)?);
@@ -98,33 +181,180 @@ impl Transaction {
     
     pub fn create(pool: &DbPool, new_transaction: NewTransaction) -> DbResult<i64> {
         let mut conn = pool.get()?;
-        let transaction_type_str = new_transaction.transaction_type.to_string();
         
         // Start a transaction to ensure atomicity
         let tx = conn.transaction()?;
+        let transaction_id = Self::create_in_tx(&tx, &new_transaction)?;
         
-        // Insert the transaction record
-        let result = tx.execute(
-            "INSERT INTO inventory_transactions (item_id, transaction_type, quantity, user_id, notes) 
-             VALUES (?, ?, ?, ?, ?)",
-            params![
-                new_transaction.item_id,
-                transaction_type_str,
-                new_transaction.quantity,
-                new_transaction.user_id,
-                new_transaction.notes,
-            ],
+        // Commit the transaction
+        tx.commit()?;
+        
+        Ok(transaction_id)
+    }
+    
+    /// Applies every line of a batch inside one SQLite transaction. If any line
+    /// fails the whole batch is rolled back and `committed` is false; the
+    /// per-line results say which lines failed and why.
+    pub fn create_batch(pool: &DbPool, batch: NewTransactionBatch) -> DbResult<BatchResult> {
+        let mut conn = pool.get()?;
+        let mut tx = conn.transaction()?;
+        
+        let mut lines = Vec::with_capacity(batch.lines.len());
+        let mut failed = false;
+        
+        for (index, line) in batch.lines.into_iter().enumerate() {
+            let item_id = line.item_id;
+            let new_transaction = NewTransaction {
+                item_id,
+                transaction_type: line.transaction_type,
+                quantity: line.quantity,
+                user_id: batch.user_id,
+                notes: line.notes.or_else(|| batch.notes.clone()),
+                reference: Some(batch.reference.clone()),
+                lot: line.lot,
+                serial_numbers: line.serial_numbers,
+                reservation_id: line.reservation_id,
+                unit: line.unit,
+                unit_quantity: line.unit_quantity,
+            };
+            
+            // Each line runs in its own savepoint so a failed line leaves no
+            // partial writes behind for the lines after it to build on
+            let result = if new_transaction.quantity < 0 {
+                Err(DbError::Validation("Quantity cannot be negative".to_string()))
+            } else {
+                let mut savepoint = tx.savepoint()?;
+                let result = Self::create_in_tx(&savepoint, &new_transaction);
+                if result.is_ok() {
+                    savepoint.commit()?;
+                } else {
+                    savepoint.rollback()?;
+                }
+                result
+            };
+            
+            match result {
+                Ok(transaction_id) => lines.push(BatchLineResult {
+                    line: index + 1,
+                    item_id,
+                    transaction_id: Some(transaction_id),
+                    error: None,
+                }),
+                Err(e) => {
+                    failed = true;
+                    let error = match e {
+                        DbError::Sqlite(rusqlite::Error::QueryReturnedNoRows) => {
+                            format!("Item {} not found", item_id)
+                        }
+                        _ => e.to_string(),
+                    };
+                    lines.push(BatchLineResult {
+                        line: index + 1,
+                        item_id,
+                        transaction_id: None,
+                        error: Some(error),
+                    });
+                }
+            }
+        }
+        
+        if failed {
+            tx.rollback()?;
+            // Ids handed out before the rollback no longer exist
+            for line in &mut lines {
+                line.transaction_id = None;
+            }
+        } else {
+            tx.commit()?;
+        }
+        
+        Ok(BatchResult {
+            reference: batch.reference,
+            committed: !failed,
+            lines,
+        })
+    }
+    
+    /// Records a transaction and applies it to the item quantity, lots, serial
+    /// numbers and any reservation it fulfils on an open connection, so
+    /// callers can group it with other writes.
+    pub fn create_in_tx(conn: &Connection, new_transaction: &NewTransaction) -> DbResult<i64> {
+        let converted;
+        let mut conversion = None;
+        let new_transaction = match &new_transaction.unit {
+            Some(unit_code) => {
+                let unit_quantity = new_transaction.unit_quantity.ok_or_else(|| {
+                    DbError::Validation("unit_quantity is required when a unit is given".to_string())
+                })?;
+                let result = UnitOfMeasure::convert(
+                    conn,
+                    new_transaction.item_id,
+                    unit_code,
+                    unit_quantity,
+                    &new_transaction.transaction_type,
+                )?;
+                
+                converted = NewTransaction {
+                    quantity: result.base_quantity,
+                    ..new_transaction.clone()
+                };
+                conversion = Some((result.unit_id, unit_quantity));
+                &converted
+            }
+            None => new_transaction,
+        };
+        
+        let transaction_id = Self::record_in_tx(conn, new_transaction)?;
+        
+        if let Some((unit_id, unit_quantity)) = conversion {
+            conn.execute(
+                "UPDATE inventory_transactions SET unit_id = ?, unit_quantity = ? WHERE id = ?",
+                params![unit_id, unit_quantity, transaction_id],
+            )?;
+        }
+        
+        Lot::apply_transaction(conn, transaction_id, new_transaction)?;
+        SerialNumber::apply_transaction(conn, transaction_id, new_transaction)?;
+        Reservation::apply_transaction(conn, transaction_id, new_transaction)?;
+        Self::publish_in_tx(conn, transaction_id)?;
+        
+        Ok(transaction_id)
+    }
+    
+    /// Records the `transaction.created` event, plus `item.low_stock` when
+    /// this transaction took the item down to its reorder point.
+    fn publish_in_tx(conn: &Connection, transaction_id: i64) -> DbResult<()> {
+        let transaction = conn.query_row(
+            &format!("SELECT {} FROM inventory_transactions WHERE id = ?", TRANSACTION_COLUMNS),
+            params![transaction_id],
+            Self::from_row,
         )?;
         
-        if result == 0 {
-            return Err(DbError::NoRowsAffected);
+        Event::record(conn, EventType::TransactionCreated, &transaction)?;
+        
+        let item = InventoryItem::find_in_tx(conn, transaction.item_id)?;
+        let crossed_reorder_point = match (item.reorder_point, transaction.quantity_before, transaction.quantity_after) {
+            (Some(reorder_point), Some(quantity_before), Some(quantity_after)) => {
+                quantity_after <= reorder_point && quantity_before > reorder_point
+            }
+            _ => false,
+        };
+        
+        if crossed_reorder_point {
+            Event::record(conn, EventType::ItemLowStock, &item)?;
         }
         
-        let transaction_id = tx.last_insert_rowid();
+        Ok(())
+    }
+    
+    /// Inserts the transaction with the quantity on hand before and after it,
+    /// and applies it to the item.
+    fn record_in_tx(conn: &Connection, new_transaction: &NewTransaction) -> DbResult<i64> {
+        let transaction_type_str = new_transaction.transaction_type.to_string();
         
         // Get current quantity
         let current_quantity: i32 = {
-            let mut stmt = tx.prepare("SELECT quantity FROM inventory_items WHERE id = ?")?;
+            let mut stmt = conn.prepare("SELECT quantity FROM inventory_items WHERE id = ?")?;
             stmt.query_row(params![new_transaction.item_id], |row| row.get(0))?
         };
         
@@ -135,8 +365,30 @@ impl Transaction {
             TransactionType::Adjustment => new_transaction.quantity,
         };
         
+        // Insert the transaction record
+        let result = conn.execute(
+            "INSERT INTO inventory_transactions (item_id, transaction_type, quantity, user_id, notes, reference, quantity_before, quantity_after) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                new_transaction.item_id,
+                transaction_type_str,
+                new_transaction.quantity,
+                new_transaction.user_id,
+                new_transaction.notes,
+                new_transaction.reference,
+                current_quantity,
+                new_quantity,
+            ],
+        )?;
+        
+        if result == 0 {
+            return Err(DbError::NoRowsAffected);
+        }
+        
+        let transaction_id = conn.last_insert_rowid();
+        
         // Update quantity
-        let update_result = tx.execute(
+        let update_result = conn.execute(
             "UPDATE inventory_items SET quantity = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
             params![new_quantity, new_transaction.item_id],
         )?;
@@ -145,22 +397,131 @@ impl Transaction {
             return Err(DbError::NoRowsAffected);
         }
         
-        // Commit the transaction
+        Ok(transaction_id)
+    }
+    
+    /// Voids a transaction by recording a compensating entry linked to it.
+    /// Additions and removals are undone by the opposite movement, while
+    /// adjustments are undone by an adjustment taking back the change they
+    /// made, so movements since then are kept.
+    pub fn reverse(pool: &DbPool, id: i64, user_id: i64, reason: &str) -> DbResult<i64> {
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        
+        let original = tx
+            .query_row(
+                &format!("SELECT {} FROM inventory_transactions WHERE id = ?", TRANSACTION_COLUMNS),
+                params![id],
+                Self::from_row,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                _ => DbError::from(e),
+            })?;
+        
+        if let Some(reversed_by) = original.reversed_by {
+            return Err(DbError::Validation(format!(
+                "Transaction {} has already been reversed by transaction {}",
+                id, reversed_by
+            )));
+        }
+        
+        if original.reversal_of.is_some() {
+            return Err(DbError::Validation(format!(
+                "Transaction {} is a reversal and cannot itself be reversed",
+                id
+            )));
+        }
+        
+        let (transaction_type, quantity) = match original.transaction_type {
+            TransactionType::Addition => (TransactionType::Removal, original.quantity),
+            TransactionType::Removal => (TransactionType::Addition, original.quantity),
+            TransactionType::Adjustment => match (original.quantity_before, original.quantity_after) {
+                // Take back the change the adjustment made, keeping later movements
+                (Some(quantity_before), Some(quantity_after)) => {
+                    let current_quantity: i32 = tx.query_row(
+                        "SELECT quantity FROM inventory_items WHERE id = ?",
+                        params![original.item_id],
+                        |row| row.get(0),
+                    )?;
+                    let restored = current_quantity - (quantity_after - quantity_before);
+                    
+                    if restored < 0 {
+                        return Err(DbError::Validation(format!(
+                            "Reversing transaction {} would leave {} in stock",
+                            id, restored
+                        )));
+                    }
+                    
+                    (TransactionType::Adjustment, restored)
+                }
+                (Some(quantity_before), None) => (TransactionType::Adjustment, quantity_before),
+                _ => {
+                    return Err(DbError::Validation(format!(
+                        "Transaction {} has no recorded prior quantity to restore",
+                        id
+                    )));
+                }
+            },
+        };
+        
+        let compensating = NewTransaction {
+            item_id: original.item_id,
+            transaction_type,
+            quantity,
+            user_id,
+            notes: Some(format!("Reversal of transaction {}: {}", id, reason)),
+            reference: original.reference.clone(),
+            lot: None,
+            serial_numbers: Vec::new(),
+            reservation_id: None,
+            unit: None,
+            unit_quantity: None,
+        };
+        
+        // The compensating entry restores exactly the lots, serial numbers and
+        // reservation the original moved
+        let reversal_id = Self::record_in_tx(&tx, &compensating)?;
+        Lot::reverse_transaction(&tx, id, reversal_id)?;
+        SerialNumber::reverse_transaction(&tx, id, reversal_id)?;
+        Reservation::reverse_transaction(&tx, id)?;
+        
+        // A movement entered in another unit is reversed in that unit too
+        if !matches!(original.transaction_type, TransactionType::Adjustment) {
+            tx.execute(
+                "UPDATE inventory_transactions
+                 SET (unit_id, unit_quantity) = (SELECT unit_id, unit_quantity FROM inventory_transactions WHERE id = ?)
+                 WHERE id = ?",
+                params![id, reversal_id],
+            )?;
+        }
+        
+        tx.execute(
+            "UPDATE inventory_transactions SET reversal_of = ? WHERE id = ?",
+            params![id, reversal_id],
+        )?;
+        tx.execute(
+            "UPDATE inventory_transactions SET reversed_by = ? WHERE id = ?",
+            params![reversal_id, id],
+        )?;
+        
+        Self::publish_in_tx(&tx, reversal_id)?;
         tx.commit()?;
         
-        Ok(transaction_id)
+        Ok(reversal_id)
     }
     
     pub fn list_by_item(pool: &DbPool, item_id: i64, with_relations: bool) -> DbResult<Vec<Self>> {
         let conn = pool.get()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, item_id, transaction_type, quantity, user_id, notes, transaction_date 
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
              FROM inventory_transactions 
              WHERE item_id = ? 
-             ORDER BY transaction_date DESC"
-        )?;
+             ORDER BY transaction_date DESC",
+            TRANSACTION_COLUMNS
+        ))?;
         
-        let transactions_iter = stmt.query_map(params![item_id], |row| Self::from_row(row))?;
+        let transactions_iter = stmt.query_map(params![item_id], Self::from_row)?;
         let mut transactions = Vec::new();
         
         for transaction_result in transactions_iter {
@@ -182,14 +543,15 @@ impl Transaction {
     
     pub fn list_by_user(pool: &DbPool, user_id: i64, with_relations: bool) -> DbResult<Vec<Self>> {
         let conn = pool.get()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, item_id, transaction_type, quantity, user_id, notes, transaction_date 
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
              FROM inventory_transactions 
              WHERE user_id = ? 
-             ORDER BY transaction_date DESC"
-        )?;
+             ORDER BY transaction_date DESC",
+            TRANSACTION_COLUMNS
+        ))?;
         
-        let transactions_iter = stmt.query_map(params![user_id], |row| Self::from_row(row))?;
+        let transactions_iter = stmt.query_map(params![user_id], Self::from_row)?;
         let mut transactions = Vec::new();
         
         for transaction_result in transactions_iter {
@@ -211,14 +573,15 @@ impl Transaction {
     
     pub fn list_recent(pool: &DbPool, limit: i64, with_relations: bool) -> DbResult<Vec<Self>> {
         let conn = pool.get()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, item_id, transaction_type, quantity, user_id, notes, transaction_date 
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
              FROM inventory_transactions 
              ORDER BY transaction_date DESC 
-             LIMIT ?"
-        )?;
+             LIMIT ?",
+            TRANSACTION_COLUMNS
+        ))?;
         
-        let transactions_iter = stmt.query_map(params![limit], |row| Self::from_row(row))?;
+        let transactions_iter = stmt.query_map(params![limit], Self::from_row)?;
         let mut transactions = Vec::new();
         
         for transaction_result in transactions_iter {
@@ -237,4 +600,85 @@ impl Transaction {
         
         Ok(transactions)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use crate::models::category::{Category, NewCategory};
+    use crate::models::inventory_item::NewInventoryItem;
+    
+    fn item(pool: &DbPool, quantity: i32) -> i64 {
+        let category_id = Category::create(pool, NewCategory {
+            name: "Fasteners".to_string(),
+            description: None,
+            parent_id: None,
+        }).unwrap();
+        InventoryItem::create(pool, NewInventoryItem {
+            name: "Washer".to_string(),
+            description: None,
+            category_id,
+            quantity,
+            unit_price: 0.1,
+            sku: None,
+            location: None,
+            serialized: false,
+            base_unit_id: None,
+            reorder_point: None,
+            supplier: None,
+            attributes: Default::default(),
+        }).unwrap()
+    }
+    
+    fn post(pool: &DbPool, item_id: i64, transaction_type: TransactionType, quantity: i32) -> i64 {
+        Transaction::create(pool, NewTransaction {
+            item_id,
+            transaction_type,
+            quantity,
+            user_id: 1,
+            notes: None,
+            reference: None,
+            lot: None,
+            serial_numbers: Vec::new(),
+            reservation_id: None,
+            unit: None,
+            unit_quantity: None,
+        }).unwrap()
+    }
+    
+    fn quantity(pool: &DbPool, item_id: i64) -> i32 {
+        InventoryItem::find_by_id(pool, item_id, false).unwrap().quantity
+    }
+    
+    #[test]
+    fn reversing_an_adjustment_takes_back_its_change_and_keeps_later_movements() {
+        let (_dir, pool) = test_pool();
+        let item_id = item(&pool, 10);
+        let adjustment_id = post(&pool, item_id, TransactionType::Adjustment, 15);
+        post(&pool, item_id, TransactionType::Removal, 3);
+        assert_eq!(quantity(&pool, item_id), 12);
+        
+        Transaction::reverse(&pool, adjustment_id, 1, "Miscounted").unwrap();
+        assert_eq!(quantity(&pool, item_id), 7);
+        
+        match Transaction::reverse(&pool, adjustment_id, 1, "Again") {
+            Err(DbError::Validation(message)) => assert!(message.contains("already been reversed"), "{}", message),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn reversing_an_adjustment_cannot_leave_negative_stock() {
+        let (_dir, pool) = test_pool();
+        let item_id = item(&pool, 10);
+        let adjustment_id = post(&pool, item_id, TransactionType::Adjustment, 15);
+        post(&pool, item_id, TransactionType::Removal, 12);
+        
+        match Transaction::reverse(&pool, adjustment_id, 1, "Miscounted") {
+            Err(DbError::Validation(message)) => assert!(message.contains("leave -2 in stock"), "{}", message),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        assert_eq!(quantity(&pool, item_id), 3);
+    }
 }
\ No newline at end of file
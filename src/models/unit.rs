@@ -0,0 +1,330 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::models::transaction::TransactionType;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnitOfMeasure {
+    pub id: Option<i64>,
+    pub code: String,
+    pub name: String,
+    /// Whether quantities in this unit may have a fractional part.
+    pub fractional: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewUnitOfMeasure {
+    pub code: String,
+    pub name: String,
+    #[serde(default)]
+    pub fractional: bool,
+}
+
+/// Which movements an alternate unit can be used for.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum UnitUsage {
+    Purchase,
+    Issue,
+    Both,
+}
+
+impl UnitUsage {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(usage: &str) -> Option<Self> {
+        match usage.to_lowercase().as_str() {
+            "purchase" => Some(UnitUsage::Purchase),
+            "issue" => Some(UnitUsage::Issue),
+            "both" => Some(UnitUsage::Both),
+            _ => None,
+        }
+    }
+    
+    fn allows(&self, transaction_type: &TransactionType) -> bool {
+        match transaction_type {
+            TransactionType::Addition => *self != UnitUsage::Issue,
+            TransactionType::Removal => *self != UnitUsage::Purchase,
+            TransactionType::Adjustment => true,
+        }
+    }
+}
+
+impl fmt::Display for UnitUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            UnitUsage::Purchase => "purchase",
+            UnitUsage::Issue => "issue",
+            UnitUsage::Both => "both",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// An alternate unit for an item, e.g. a 100 m drum of cable stocked in metres.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ItemUnit {
+    pub id: Option<i64>,
+    pub item_id: i64,
+    pub unit_id: i64,
+    pub unit_code: String,
+    /// Base units in one of this unit.
+    pub factor: f64,
+    pub usage: UnitUsage,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewItemUnit {
+    pub unit_id: i64,
+    pub factor: f64,
+    pub usage: UnitUsage,
+}
+
+/// A quantity entered in some unit, converted to the item's base unit.
+#[derive(Debug)]
+pub struct Conversion {
+    pub unit_id: i64,
+    pub base_quantity: i32,
+}
+
+impl UnitOfMeasure {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let created_at_str: String = row.get("created_at")?;
+        let created_at = parse_timestamp(&created_at_str);
+        
+        Ok(UnitOfMeasure {
+            id: row.get("id")?,
+            code: row.get("code")?,
+            name: row.get("name")?,
+            fractional: row.get("fractional")?,
+            created_at,
+        })
+    }
+    
+    pub fn find_by_id(pool: &DbPool, id: i64) -> DbResult<Self> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, code, name, fractional, created_at FROM units WHERE id = ?"
+        )?;
+        
+        stmt.query_row(params![id], Self::from_row)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                _ => DbError::from(e),
+            })
+    }
+    
+    pub fn create(pool: &DbPool, new_unit: NewUnitOfMeasure) -> DbResult<i64> {
+        if new_unit.code.trim().is_empty() {
+            return Err(DbError::Validation("Unit code is required".to_string()));
+        }
+        
+        let conn = pool.get()?;
+        
+        let result = conn.execute(
+            "INSERT INTO units (code, name, fractional) VALUES (?, ?, ?)",
+            params![new_unit.code.trim(), new_unit.name, new_unit.fractional],
+        )?;
+        
+        if result > 0 {
+            Ok(conn.last_insert_rowid())
+        } else {
+            Err(DbError::NoRowsAffected)
+        }
+    }
+    
+    pub fn list(pool: &DbPool) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, code, name, fractional, created_at FROM units ORDER BY code"
+        )?;
+        
+        let units_iter = stmt.query_map([], Self::from_row)?;
+        let mut units = Vec::new();
+        
+        for unit_result in units_iter {
+            match unit_result {
+                Ok(unit) => units.push(unit),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(units)
+    }
+    
+    /// Converts a quantity entered in `unit_code` into the item's base unit.
+    /// The unit must be the item's base unit or one of its alternate units
+    /// set up for this kind of movement, fractional quantities need a
+    /// fractional unit, and the result must be a whole number of base units.
+    pub fn convert(
+        conn: &Connection,
+        item_id: i64,
+        unit_code: &str,
+        quantity: f64,
+        transaction_type: &TransactionType,
+    ) -> DbResult<Conversion> {
+        let (unit_id, fractional): (i64, bool) = conn
+            .query_row(
+                "SELECT id, fractional FROM units WHERE code = ?",
+                params![unit_code],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .ok_or_else(|| DbError::Validation(format!("Unknown unit '{}'", unit_code)))?;
+        
+        if quantity < 0.0 {
+            return Err(DbError::Validation("Quantity cannot be negative".to_string()));
+        }
+        
+        if !fractional && quantity.fract() != 0.0 {
+            return Err(DbError::Validation(format!(
+                "Unit '{}' only takes whole quantities",
+                unit_code
+            )));
+        }
+        
+        let base_unit_id: Option<i64> = conn.query_row(
+            "SELECT base_unit_id FROM inventory_items WHERE id = ?",
+            params![item_id],
+            |row| row.get(0),
+        )?;
+        
+        let factor = if base_unit_id == Some(unit_id) {
+            1.0
+        } else {
+            let (factor, usage): (f64, String) = conn
+                .query_row(
+                    "SELECT factor, usage FROM item_units WHERE item_id = ? AND unit_id = ?",
+                    params![item_id, unit_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?
+                .ok_or_else(|| {
+                    DbError::Validation(format!("Unit '{}' is not set up for this item", unit_code))
+                })?;
+            
+            let usage = UnitUsage::from_str(&usage).unwrap_or(UnitUsage::Both);
+            if !usage.allows(transaction_type) {
+                return Err(DbError::Validation(format!(
+                    "Unit '{}' is a {} unit for this item and cannot be used for a {}",
+                    unit_code, usage, transaction_type
+                )));
+            }
+            
+            factor
+        };
+        
+        let base_quantity = quantity * factor;
+        let rounded = base_quantity.round();
+        
+        // Allow for float noise such as 0.1 * 30 but nothing more
+        if (base_quantity - rounded).abs() > 1e-6 {
+            return Err(DbError::Validation(format!(
+                "{} {} is {} in the item's base unit; stock is kept in whole base units",
+                quantity, unit_code, base_quantity
+            )));
+        }
+        
+        if rounded > i32::MAX as f64 {
+            return Err(DbError::Validation("Quantity is too large".to_string()));
+        }
+        
+        Ok(Conversion {
+            unit_id,
+            base_quantity: rounded as i32,
+        })
+    }
+}
+
+impl ItemUnit {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let usage_str: String = row.get("usage")?;
+        let usage = UnitUsage::from_str(&usage_str).unwrap_or(UnitUsage::Both);
+        
+        Ok(ItemUnit {
+            id: row.get("id")?,
+            item_id: row.get("item_id")?,
+            unit_id: row.get("unit_id")?,
+            unit_code: row.get("unit_code")?,
+            factor: row.get("factor")?,
+            usage,
+        })
+    }
+    
+    pub fn create(pool: &DbPool, item_id: i64, new_unit: NewItemUnit) -> DbResult<i64> {
+        if new_unit.factor <= 0.0 || !new_unit.factor.is_finite() {
+            return Err(DbError::Validation("Conversion factor must be positive".to_string()));
+        }
+        
+        let conn = pool.get()?;
+        
+        let base_unit_id: Option<i64> = conn
+            .query_row(
+                "SELECT base_unit_id FROM inventory_items WHERE id = ?",
+                params![item_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(DbError::NotFound)?;
+        
+        if base_unit_id.is_none() {
+            return Err(DbError::Validation(
+                "Set the item's base unit before adding alternate units".to_string(),
+            ));
+        }
+        
+        if base_unit_id == Some(new_unit.unit_id) {
+            return Err(DbError::Validation("The base unit cannot also be an alternate unit".to_string()));
+        }
+        
+        UnitOfMeasure::find_by_id(pool, new_unit.unit_id).map_err(|e| match e {
+            DbError::NotFound => DbError::Validation(format!("Unit {} not found", new_unit.unit_id)),
+            _ => e,
+        })?;
+        
+        conn.execute(
+            "INSERT INTO item_units (item_id, unit_id, factor, usage) VALUES (?, ?, ?, ?)",
+            params![item_id, new_unit.unit_id, new_unit.factor, new_unit.usage.to_string()],
+        )?;
+        
+        Ok(conn.last_insert_rowid())
+    }
+    
+    pub fn list_by_item(pool: &DbPool, item_id: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT iu.id, iu.item_id, iu.unit_id, u.code AS unit_code, iu.factor, iu.usage
+             FROM item_units iu
+             JOIN units u ON u.id = iu.unit_id
+             WHERE iu.item_id = ?
+             ORDER BY u.code"
+        )?;
+        
+        let units_iter = stmt.query_map(params![item_id], Self::from_row)?;
+        let mut units = Vec::new();
+        
+        for unit_result in units_iter {
+            match unit_result {
+                Ok(unit) => units.push(unit),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(units)
+    }
+    
+    pub fn delete(pool: &DbPool, item_id: i64, unit_id: i64) -> DbResult<()> {
+        let conn = pool.get()?;
+        let result = conn.execute(
+            "DELETE FROM item_units WHERE item_id = ? AND unit_id = ?",
+            params![item_id, unit_id],
+        )?;
+        
+        if result > 0 {
+            Ok(())
+        } else {
+            Err(DbError::NotFound)
+        }
+    }
+}
\ No newline at end of file
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use rusqlite::{params, Connection, Result as SqliteResult, Row};
+use std::fmt;
+use rusqlite::{params, Result as SqliteResult, Row};
 use crate::db::{DbError, DbPool, DbResult};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -11,6 +12,7 @@ pub enum UserRole {
 }
 
 impl UserRole {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(role: &str) -> Option<Self> {
         match role.to_lowercase().as_str() {
             "admin" => Some(UserRole::Admin),
@@ -19,13 +21,16 @@ impl UserRole {
             _ => None,
         }
     }
-    
-    pub fn to_string(&self) -> String {
-        match self {
-            UserRole::Admin => "admin".to_string(),
-            UserRole::Manager => "manager".to_string(),
-            UserRole::User => "user".to_string(),
-        }
+}
+
+impl fmt::Display for UserRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            UserRole::Admin => "admin",
+            UserRole::Manager => "manager",
+            UserRole::User => "user",
+        };
+        write!(f, "{}", value)
     }
 }
 
@@ -96,7 +101,7 @@ impl User {
              FROM users WHERE id = ?"
         )?;
         
-        let user = stmt.query_row(params![id], |row| Self::from_row(row))?;
+        let user = stmt.query_row(params![id], Self::from_row)?;
         Ok(user)
     }
     
@@ -107,7 +112,7 @@ impl User {
              FROM users WHERE username = ?"
         )?;
         
-        let user = stmt.query_row(params![username], |row| Self::from_row(row))?;
+        let user = stmt.query_row(params![username], Self::from_row)?;
         Ok(user)
     }
     
@@ -193,7 +198,7 @@ impl User {
              FROM users ORDER BY username"
         )?;
         
-        let users_iter = stmt.query_map([], |row| Self::from_row(row))?;
+        let users_iter = stmt.query_map([], Self::from_row)?;
         let mut users = Vec::new();
         
         for user_result in users_iter {
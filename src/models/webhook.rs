@@ -0,0 +1,342 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use rusqlite::{params, Result as SqliteResult, Row};
+use crate::db::{parse_timestamp, DbError, DbPool, DbResult};
+use crate::models::event::{Event, EventType};
+
+/// Deliveries are given up on after this many failed attempts.
+pub const MAX_DELIVERY_ATTEMPTS: i32 = 8;
+/// Delay before the first retry; it doubles with every further failure.
+const RETRY_BASE_SECONDS: i64 = 30;
+const RETRY_MAX_SECONDS: i64 = 6 * 60 * 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: Option<i64>,
+    pub url: String,
+    /// Shared secret for the `X-Webhook-Signature` HMAC; never returned by the API.
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    /// Events to deliver; empty means all of them.
+    pub events: Vec<EventType>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewWebhook {
+    pub url: String,
+    pub secret: String,
+    #[serde(default)]
+    pub events: Vec<EventType>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateWebhook {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<EventType>>,
+    pub active: Option<bool>,
+}
+
+/// One attempt at delivering an event to a webhook.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event_id: i64,
+    pub event_type: String,
+    pub attempt: i32,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub attempted_at: DateTime<Utc>,
+}
+
+/// An outbox row that is due for delivery, with everything needed to send it.
+#[derive(Debug)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub url: String,
+    pub secret: String,
+    pub attempts: i32,
+    pub event: Event,
+}
+
+fn validate_url(url: &str) -> DbResult<()> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(DbError::Validation("Webhook URL must start with http:// or https://".to_string()))
+    }
+}
+
+fn events_json(events: &[EventType]) -> String {
+    serde_json::to_string(events).unwrap_or_else(|_| "[]".to_string())
+}
+
+impl Webhook {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let events_str: String = row.get("events")?;
+        let created_at_str: String = row.get("created_at")?;
+        
+        Ok(Webhook {
+            id: row.get("id")?,
+            url: row.get("url")?,
+            secret: row.get("secret")?,
+            events: serde_json::from_str(&events_str).unwrap_or_default(),
+            active: row.get("active")?,
+            created_at: parse_timestamp(&created_at_str),
+        })
+    }
+    
+    pub fn find_by_id(pool: &DbPool, id: i64) -> DbResult<Self> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, url, secret, events, active, created_at FROM webhooks WHERE id = ?"
+        )?;
+        
+        stmt.query_row(params![id], Self::from_row)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DbError::NotFound,
+                _ => DbError::from(e),
+            })
+    }
+    
+    pub fn create(pool: &DbPool, new_webhook: NewWebhook) -> DbResult<i64> {
+        validate_url(&new_webhook.url)?;
+        
+        if new_webhook.secret.is_empty() {
+            return Err(DbError::Validation("A signing secret is required".to_string()));
+        }
+        
+        let conn = pool.get()?;
+        let result = conn.execute(
+            "INSERT INTO webhooks (url, secret, events) VALUES (?, ?, ?)",
+            params![new_webhook.url, new_webhook.secret, events_json(&new_webhook.events)],
+        )?;
+        
+        if result > 0 {
+            Ok(conn.last_insert_rowid())
+        } else {
+            Err(DbError::NoRowsAffected)
+        }
+    }
+    
+    pub fn list(pool: &DbPool) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, url, secret, events, active, created_at FROM webhooks ORDER BY id"
+        )?;
+        
+        let webhooks_iter = stmt.query_map([], Self::from_row)?;
+        let mut webhooks = Vec::new();
+        
+        for webhook_result in webhooks_iter {
+            match webhook_result {
+                Ok(webhook) => webhooks.push(webhook),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(webhooks)
+    }
+    
+    pub fn update(pool: &DbPool, id: i64, update: UpdateWebhook) -> DbResult<()> {
+        let mut webhook = Self::find_by_id(pool, id)?;
+        
+        if let Some(url) = update.url {
+            validate_url(&url)?;
+            webhook.url = url;
+        }
+        
+        if let Some(secret) = update.secret {
+            if secret.is_empty() {
+                return Err(DbError::Validation("A signing secret is required".to_string()));
+            }
+            webhook.secret = secret;
+        }
+        
+        if let Some(events) = update.events {
+            webhook.events = events;
+        }
+        
+        if let Some(active) = update.active {
+            webhook.active = active;
+        }
+        
+        let conn = pool.get()?;
+        conn.execute(
+            "UPDATE webhooks SET url = ?, secret = ?, events = ?, active = ? WHERE id = ?",
+            params![webhook.url, webhook.secret, events_json(&webhook.events), webhook.active, id],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Deletes a webhook along with its queued deliveries and delivery log.
+    pub fn delete(pool: &DbPool, id: i64) -> DbResult<()> {
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        
+        tx.execute("DELETE FROM webhook_deliveries WHERE webhook_id = ?", params![id])?;
+        tx.execute("DELETE FROM webhook_outbox WHERE webhook_id = ?", params![id])?;
+        let result = tx.execute("DELETE FROM webhooks WHERE id = ?", params![id])?;
+        
+        if result == 0 {
+            return Err(DbError::NotFound);
+        }
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// Puts deliveries that ran out of attempts back in the queue.
+    /// Returns how many were requeued.
+    pub fn retry_failed(pool: &DbPool, id: i64) -> DbResult<usize> {
+        Self::find_by_id(pool, id)?;
+        
+        let conn = pool.get()?;
+        let requeued = conn.execute(
+            "UPDATE webhook_outbox SET status = 'pending', attempts = 0, next_attempt_at = CURRENT_TIMESTAMP
+             WHERE webhook_id = ? AND status = 'failed'",
+            params![id],
+        )?;
+        
+        Ok(requeued)
+    }
+}
+
+impl WebhookDelivery {
+    pub fn from_row(row: &Row) -> SqliteResult<Self> {
+        let attempted_at_str: String = row.get("attempted_at")?;
+        
+        Ok(WebhookDelivery {
+            id: row.get("id")?,
+            webhook_id: row.get("webhook_id")?,
+            event_id: row.get("event_id")?,
+            event_type: row.get("event_type")?,
+            attempt: row.get("attempt")?,
+            status_code: row.get("status_code")?,
+            error: row.get("error")?,
+            duration_ms: row.get("duration_ms")?,
+            attempted_at: parse_timestamp(&attempted_at_str),
+        })
+    }
+    
+    /// Delivery log for a webhook, most recent attempt first.
+    pub fn list_by_webhook(pool: &DbPool, webhook_id: i64, limit: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT d.id, d.webhook_id, o.event_id, e.event_type, d.attempt, d.status_code, d.error,
+                    d.duration_ms, d.attempted_at
+             FROM webhook_deliveries d
+             JOIN webhook_outbox o ON o.id = d.outbox_id
+             JOIN events e ON e.id = o.event_id
+             WHERE d.webhook_id = ?
+             ORDER BY d.id DESC
+             LIMIT ?"
+        )?;
+        
+        let deliveries_iter = stmt.query_map(params![webhook_id, limit], Self::from_row)?;
+        let mut deliveries = Vec::new();
+        
+        for delivery_result in deliveries_iter {
+            match delivery_result {
+                Ok(delivery) => deliveries.push(delivery),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(deliveries)
+    }
+}
+
+impl OutboxEntry {
+    /// Pending deliveries whose next attempt is due, oldest first.
+    pub fn due(pool: &DbPool, limit: i64) -> DbResult<Vec<Self>> {
+        let conn = pool.get()?;
+        let mut stmt = conn.prepare(
+            "SELECT o.id AS outbox_id, o.webhook_id, o.attempts, w.url, w.secret,
+                    e.id, e.event_type, e.payload, e.created_at
+             FROM webhook_outbox o
+             JOIN webhooks w ON w.id = o.webhook_id
+             JOIN events e ON e.id = o.event_id
+             WHERE o.status = 'pending' AND w.active = 1 AND o.next_attempt_at <= CURRENT_TIMESTAMP
+             ORDER BY o.id
+             LIMIT ?"
+        )?;
+        
+        let entries_iter = stmt.query_map(params![limit], |row| {
+            Ok(OutboxEntry {
+                id: row.get("outbox_id")?,
+                webhook_id: row.get("webhook_id")?,
+                url: row.get("url")?,
+                secret: row.get("secret")?,
+                attempts: row.get("attempts")?,
+                event: Event::from_row(row)?,
+            })
+        })?;
+        
+        let mut entries = Vec::new();
+        
+        for entry_result in entries_iter {
+            match entry_result {
+                Ok(entry) => entries.push(entry),
+                Err(e) => return Err(DbError::from(e)),
+            };
+        }
+        
+        Ok(entries)
+    }
+    
+    /// Logs a delivery attempt and either marks the entry delivered or
+    /// schedules the next retry with exponential backoff, giving up after
+    /// `MAX_DELIVERY_ATTEMPTS`. `error` is `None` when the attempt succeeded.
+    pub fn record_attempt(
+        &self,
+        pool: &DbPool,
+        status_code: Option<u16>,
+        error: Option<String>,
+        duration_ms: i64,
+    ) -> DbResult<()> {
+        let attempt = self.attempts + 1;
+        
+        let mut conn = pool.get()?;
+        let tx = conn.transaction()?;
+        
+        tx.execute(
+            "INSERT INTO webhook_deliveries (outbox_id, webhook_id, attempt, status_code, error, duration_ms)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            params![self.id, self.webhook_id, attempt, status_code, error, duration_ms],
+        )?;
+        
+        match error {
+            None => {
+                tx.execute(
+                    "UPDATE webhook_outbox SET status = 'delivered', attempts = ?, last_error = NULL,
+                     delivered_at = CURRENT_TIMESTAMP WHERE id = ?",
+                    params![attempt, self.id],
+                )?;
+            }
+            Some(error) if attempt >= MAX_DELIVERY_ATTEMPTS => {
+                tx.execute(
+                    "UPDATE webhook_outbox SET status = 'failed', attempts = ?, last_error = ? WHERE id = ?",
+                    params![attempt, error, self.id],
+                )?;
+            }
+            Some(error) => {
+                let delay = (RETRY_BASE_SECONDS << (attempt - 1)).min(RETRY_MAX_SECONDS);
+                tx.execute(
+                    "UPDATE webhook_outbox SET attempts = ?, last_error = ?,
+                     next_attempt_at = datetime('now', ?) WHERE id = ?",
+                    params![attempt, error, format!("+{} seconds", delay), self.id],
+                )?;
+            }
+        }
+        
+        tx.commit()?;
+        Ok(())
+    }
+}
\ No newline at end of file
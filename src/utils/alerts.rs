@@ -0,0 +1,142 @@
+use futures::future::BoxFuture;
+use log::{error, info, warn};
+use std::time::Duration;
+use crate::config::Config;
+use crate::db::{run_blocking, DbPool, DbResult};
+use crate::models::alert::{Alert, AlertDelivery, Recipient};
+use crate::utils::mail::Mailer;
+
+/// How often the engine looks for stock movements to evaluate.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const BATCH_SIZE: i64 = 50;
+
+/// A way of telling people about a newly raised alert.
+pub trait AlertChannel: Send + Sync {
+    fn name(&self) -> &'static str;
+    
+    fn deliver<'a>(&'a self, alert: &'a Alert, recipients: &'a [Recipient]) -> BoxFuture<'a, Result<(), String>>;
+}
+
+/// Writes alerts to the server log.
+pub struct LogChannel;
+
+impl AlertChannel for LogChannel {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+    
+    fn deliver<'a>(&'a self, alert: &'a Alert, recipients: &'a [Recipient]) -> BoxFuture<'a, Result<(), String>> {
+        let usernames: Vec<&str> = recipients.iter().map(|r| r.username.as_str()).collect();
+        warn!("Stock alert {}: {} (subscribers: {})", alert.id, alert.message, usernames.join(", "));
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Publishes alerts as `alert.raised` events, which go out to registered
+/// webhooks and the live event stream.
+pub struct WebhookChannel {
+    pool: DbPool,
+}
+
+impl AlertChannel for WebhookChannel {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+    
+    fn deliver<'a>(&'a self, alert: &'a Alert, _recipients: &'a [Recipient]) -> BoxFuture<'a, Result<(), String>> {
+        let alert = alert.clone();
+        Box::pin(async move {
+            run_blocking(&self.pool, move |pool| alert.publish(pool))
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// Emails subscribers through the SMTP relay.
+pub struct EmailChannel {
+    mailer: Mailer,
+}
+
+impl AlertChannel for EmailChannel {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+    
+    fn deliver<'a>(&'a self, alert: &'a Alert, recipients: &'a [Recipient]) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let to: Vec<String> = recipients.iter().map(|r| r.email.clone()).collect();
+            let subject = format!("[Inventory] {}", alert.message);
+            let body = format!(
+                "{}\n\nAlert {} raised at {}. Acknowledge or resolve it at /api/alerts/{}.\n",
+                alert.message, alert.id, alert.created_at, alert.id
+            );
+            
+            self.mailer.send(&to, &subject, body).await.map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// Builds the channels named in `ALERT_CHANNELS`, skipping any that are
+/// unknown or not configured.
+pub fn channels_from_config(config: &Config, pool: &DbPool) -> Vec<Box<dyn AlertChannel>> {
+    let mut channels: Vec<Box<dyn AlertChannel>> = Vec::new();
+    
+    for name in &config.alert_channels {
+        match name.as_str() {
+            "log" => channels.push(Box::new(LogChannel)),
+            "webhook" => channels.push(Box::new(WebhookChannel { pool: pool.clone() })),
+            "email" => match Mailer::from_config(config) {
+                Ok(Some(mailer)) => channels.push(Box::new(EmailChannel { mailer })),
+                Ok(None) => warn!("Email alert channel needs SMTP_HOST; skipping it"),
+                Err(e) => warn!("Email alert channel disabled: {}", e),
+            },
+            other => warn!("Unknown alert channel '{}'", other),
+        }
+    }
+    
+    channels
+}
+
+/// Evaluates stock after each movement and delivers new alerts until the
+/// server shuts down. Failed deliveries are retried with backoff.
+pub async fn run_alert_engine(pool: DbPool, channels: Vec<Box<dyn AlertChannel>>) {
+    let names: Vec<&str> = channels.iter().map(|channel| channel.name()).collect();
+    info!("Alert engine delivering through: {}", names.join(", "));
+    
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    
+    loop {
+        interval.tick().await;
+        
+        let channel_names = names.clone();
+        if let Err(e) = run_blocking(&pool, move |pool| Alert::evaluate_pending(pool, &channel_names)).await {
+            error!("Alert evaluation failed: {}", e);
+        }
+        
+        if let Err(e) = deliver_due(&pool, &channels, &names).await {
+            error!("Alert delivery failed: {}", e);
+        }
+    }
+}
+
+async fn deliver_due(pool: &DbPool, channels: &[Box<dyn AlertChannel>], names: &[&'static str]) -> DbResult<()> {
+    let names = names.to_vec();
+    
+    for delivery in run_blocking(pool, move |pool| AlertDelivery::due(pool, &names, BATCH_SIZE)).await? {
+        let channel = match channels.iter().find(|channel| channel.name() == delivery.channel) {
+            Some(channel) => channel,
+            None => continue,
+        };
+        
+        let result = channel.deliver(&delivery.alert, &delivery.recipients).await;
+        
+        if let Err(e) = &result {
+            warn!("{} channel failed for alert {}: {}", channel.name(), delivery.alert.id, e);
+        }
+        
+        run_blocking(pool, move |pool| delivery.record_attempt(pool, result.err())).await?;
+    }
+    
+    Ok(())
+}
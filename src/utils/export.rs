@@ -0,0 +1,315 @@
+use printpdf::{Mm, PdfDocument};
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use thiserror::Error;
+
+/// Landscape A4, in millimetres.
+const PAGE_WIDTH: f32 = 297.0;
+const PAGE_HEIGHT: f32 = 210.0;
+const PAGE_MARGIN: f32 = 12.0;
+/// PDF tables are set in a monospaced font so columns line up by character
+/// count. It is embedded because the PDF built-in fonts only cover Latin-1.
+const PDF_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSansMono.ttf");
+const PDF_BOLD_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSansMono-Bold.ttf");
+const PDF_FONT_SIZE: f32 = 7.0;
+const PDF_LINE_HEIGHT: f32 = 3.6;
+const PDF_CHAR_WIDTH: f32 = PDF_FONT_SIZE * 0.6 * 25.4 / 72.0;
+const PDF_MAX_COLUMN_CHARS: usize = 28;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("XLSX error: {0}")]
+    Xlsx(#[from] XlsxError),
+    #[error("PDF error: {0}")]
+    Pdf(String),
+    #[error("Could not finish export: {0}")]
+    Io(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Xlsx,
+    Pdf,
+}
+
+impl ExportFormat {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            "xlsx" => Some(ExportFormat::Xlsx),
+            "pdf" => Some(ExportFormat::Pdf),
+            _ => None,
+        }
+    }
+    
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ExportFormat::Pdf => "application/pdf",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Pdf => "pdf",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// One table of a report. Columns come from the rows' field names, with
+/// nested objects flattened to `parent.field`.
+#[derive(Debug)]
+pub struct Sheet {
+    pub title: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Sheet {
+    pub fn from_rows<T: Serialize>(title: &str, rows: &[T]) -> Result<Self, ExportError> {
+        let mut columns: Vec<String> = Vec::new();
+        let mut records = Vec::with_capacity(rows.len());
+        
+        for row in rows {
+            let value = serde_json::to_value(row).map_err(|e| ExportError::Io(e.to_string()))?;
+            let mut record = Map::new();
+            flatten("", value, &mut record);
+            
+            for key in record.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+            records.push(record);
+        }
+        
+        let rows = records
+            .into_iter()
+            .map(|mut record| {
+                columns
+                    .iter()
+                    .map(|column| record.remove(column).unwrap_or(Value::Null))
+                    .collect()
+            })
+            .collect();
+        
+        Ok(Sheet {
+            title: title.to_string(),
+            columns,
+            rows,
+        })
+    }
+    
+    /// Names the columns of a sheet without rows, which has nothing to take
+    /// them from, so empty reports still get a header.
+    pub fn or_columns(mut self, columns: &[&str]) -> Self {
+        if self.columns.is_empty() {
+            self.columns = columns.iter().map(|column| column.to_string()).collect();
+        }
+        self
+    }
+}
+
+/// Flattens nested objects into dotted keys; lists are kept as JSON text.
+fn flatten(prefix: &str, value: Value, record: &mut Map<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+                flatten(&key, value, record);
+            }
+        }
+        Value::Array(_) => {
+            record.insert(prefix.to_string(), Value::String(value.to_string()));
+        }
+        _ => {
+            let key = if prefix.is_empty() { "value".to_string() } else { prefix.to_string() };
+            record.insert(key, value);
+        }
+    }
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Writes sheets as CSV. A report with several sheets gets each one under a
+/// title line, separated by a blank line.
+pub fn to_csv(sheets: &[Sheet]) -> Result<Vec<u8>, ExportError> {
+    let mut output = Vec::new();
+    
+    for (i, sheet) in sheets.iter().enumerate() {
+        let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(Vec::new());
+        
+        if sheets.len() > 1 {
+            if i > 0 {
+                output.push(b'\n');
+            }
+            writer.write_record([&sheet.title])?;
+        }
+        
+        writer.write_record(&sheet.columns)?;
+        for row in &sheet.rows {
+            writer.write_record(row.iter().map(cell_text))?;
+        }
+        
+        output.extend(writer.into_inner().map_err(|e| ExportError::Io(e.to_string()))?);
+    }
+    
+    Ok(output)
+}
+
+/// Writes each sheet to its own worksheet, keeping numbers as numbers.
+pub fn to_xlsx(sheets: &[Sheet]) -> Result<Vec<u8>, ExportError> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    
+    for sheet in sheets {
+        let worksheet = workbook.add_worksheet();
+        // Worksheet names are limited to 31 characters and a safe character set
+        let name: String = sheet
+            .title
+            .chars()
+            .filter(|c| !"[]:*?/\\".contains(*c))
+            .take(31)
+            .collect();
+        worksheet.set_name(name)?;
+        
+        for (col, column) in sheet.columns.iter().enumerate() {
+            worksheet.write_string_with_format(0, col as u16, column, &bold)?;
+        }
+        
+        for (i, row) in sheet.rows.iter().enumerate() {
+            let row_number = i as u32 + 1;
+            for (col, value) in row.iter().enumerate() {
+                match value {
+                    Value::Null => {}
+                    Value::Number(number) => {
+                        worksheet.write_number(row_number, col as u16, number.as_f64().unwrap_or_default())?;
+                    }
+                    Value::Bool(flag) => {
+                        worksheet.write_boolean(row_number, col as u16, *flag)?;
+                    }
+                    other => {
+                        worksheet.write_string(row_number, col as u16, cell_text(other))?;
+                    }
+                }
+            }
+        }
+        
+        worksheet.autofit();
+    }
+    
+    Ok(workbook.save_to_buffer()?)
+}
+
+/// Lays sheets out as plain text tables on landscape A4 pages. Columns that
+/// do not fit across the page are dropped from the right, and long values
+/// are cut short; the spreadsheet formats carry the full data.
+pub fn to_pdf(title: &str, sheets: &[Sheet]) -> Result<Vec<u8>, ExportError> {
+    let (document, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Report");
+    let font = document
+        .add_external_font(PDF_FONT)
+        .map_err(|e| ExportError::Pdf(e.to_string()))?;
+    let bold = document
+        .add_external_font(PDF_BOLD_FONT)
+        .map_err(|e| ExportError::Pdf(e.to_string()))?;
+    
+    let max_chars = ((PAGE_WIDTH - 2.0 * PAGE_MARGIN) / PDF_CHAR_WIDTH) as usize;
+    let mut layer = document.get_page(page).get_layer(layer);
+    let mut y = PAGE_HEIGHT - PAGE_MARGIN;
+    
+    layer.use_text(title, PDF_FONT_SIZE * 2.0, Mm(PAGE_MARGIN), Mm(y), &bold);
+    y -= PDF_LINE_HEIGHT * 3.0;
+    
+    for sheet in sheets {
+        let texts: Vec<Vec<String>> = sheet
+            .rows
+            .iter()
+            .map(|row| row.iter().map(cell_text).collect())
+            .collect();
+        
+        let mut widths = Vec::new();
+        let mut line_width = 0;
+        for (col, column) in sheet.columns.iter().enumerate() {
+            let width = texts
+                .iter()
+                .map(|row| row[col].chars().count())
+                .chain(std::iter::once(column.chars().count()))
+                .max()
+                .unwrap_or(0)
+                .min(PDF_MAX_COLUMN_CHARS);
+            if line_width + width > max_chars {
+                break;
+            }
+            line_width += width + 2;
+            widths.push(width);
+        }
+        
+        let format_line = |cells: &[String]| {
+            widths
+                .iter()
+                .zip(cells)
+                .map(|(width, text)| {
+                    let text: String = text.chars().take(*width).collect();
+                    format!("{:<width$}", text, width = width)
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+        let header = format_line(&sheet.columns);
+        
+        let mut lines = vec![(sheet.title.clone(), true), (header.clone(), true)];
+        lines.extend(texts.iter().map(|row| (format_line(row), false)));
+        
+        for (i, (line, is_bold)) in lines.iter().enumerate() {
+            if y < PAGE_MARGIN {
+                let (page, new_layer) = document.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Report");
+                layer = document.get_page(page).get_layer(new_layer);
+                y = PAGE_HEIGHT - PAGE_MARGIN;
+                // Repeat the column headings at the top of each continued page
+                if i > 1 {
+                    layer.use_text(&header, PDF_FONT_SIZE, Mm(PAGE_MARGIN), Mm(y), &bold);
+                    y -= PDF_LINE_HEIGHT;
+                }
+            }
+            
+            layer.use_text(line, PDF_FONT_SIZE, Mm(PAGE_MARGIN), Mm(y), if *is_bold { &bold } else { &font });
+            y -= PDF_LINE_HEIGHT;
+        }
+        
+        y -= PDF_LINE_HEIGHT * 2.0;
+    }
+    
+    document.save_to_bytes().map_err(|e| ExportError::Pdf(e.to_string()))
+}
+
+/// Renders a report's sheets in a file format; JSON is answered by the caller.
+pub fn render(format: &ExportFormat, title: &str, sheets: &[Sheet]) -> Result<Vec<u8>, ExportError> {
+    match format {
+        ExportFormat::Csv => to_csv(sheets),
+        ExportFormat::Xlsx => to_xlsx(sheets),
+        ExportFormat::Pdf => to_pdf(title, sheets),
+        ExportFormat::Json => Err(ExportError::Io("JSON is not a file export".to_string())),
+    }
+}
\ No newline at end of file
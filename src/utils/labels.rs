@@ -0,0 +1,210 @@
+use barcoders::sym::code128::Code128;
+use image::{GrayImage, ImageFormat, Luma};
+use qrcode::{Color, QrCode};
+use std::fmt;
+use std::io::Cursor;
+use thiserror::Error;
+
+/// Pixels per module in PNG output.
+const PNG_MODULE_SIZE: u32 = 4;
+/// Height of a Code128 symbol, in modules.
+const BARCODE_HEIGHT: usize = 40;
+/// Labels per row and label size, in millimetres, on an A4 sheet.
+const SHEET_COLUMNS: usize = 3;
+const LABEL_WIDTH: f64 = 70.0;
+const LABEL_HEIGHT: f64 = 37.0;
+
+#[derive(Debug, Error)]
+pub enum LabelError {
+    #[error("Cannot encode '{0}' as a barcode")]
+    Encoding(String),
+    #[error("Image error: {0}")]
+    Image(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Symbology {
+    Code128,
+    Qr,
+}
+
+impl Symbology {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(symbology: &str) -> Option<Self> {
+        match symbology.to_lowercase().as_str() {
+            "code128" => Some(Symbology::Code128),
+            "qr" => Some(Symbology::Qr),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Symbology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = match self {
+            Symbology::Code128 => "code128",
+            Symbology::Qr => "qr",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+/// A barcode as a grid of dark and light modules, quiet zone included.
+pub struct Symbol {
+    modules: Vec<bool>,
+    width: usize,
+    height: usize,
+}
+
+impl Symbol {
+    pub fn encode(symbology: &Symbology, data: &str) -> Result<Self, LabelError> {
+        match symbology {
+            Symbology::Code128 => {
+                // Character set B covers printable ASCII, which is what SKUs use
+                let bars = Code128::new(format!("\u{0181}{}", data))
+                    .map_err(|_| LabelError::Encoding(data.to_string()))?
+                    .encode();
+                
+                let quiet = 10;
+                let width = bars.len() + quiet * 2;
+                let mut row = vec![false; width];
+                for (i, bar) in bars.iter().enumerate() {
+                    row[quiet + i] = *bar == 1;
+                }
+                
+                Ok(Symbol {
+                    modules: row.repeat(BARCODE_HEIGHT),
+                    width,
+                    height: BARCODE_HEIGHT,
+                })
+            }
+            Symbology::Qr => {
+                let code = QrCode::new(data.as_bytes())
+                    .map_err(|_| LabelError::Encoding(data.to_string()))?;
+                
+                let quiet = 4;
+                let size = code.width();
+                let width = size + quiet * 2;
+                let mut modules = vec![false; width * width];
+                for (i, color) in code.to_colors().into_iter().enumerate() {
+                    modules[(i / size + quiet) * width + i % size + quiet] = color == Color::Dark;
+                }
+                
+                Ok(Symbol {
+                    modules,
+                    width,
+                    height: width,
+                })
+            }
+        }
+    }
+    
+    /// SVG path drawing the dark modules, one unit per module.
+    fn path(&self) -> String {
+        let mut path = String::new();
+        
+        for y in 0..self.height {
+            let mut x = 0;
+            while x < self.width {
+                if self.modules[y * self.width + x] {
+                    let start = x;
+                    while x < self.width && self.modules[y * self.width + x] {
+                        x += 1;
+                    }
+                    path.push_str(&format!("M{} {}h{}v1h-{}z", start, y, x - start, x - start));
+                } else {
+                    x += 1;
+                }
+            }
+        }
+        
+        path
+    }
+    
+    pub fn to_svg(&self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w} {h}\" width=\"{pw}\" height=\"{ph}\" shape-rendering=\"crispEdges\">\
+             <rect width=\"{w}\" height=\"{h}\" fill=\"#fff\"/><path d=\"{path}\" fill=\"#000\"/></svg>",
+            w = self.width,
+            h = self.height,
+            pw = self.width as u32 * PNG_MODULE_SIZE,
+            ph = self.height as u32 * PNG_MODULE_SIZE,
+            path = self.path()
+        )
+    }
+    
+    pub fn to_png(&self) -> Result<Vec<u8>, LabelError> {
+        let image = GrayImage::from_fn(
+            self.width as u32 * PNG_MODULE_SIZE,
+            self.height as u32 * PNG_MODULE_SIZE,
+            |x, y| {
+                let index = (y / PNG_MODULE_SIZE) as usize * self.width + (x / PNG_MODULE_SIZE) as usize;
+                if self.modules[index] { Luma([0]) } else { Luma([255]) }
+            },
+        );
+        
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|e| LabelError::Image(e.to_string()))?;
+        
+        Ok(png)
+    }
+}
+
+/// One label on a sheet: the symbol with a title and the encoded text below it.
+pub struct Label {
+    pub symbol: Symbol,
+    pub title: String,
+    pub caption: String,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Lays labels out in a grid sized for A4 label stock, as one SVG in millimetres.
+pub fn sheet_svg(labels: &[Label]) -> String {
+    let rows = labels.len().div_ceil(SHEET_COLUMNS).max(1);
+    let width = LABEL_WIDTH * SHEET_COLUMNS as f64;
+    let height = LABEL_HEIGHT * rows as f64;
+    
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}mm\" height=\"{h}mm\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"#fff\"/>",
+        w = width,
+        h = height
+    );
+    
+    for (i, label) in labels.iter().enumerate() {
+        let x = (i % SHEET_COLUMNS) as f64 * LABEL_WIDTH;
+        let y = (i / SHEET_COLUMNS) as f64 * LABEL_HEIGHT;
+        
+        svg.push_str(&format!(
+            "<g transform=\"translate({x} {y})\">\
+             <rect x=\"1\" y=\"1\" width=\"{lw}\" height=\"{lh}\" fill=\"none\" stroke=\"#ccc\" stroke-width=\"0.2\"/>\
+             <text x=\"{cx}\" y=\"6\" font-size=\"3.5\" text-anchor=\"middle\">{title}</text>\
+             <svg x=\"4\" y=\"8\" width=\"{sw}\" height=\"22\" viewBox=\"0 0 {vw} {vh}\" preserveAspectRatio=\"xMidYMid meet\" shape-rendering=\"crispEdges\">\
+             <path d=\"{path}\" fill=\"#000\"/></svg>\
+             <text x=\"{cx}\" y=\"34\" font-size=\"3\" text-anchor=\"middle\">{caption}</text>\
+             </g>",
+            x = x,
+            y = y,
+            lw = LABEL_WIDTH - 2.0,
+            lh = LABEL_HEIGHT - 2.0,
+            cx = LABEL_WIDTH / 2.0,
+            sw = LABEL_WIDTH - 8.0,
+            vw = label.symbol.width,
+            vh = label.symbol.height,
+            path = label.symbol.path(),
+            title = escape(&label.title),
+            caption = escape(&label.caption)
+        ));
+    }
+    
+    svg.push_str("</svg>");
+    svg
+}
\ No newline at end of file
@@ -0,0 +1,109 @@
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MessageBuilder, MultiPart, SinglePart};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use thiserror::Error;
+use crate::config::Config;
+
+#[derive(Debug, Error)]
+pub enum MailError {
+    #[error("Invalid address: {0}")]
+    Address(String),
+    #[error("Could not build message: {0}")]
+    Message(String),
+    #[error("SMTP error: {0}")]
+    Smtp(String),
+}
+
+/// Sends plain-text mail, optionally with an attachment, through the
+/// configured SMTP relay. The relay is expected to be local, so no TLS or
+/// authentication is used.
+#[derive(Clone)]
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl Mailer {
+    /// Returns `None` when no SMTP host is configured.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, MailError> {
+        let host = match &config.smtp_host {
+            Some(host) => host,
+            None => return Ok(None),
+        };
+        
+        let from = config
+            .smtp_from
+            .parse::<Mailbox>()
+            .map_err(|_| MailError::Address(config.smtp_from.clone()))?;
+        
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+            .port(config.smtp_port)
+            .build();
+        
+        Ok(Some(Mailer { transport, from }))
+    }
+    
+    pub async fn send(&self, to: &[String], subject: &str, body: String) -> Result<(), MailError> {
+        if to.is_empty() {
+            return Ok(());
+        }
+        
+        let message = self
+            .builder(to, subject)?
+            .body(body)
+            .map_err(|e| MailError::Message(e.to_string()))?;
+        
+        self.deliver(message).await
+    }
+    
+    /// Sends a plain-text message with one file attached.
+    pub async fn send_with_attachment(
+        &self,
+        to: &[String],
+        subject: &str,
+        body: String,
+        filename: String,
+        content_type: &str,
+        data: Vec<u8>,
+    ) -> Result<(), MailError> {
+        if to.is_empty() {
+            return Ok(());
+        }
+        
+        let content_type = ContentType::parse(content_type)
+            .map_err(|e| MailError::Message(e.to_string()))?;
+        
+        let message = self
+            .builder(to, subject)?
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(body))
+                    .singlepart(Attachment::new(filename).body(data, content_type)),
+            )
+            .map_err(|e| MailError::Message(e.to_string()))?;
+        
+        self.deliver(message).await
+    }
+    
+    fn builder(&self, to: &[String], subject: &str) -> Result<MessageBuilder, MailError> {
+        let mut builder = Message::builder().from(self.from.clone()).subject(subject);
+        
+        for address in to {
+            let mailbox = address
+                .parse::<Mailbox>()
+                .map_err(|_| MailError::Address(address.clone()))?;
+            builder = builder.to(mailbox);
+        }
+        
+        Ok(builder)
+    }
+    
+    async fn deliver(&self, message: Message) -> Result<(), MailError> {
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| MailError::Smtp(e.to_string()))?;
+        
+        Ok(())
+    }
+}
\ No newline at end of file
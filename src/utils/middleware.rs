@@ -1,14 +1,27 @@
 use actix_web::{
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
     error::ErrorUnauthorized,
-    Error, HttpMessage,
+    web, Error, HttpMessage, HttpRequest,
 };
 use futures::future::{ready, LocalBoxFuture, Ready};
+use serde::Deserialize;
 use std::rc::Rc;
 
 use crate::auth::jwt::{validate_token, Claims};
 use crate::models::user::UserRole;
 
+/// Returns the id of the user whose token authenticated this request.
+pub fn authenticated_user_id(req: &HttpRequest) -> Option<i64> {
+    req.extensions()
+        .get::<Claims>()
+        .and_then(|claims| claims.sub.parse::<i64>().ok())
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    access_token: Option<String>,
+}
+
 pub struct Authentication {
     jwt_secret: String,
 }
@@ -70,13 +83,31 @@ where
         let jwt_secret = self.jwt_secret.clone();
         let service = Rc::clone(&self.service);
 
+        // Browsers cannot set headers on an EventSource, so the event stream
+        // also takes the token as an `access_token` query parameter
+        let query_token = if req.path() == "/api/events" {
+            web::Query::<TokenQuery>::from_query(req.query_string())
+                .ok()
+                .and_then(|query| query.into_inner().access_token)
+        } else {
+            None
+        };
+
         Box::pin(async move {
+            if let Some(token) = query_token {
+                return match validate_token(&token, jwt_secret.as_bytes()) {
+                    Ok(claims) => {
+                        req.extensions_mut().insert(claims);
+                        service.call(req).await
+                    }
+                    Err(_) => Err(ErrorUnauthorized("Invalid token")),
+                };
+            }
+            
             if let Some(auth_value) = auth_header {
                 let auth_str = auth_value.to_str().map_err(|_| ErrorUnauthorized("Invalid authorization header"))?;
                 
-                if auth_str.starts_with("Bearer ") {
-                    let token = &auth_str[7..]; // Remove "Bearer " prefix
-                    
+                if let Some(token) = auth_str.strip_prefix("Bearer ") {
                     match validate_token(token, jwt_secret.as_bytes()) {
                         Ok(claims) => {
                             // Add claims to request extensions
@@ -1 +1,7 @@
-pub mod middleware;
\ No newline at end of file
+pub mod middleware;
+pub mod labels;
+pub mod webhooks;
+pub mod mail;
+pub mod alerts;
+pub mod export;
+pub mod scheduler;
\ No newline at end of file
@@ -0,0 +1,142 @@
+use chrono::Utc;
+use log::{error, info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::db::{run_blocking, DbPool, DbResult};
+use crate::models::report_request::{BuiltReport, ReportRequest};
+use crate::models::report_schedule::{NewReportRun, ReportSchedule, RunStatus};
+use crate::utils::export::ExportFormat;
+use crate::utils::mail::Mailer;
+
+/// How often schedules are checked for due runs.
+const POLL_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Runs due report schedules until the server shuts down: builds each report,
+/// writes it to the archive and emails it to the schedule's recipients.
+pub async fn run_scheduler(pool: DbPool, archive_dir: PathBuf, mailer: Option<Mailer>) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    
+    loop {
+        interval.tick().await;
+        
+        if let Err(e) = run_due(&pool, &archive_dir, mailer.as_ref()).await {
+            error!("Report scheduler failed: {}", e);
+        }
+    }
+}
+
+async fn run_due(pool: &DbPool, archive_dir: &Path, mailer: Option<&Mailer>) -> DbResult<()> {
+    for schedule in run_blocking(pool, ReportSchedule::due).await? {
+        let started_at = Utc::now();
+        let format = ExportFormat::from_str(&schedule.format).unwrap_or(ExportFormat::Pdf);
+        
+        // Building and rendering a report reads the database and the disk, so
+        // it runs off the async runtime
+        let generated = {
+            let schedule = schedule.clone();
+            let archive_dir = archive_dir.to_path_buf();
+            let format = format.clone();
+            // A report that panics fails its run instead of stopping the
+            // scheduler for every schedule
+            run_blocking(pool, move |pool| {
+                Ok(ReportRequest::parse(&schedule.report_type, &schedule.parameters)
+                    .and_then(|request| request.build(pool))
+                    .map_err(|e| e.to_string())
+                    .and_then(|report| archive(&archive_dir, &schedule, &report, &format).map(|file| (report, file))))
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()))
+        };
+        
+        let run = match generated {
+            Ok((report, (path, data))) => {
+                let (status, error) = match deliver(mailer, &schedule, &report, &format, &path, data).await {
+                    Ok(status) => (status, None),
+                    Err(e) => (RunStatus::Failed, Some(e)),
+                };
+                
+                NewReportRun {
+                    status,
+                    file_size: fs::metadata(&path).ok().map(|metadata| metadata.len() as i64),
+                    file_path: Some(path.to_string_lossy().into_owned()),
+                    error,
+                    started_at,
+                }
+            }
+            Err(e) => NewReportRun {
+                status: RunStatus::Failed,
+                file_path: None,
+                file_size: None,
+                error: Some(e),
+                started_at,
+            },
+        };
+        
+        match &run.error {
+            Some(e) => warn!("Scheduled report '{}' ({}) failed: {}", schedule.name, schedule.id, e),
+            None => info!("Scheduled report '{}' ({}) {}", schedule.name, schedule.id, run.status),
+        }
+        
+        run_blocking(pool, move |pool| schedule.record_run(pool, run)).await?;
+    }
+    
+    Ok(())
+}
+
+/// Renders a report and writes it under `{archive}/{schedule id}/`, returning
+/// the file's path and contents.
+fn archive(
+    archive_dir: &Path,
+    schedule: &ReportSchedule,
+    report: &BuiltReport,
+    format: &ExportFormat,
+) -> Result<(PathBuf, Vec<u8>), String> {
+    let data = report.render(format).map_err(|e| e.to_string())?;
+    
+    let dir = archive_dir.join(schedule.id.to_string());
+    fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    
+    let path = dir.join(format!("{}-{}.{}", report.name, Utc::now().format("%Y%m%dT%H%M%S"), format));
+    fs::write(&path, &data).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    
+    Ok((path, data))
+}
+
+async fn deliver(
+    mailer: Option<&Mailer>,
+    schedule: &ReportSchedule,
+    report: &BuiltReport,
+    format: &ExportFormat,
+    path: &Path,
+    data: Vec<u8>,
+) -> Result<RunStatus, String> {
+    if schedule.recipients.is_empty() {
+        return Ok(RunStatus::Archived);
+    }
+    
+    let mailer = match mailer {
+        Some(mailer) => mailer,
+        None => return Err("SMTP_HOST is not set; the report was archived but not emailed".to_string()),
+    };
+    
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| report.filename(format));
+    let subject = format!("{}: {}", schedule.name, report.title);
+    let body = format!(
+        "{} generated at {} for the '{}' schedule is attached as {}.\n",
+        report.title,
+        Utc::now().format("%Y-%m-%d %H:%M UTC"),
+        schedule.name,
+        filename
+    );
+    
+    mailer
+        .send_with_attachment(&schedule.recipients, &subject, body, filename, format.content_type(), data)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    Ok(RunStatus::Delivered)
+}
\ No newline at end of file
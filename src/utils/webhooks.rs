@@ -0,0 +1,82 @@
+use hmac::{Hmac, Mac};
+use log::{error, warn};
+use serde_json::json;
+use sha2::Sha256;
+use std::time::{Duration, Instant};
+use crate::db::{run_blocking, DbPool, DbResult};
+use crate::models::webhook::OutboxEntry;
+
+/// How often the outbox is checked for due deliveries.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const BATCH_SIZE: i64 = 50;
+
+/// Hex HMAC-SHA256 of the body, sent as `X-Webhook-Signature: sha256=<hex>`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Delivers queued webhook events until the server shuts down.
+pub async fn run_dispatcher(pool: DbPool) {
+    let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Webhook dispatcher could not start: {}", e);
+            return;
+        }
+    };
+    
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    
+    loop {
+        interval.tick().await;
+        
+        if let Err(e) = dispatch_due(&pool, &client).await {
+            error!("Webhook dispatch failed: {}", e);
+        }
+    }
+}
+
+async fn dispatch_due(pool: &DbPool, client: &reqwest::Client) -> DbResult<()> {
+    for entry in run_blocking(pool, |pool| OutboxEntry::due(pool, BATCH_SIZE)).await? {
+        let body = json!({
+            "id": entry.event.id,
+            "event": entry.event.event_type,
+            "created_at": entry.event.created_at,
+            "data": entry.event.payload,
+        })
+        .to_string();
+        
+        let started = Instant::now();
+        let result = client
+            .post(&entry.url)
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Event", entry.event.event_type.to_string())
+            .header("X-Webhook-Delivery", entry.id.to_string())
+            .header("X-Webhook-Signature", sign(&entry.secret, body.as_bytes()))
+            .body(body)
+            .send()
+            .await;
+        let duration_ms = started.elapsed().as_millis() as i64;
+        
+        let (status_code, error) = match result {
+            Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+            Ok(response) => (
+                Some(response.status().as_u16()),
+                Some(format!("Endpoint responded with {}", response.status())),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        
+        if let Some(error) = &error {
+            warn!("Webhook {} delivery {} failed: {}", entry.webhook_id, entry.id, error);
+        }
+        
+        run_blocking(pool, move |pool| entry.record_attempt(pool, status_code, error, duration_ms)).await?;
+    }
+    
+    Ok(())
+}
\ No newline at end of file